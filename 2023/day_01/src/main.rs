@@ -1,97 +1,236 @@
-use std::u32;
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
 
-use lazy_static::lazy_static;
-use library::{get_filename_arg, get_lines};
-use regex::Regex;
+use library::{get_filename_and_options, get_lines};
 
 fn main() {
-    let file_name = get_filename_arg();
-
+    // --vocabulary=a.txt,b.txt adds the word lists in those files to the English ones recognised in part 2
+    let (file_name, options) = get_filename_and_options(&["vocabulary"]);
     let lines = get_lines(&file_name);
+
+    let part_one_scanner = Scanner::new(&digits());
+    let part_two_scanner = Scanner::new(&part_two_words(options.value("vocabulary")));
+
     let mut part_one_sum = 0;
     let mut part_two_sum = 0;
-
     for line in lines {
-        part_one_sum += get_number(line.clone());
-        let digits = get_digits(line);
-        part_two_sum += get_line_number(digits);
+        part_one_sum += get_calibration(&line, &part_one_scanner);
+        part_two_sum += get_calibration(&line, &part_two_scanner);
     }
 
     println!("Part 1: {}", part_one_sum);
     println!("Part 2: {}", part_two_sum);
 }
 
-fn get_number(line: String) -> u32 {
-    lazy_static! {
-        static ref REGEX: Regex = Regex::new(r"\d").unwrap();
-    }
-    let numbers: Vec<&str> = REGEX.find_iter(line.as_str()).map(|m| m.as_str()).collect();
+fn digits() -> Vec<(String, u32)> {
+    (0..10).map(|digit| (digit.to_string(), digit)).collect()
+}
 
-    let mut calibration = String::new();
-    calibration.push_str(
-        numbers
-            .first()
-            .expect("Failed to get first matching string"),
-    );
-    calibration.push_str(numbers.last().expect("Failed to get last matching string"));
+fn english_words() -> Vec<(String, u32)> {
+    [
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    ]
+    .iter()
+    .zip(1..)
+    .map(|(word, value)| (String::from(*word), value))
+    .collect()
+}
 
-    calibration
-        .parse::<u32>()
-        .expect("Failed to parse string to number")
+fn part_two_words(vocabulary_files: Option<&str>) -> Vec<(String, u32)> {
+    let mut words = digits();
+    words.extend(english_words());
+    for vocabulary_file in vocabulary_files
+        .into_iter()
+        .flat_map(|files| files.split(','))
+    {
+        words.extend(load_vocabulary(vocabulary_file));
+    }
+    words
 }
 
-fn get_digits(line: String) -> Vec<u32> {
-    let line_formatted = line
-        .replace("one", "one1one")
-        .replace("two", "two2two")
-        .replace("three", "three3three")
-        .replace("four", "four4four")
-        .replace("five", "five5five")
-        .replace("six", "six6six")
-        .replace("seven", "seven7seven")
-        .replace("eight", "eight8eight")
-        .replace("nine", "nine9nine");
-
-    line_formatted
-        .chars()
-        .filter_map(|character| character.to_digit(10))
+// One "word value" pair per line
+fn load_vocabulary(file_path: &str) -> Vec<(String, u32)> {
+    get_lines(file_path)
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (word, value) = line
+                .trim()
+                .split_once(' ')
+                .unwrap_or_else(|| panic!("Invalid vocabulary line {}", line));
+            let value = value
+                .trim()
+                .parse()
+                .expect("Failed to parse vocabulary value into number");
+            (String::from(word), value)
+        })
         .collect()
 }
 
-fn get_line_number(line_digits: Vec<u32>) -> u32 {
-    let mut num_string = line_digits.first().expect("Empty line").to_string();
-    num_string.push_str(line_digits.last().expect("Empty line").to_string().as_str());
+fn get_calibration(line: &str, scanner: &Scanner) -> u32 {
+    let (first, last) = scanner
+        .first_and_last(line)
+        .unwrap_or_else(|| panic!("No digits found in line {}", line));
+
+    first.value * 10 + last.value
+}
+
+// A word from the vocabulary found in a line, covering the bytes start..end
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Match {
+    start: usize,
+    end: usize,
+    value: u32,
+}
+
+/* Aho-Corasick automaton over the words in the vocabulary. It is a trie of the words where every node also has a
+* failure link to the node for the longest proper suffix of it that is still in the trie, so a line is scanned in a
+* single pass and overlapping words such as "eightwo" are all found.
+*/
+struct Scanner {
+    children: Vec<HashMap<u8, usize>>,
+    failures: Vec<usize>,
+    // Lengths and values of the words ending at each node, including those reached through failure links
+    outputs: Vec<Vec<(usize, u32)>>,
+}
+
+impl Scanner {
+    fn new(words: &[(String, u32)]) -> Self {
+        let mut children: Vec<HashMap<u8, usize>> = vec![HashMap::new()];
+        let mut outputs: Vec<Vec<(usize, u32)>> = vec![vec![]];
+
+        for (word, value) in words {
+            if word.is_empty() {
+                panic!("Vocabulary words can not be empty");
+            }
+            let mut node = 0;
+            for &byte in word.as_bytes() {
+                node = match children[node].get(&byte) {
+                    Some(&child) => child,
+                    None => {
+                        children.push(HashMap::new());
+                        outputs.push(vec![]);
+                        let child = children.len() - 1;
+                        children[node].insert(byte, child);
+                        child
+                    }
+                };
+            }
+            outputs[node].push((word.len(), *value));
+        }
+
+        // Breadth first, so the failure link of a node's parent is always set before the node's own
+        let mut failures = vec![0; children.len()];
+        let mut queue: VecDeque<usize> = children[0].values().copied().collect();
+        while let Some(node) = queue.pop_front() {
+            let edges: Vec<(u8, usize)> = children[node].iter().map(|(&b, &c)| (b, c)).collect();
+            for (byte, child) in edges {
+                let mut failure = failures[node];
+                let child_failure = loop {
+                    if let Some(&next) = children[failure].get(&byte) {
+                        break next;
+                    }
+                    if failure == 0 {
+                        break 0;
+                    }
+                    failure = failures[failure];
+                };
+                failures[child] = child_failure;
+                let inherited = outputs[child_failure].clone();
+                outputs[child].extend(inherited);
+                queue.push_back(child);
+            }
+        }
+
+        Self {
+            children,
+            failures,
+            outputs,
+        }
+    }
+
+    fn matches<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Match> + 'a {
+        let mut node = 0;
+        line.bytes().enumerate().flat_map(move |(i, byte)| {
+            node = loop {
+                if let Some(&next) = self.children[node].get(&byte) {
+                    break next;
+                }
+                if node == 0 {
+                    break 0;
+                }
+                node = self.failures[node];
+            };
+            self.outputs[node]
+                .iter()
+                .map(move |&(length, value)| Match {
+                    start: i + 1 - length,
+                    end: i + 1,
+                    value,
+                })
+        })
+    }
 
-    num_string
-        .parse::<u32>()
-        .expect("Failed to parse string to number")
+    // The matches starting furthest left and furthest right, preferring longer words when they start together
+    fn first_and_last(&self, line: &str) -> Option<(Match, Match)> {
+        self.matches(line).fold(None, |found, current| {
+            let Some((first, last)) = found else {
+                return Some((current, current));
+            };
+            let first = if (current.start, Reverse(current.end)) < (first.start, Reverse(first.end))
+            {
+                current
+            } else {
+                first
+            };
+            let last = if (current.start, current.end) > (last.start, last.end) {
+                current
+            } else {
+                last
+            };
+            Some((first, last))
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn get_word_scanner() -> Scanner {
+        let mut words = digits();
+        words.extend(english_words());
+        Scanner::new(&words)
+    }
+
+    fn get_values(line: &str) -> Vec<u32> {
+        get_word_scanner()
+            .matches(line)
+            .map(|found| found.value)
+            .collect()
+    }
+
     #[test]
-    fn test_get_number() {
-        let number = get_number(String::from("1abc2"));
-        assert_eq!(number, 12);
+    fn test_get_calibration() {
+        let scanner = Scanner::new(&digits());
+        assert_eq!(get_calibration("1abc2", &scanner), 12);
     }
 
     #[test]
-    fn test_get_number_multiple_digits() {
-        let number = get_number(String::from("a1b2c3d4e55f"));
-        assert_eq!(number, 15);
+    fn test_get_calibration_multiple_digits() {
+        let scanner = Scanner::new(&digits());
+        assert_eq!(get_calibration("a1b2c3d4e55f", &scanner), 15);
     }
 
     #[test]
-    fn test_get_number_single_digit() {
-        let number = get_number(String::from("treb7uchet"));
-        assert_eq!(number, 77);
+    fn test_get_calibration_single_digit() {
+        let scanner = Scanner::new(&digits());
+        assert_eq!(get_calibration("treb7uchet", &scanner), 77);
     }
 
     #[test]
-    fn test_get_digits() {
-        let digits = get_digits(String::from("two1nine"));
+    fn test_matches() {
+        let digits = get_values("two1nine");
         assert_eq!(digits.len(), 3);
         assert_eq!(digits[0], 2);
         assert_eq!(digits[1], 1);
@@ -99,28 +238,76 @@ mod tests {
     }
 
     #[test]
-    fn test_get_digits_overlapping() {
-        let digits = get_digits(String::from("sevenine"));
+    fn test_matches_overlapping() {
+        let digits = get_values("sevenine");
         assert_eq!(digits.len(), 2);
         assert_eq!(digits[0], 7);
         assert_eq!(digits[1], 9);
+        assert_eq!(get_values("eightwothree"), vec![8, 2, 3]);
+    }
+
+    #[test]
+    fn test_get_calibration_words() {
+        let scanner = get_word_scanner();
+        assert_eq!(get_calibration("two1nine", &scanner), 29);
+        assert_eq!(get_calibration("sevenine", &scanner), 79);
+        assert_eq!(get_calibration("four", &scanner), 44);
     }
 
     #[test]
-    fn test_get_line_number() {
-        let number = get_line_number(get_digits(String::from("two1nine")));
-        assert_eq!(number, 29);
+    fn test_first_and_last_positions() {
+        let scanner = get_word_scanner();
+
+        assert_eq!(
+            scanner.first_and_last("xtwone3fourx"),
+            Some((
+                Match {
+                    start: 1,
+                    end: 4,
+                    value: 2
+                },
+                Match {
+                    start: 7,
+                    end: 11,
+                    value: 4
+                }
+            ))
+        );
+        assert_eq!(scanner.first_and_last("abc"), None);
     }
 
     #[test]
-    fn test_get_line_number_overlapping() {
-        let number = get_line_number(get_digits(String::from("sevenine")));
-        assert_eq!(number, 79);
+    fn test_words_ending_inside_longer_words() {
+        // "ab" ends before "xaby" does, but "xaby" starts first
+        let scanner = Scanner::new(&[(String::from("xaby"), 1), (String::from("ab"), 2)]);
+        let (first, last) = scanner.first_and_last("xaby").unwrap();
+
+        assert_eq!(first.value, 1);
+        assert_eq!(last.value, 2);
     }
 
     #[test]
-    fn test_get_line_number_one_digit() {
-        let number = get_line_number(get_digits(String::from("four")));
-        assert_eq!(number, 44);
+    fn test_load_vocabulary() {
+        let mut words = digits();
+        words.extend(load_vocabulary("resources/test_vocabulary.txt"));
+        let scanner = Scanner::new(&words);
+
+        assert_eq!(get_calibration("deuxtroisept", &scanner), 27);
+        assert_eq!(get_calibration("zweiachtx", &scanner), 28);
+        // "un" starts after "neun" does, so it is the last word
+        assert_eq!(get_calibration("zweineun", &scanner), 21);
+    }
+
+    #[test]
+    fn test_part_two_words() {
+        let english = Scanner::new(&part_two_words(None));
+        let scanner = Scanner::new(&part_two_words(Some("resources/test_vocabulary.txt")));
+
+        assert_eq!(get_calibration("two1deux", &english), 21);
+        assert_eq!(get_calibration("two1deux", &scanner), 22);
+        // 10 digits, 9 English words and the 18 from each list
+        assert_eq!(part_two_words(None).len(), 19);
+        let files = "resources/test_vocabulary.txt,resources/test_vocabulary.txt";
+        assert_eq!(part_two_words(Some(files)).len(), 19 + 2 * 18);
     }
 }
@@ -1,194 +1,352 @@
-use lazy_static::lazy_static;
-use library::{get_filename_arg, get_lines};
-use regex::Regex;
-
-const MAX_RED: u32 = 12;
-const MAX_GREEN: u32 = 13;
-const MAX_BLUE: u32 = 14;
-
-#[derive(Debug)]
-struct Counts {
-    red: u32,
-    green: u32,
-    blue: u32,
-}
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::read_to_string;
+use std::path::Path;
+
+use library::{get_filename_and_options, get_lines};
+
+// The bag the elf asks about
+const DEFAULT_BAG: &str = "12 red, 13 green, 14 blue";
 
 fn main() {
-    let file_name = get_filename_arg();
-    let lines = get_lines(&file_name);
+    /* --bag=<bag> asks about another bag, either a file listing the cubes or text in the same form as DEFAULT_BAG
+     * --statistics prints how many cubes of each colour were shown across all the games
+     */
+    let (file_name, options) = get_filename_and_options(&["bag", "statistics"]);
+    let bag = load_bag(options.value("bag").unwrap_or(DEFAULT_BAG))
+        .unwrap_or_else(|error| panic!("{}", error));
+    let games: Vec<Game> = get_lines(&file_name)
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Game::parse(line).unwrap_or_else(|error| panic!("{}", error)))
+        .collect();
 
-    let mut part_one_sum: u32 = 0;
-    let mut part_two_sum: u32 = 0;
-    for line in lines {
-        part_one_sum += get_id_value(line.clone());
-        part_two_sum += get_power(line);
+    println!("Part one: {}", part_one(&games, &bag));
+    println!("Part two: {}", part_two(&games, &bag));
+    if options.is_set("statistics") {
+        for (color, statistics) in get_color_statistics(&games) {
+            println!("{}: {}", color, statistics);
+        }
     }
-    println!("Part one: {}", part_one_sum);
-    println!("Part two: {}", part_two_sum);
 }
 
-fn get_number(numeric_string: &str) -> u32 {
-    lazy_static! {
-        static ref REGEX: Regex = Regex::new(r"\d{1,3}").unwrap();
-    }
-    let first_match = REGEX
-        .find(&numeric_string)
-        .map(|x| x.as_str())
-        .expect("Failed to find game number");
+fn part_one(games: &[Game], bag: &Bag) -> u32 {
+    games
+        .iter()
+        .filter(|game| game.is_possible(bag))
+        .map(|game| game.id)
+        .sum()
+}
 
-    first_match
-        .parse::<u32>()
-        .expect("Failed to parse game number")
+fn part_two(games: &[Game], bag: &Bag) -> u64 {
+    games.iter().map(|game| game.power(bag)).sum()
 }
 
-fn get_color_counts(counts_string: &str) -> Counts {
-    lazy_static! {
-        static ref REGEX: Regex = Regex::new(r"\d{1,2} (red|green|blue)").unwrap();
+// Either the path of a file with the cubes in it, one or more to a line, or the cubes themselves
+fn load_bag(bag: &str) -> Result<Bag, ParseError> {
+    if !Path::new(bag).is_file() {
+        return Cubes::parse(bag);
     }
 
-    let mut counts = Counts {
-        red: 0,
-        green: 0,
-        blue: 0,
-    };
-    let count_matches: Vec<&str> = REGEX
-        .find_iter(&counts_string)
-        .map(|m| m.as_str())
+    let contents = read_to_string(bag).expect("Failed to read bag file");
+    let lines: Vec<&str> = contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
         .collect();
+    Cubes::parse(&lines.join(", "))
+}
 
-    for count_match in count_matches {
-        let count = get_number(count_match);
+#[derive(PartialEq, Eq, Debug)]
+enum ParseError {
+    MissingId(String),
+    InvalidCubes(String),
+    RepeatedColor(String),
+}
 
-        if count_match.contains("red") {
-            counts.red = count;
-        } else if count_match.contains("green") {
-            counts.green = count;
-        } else if count_match.contains("blue") {
-            counts.blue = count;
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingId(line) => write!(f, "\"{}\" does not start with \"Game <id>:\"", line),
+            Self::InvalidCubes(cubes) => {
+                write!(f, "\"{}\" is not a number of cubes and a colour", cubes)
+            }
+            Self::RepeatedColor(color) => write!(f, "The colour {} is listed twice", color),
         }
     }
+}
 
-    counts
+// How many cubes there are of each colour, e.g. "3 blue, 4 red". There are none of any colour not listed
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+struct Cubes {
+    counts: BTreeMap<String, u32>,
 }
 
-fn game_is_valid(game: &str) -> bool {
-    let substrings = game.split(";").collect::<Vec<&str>>();
+// A handful of cubes the elf shows
+type Draw = Cubes;
+type Bag = Cubes;
 
-    for substring in substrings {
-        let counts = get_color_counts(substring);
-        if counts.red > MAX_RED || counts.green > MAX_GREEN || counts.blue > MAX_BLUE {
-            return false;
+impl Cubes {
+    fn parse(cubes: &str) -> Result<Self, ParseError> {
+        let mut counts = BTreeMap::new();
+        for entry in cubes.split(',').map(|entry| entry.trim()) {
+            let invalid = || ParseError::InvalidCubes(String::from(entry));
+            let (count, color) = entry.split_once(' ').ok_or_else(invalid)?;
+            let count = count.parse().map_err(|_| invalid())?;
+            if color.is_empty() || color.contains(char::is_whitespace) {
+                return Err(invalid());
+            }
+            if counts.insert(String::from(color), count).is_some() {
+                return Err(ParseError::RepeatedColor(String::from(color)));
+            }
         }
+
+        Ok(Self { counts })
+    }
+
+    fn get(&self, color: &str) -> u32 {
+        self.counts.get(color).copied().unwrap_or(0)
+    }
+
+    fn fits_in(&self, bag: &Bag) -> bool {
+        self.counts
+            .iter()
+            .all(|(color, &count)| count <= bag.get(color))
     }
+}
 
-    true
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Game {
+    id: u32,
+    draws: Vec<Draw>,
 }
 
-fn get_id_value(line: String) -> u32 {
-    let substrings = line.split(":").collect::<Vec<&str>>();
-    if game_is_valid(substrings[1]) {
-        get_number(substrings[0])
-    } else {
-        0
+impl Game {
+    // e.g. "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green"
+    fn parse(line: &str) -> Result<Self, ParseError> {
+        let (header, draws) = line
+            .split_once(':')
+            .ok_or_else(|| ParseError::MissingId(String::from(line)))?;
+        let id = header
+            .strip_prefix("Game ")
+            .and_then(|id| id.trim().parse().ok())
+            .ok_or_else(|| ParseError::MissingId(String::from(line)))?;
+        let draws = draws
+            .split(';')
+            .map(Cubes::parse)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { id, draws })
+    }
+
+    // The fewest cubes of each colour the bag could have held for this game to be possible
+    fn minimum_bag(&self) -> Bag {
+        let mut bag = Bag::default();
+        for draw in &self.draws {
+            for (color, &count) in &draw.counts {
+                let most = bag.counts.entry(color.clone()).or_insert(0);
+                *most = (*most).max(count);
+            }
+        }
+        bag
+    }
+
+    fn is_possible(&self, bag: &Bag) -> bool {
+        self.draws.iter().all(|draw| draw.fits_in(bag))
     }
+
+    // The minimum number of cubes of each of the colours in the bag multiplied together
+    fn power(&self, bag: &Bag) -> u64 {
+        let minimum_bag = self.minimum_bag();
+        bag.counts
+            .keys()
+            .map(|color| minimum_bag.get(color) as u64)
+            .product()
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+struct ColorStatistics {
+    total: u64,
+    most: u32,
+    // Number of draws the colour was shown in
+    draws: usize,
 }
 
-fn get_power(line: String) -> u32 {
-    let substrings = line.split(";").collect::<Vec<&str>>();
-    let mut counts = vec![];
-    for substring in substrings {
-        counts.push(get_color_counts(substring));
+impl fmt::Display for ColorStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mean = if self.draws == 0 {
+            0.0
+        } else {
+            self.total as f64 / self.draws as f64
+        };
+        write!(
+            f,
+            "{} cubes over {} draws, {:.2} on average and at most {}",
+            self.total, self.draws, mean, self.most
+        )
     }
-    let red = counts
-        .iter()
-        .map(|count| count.red)
-        .max()
-        .expect("Failed to get max red value");
-    let green = counts
-        .iter()
-        .map(|count| count.green)
-        .max()
-        .expect("Failed to get max green value");
-    let blue = counts
-        .iter()
-        .map(|count| count.blue)
-        .max()
-        .expect("Failed to get max blue value");
+}
 
-    red * green * blue
+fn get_color_statistics(games: &[Game]) -> BTreeMap<String, ColorStatistics> {
+    let mut statistics: BTreeMap<String, ColorStatistics> = BTreeMap::new();
+    for draw in games.iter().flat_map(|game| &game.draws) {
+        for (color, &count) in &draw.counts {
+            let color_statistics = statistics.entry(color.clone()).or_default();
+            color_statistics.total += count as u64;
+            color_statistics.most = color_statistics.most.max(count);
+            color_statistics.draws += 1;
+        }
+    }
+    statistics
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn get_default_bag() -> Bag {
+        Cubes::parse(DEFAULT_BAG).unwrap()
+    }
+
+    fn get_test_games() -> Vec<Game> {
+        get_lines("resources/test_input.txt")
+            .iter()
+            .map(|line| Game::parse(line).unwrap())
+            .collect()
+    }
+
     #[test]
-    fn test_get_game_number() {
-        let game_number = get_number("Game 1");
-        assert_eq!(game_number, 1);
+    fn test_parts() {
+        let games = get_test_games();
+        let bag = get_default_bag();
+
+        assert_eq!(part_one(&games, &bag), 8);
+        assert_eq!(part_two(&games, &bag), 2286);
     }
 
     #[test]
-    fn test_get_game_number_three_digits() {
-        let game_number = get_number("100 blue");
-        assert_eq!(game_number, 100);
+    fn test_parse_game() {
+        let game = Game::parse("Game 100: 3 blue, 4 red; 2 green").unwrap();
+
+        assert_eq!(game.id, 100);
+        assert_eq!(game.draws.len(), 2);
+        assert_eq!(game.draws[1].get("green"), 2);
     }
 
     #[test]
     fn test_get_color_counts() {
-        let color_counts = get_color_counts("1 red, 2 green, 6 blue");
-        assert_eq!(color_counts.red, 1);
-        assert_eq!(color_counts.green, 2);
-        assert_eq!(color_counts.blue, 6);
+        let draw = Cubes::parse("1 red, 2 green, 6 blue").unwrap();
+        assert_eq!(draw.get("red"), 1);
+        assert_eq!(draw.get("green"), 2);
+        assert_eq!(draw.get("blue"), 6);
     }
 
     #[test]
     fn test_get_color_counts_partial() {
-        let color_counts = get_color_counts("1 red, 2 green");
-        assert_eq!(color_counts.red, 1);
-        assert_eq!(color_counts.green, 2);
-        assert_eq!(color_counts.blue, 0);
+        let draw = Cubes::parse("1 red, 2 green").unwrap();
+        assert_eq!(draw.get("red"), 1);
+        assert_eq!(draw.get("green"), 2);
+        assert_eq!(draw.get("blue"), 0);
+    }
+
+    #[test]
+    fn test_get_color_counts_any_size_or_color() {
+        let draw = Cubes::parse("100 blue, 7 purple").unwrap();
+        assert_eq!(draw.get("blue"), 100);
+        assert_eq!(draw.get("purple"), 7);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Game::parse("3 blue, 4 red"),
+            Err(ParseError::MissingId(String::from("3 blue, 4 red")))
+        );
+        assert_eq!(
+            Game::parse("Game x: 3 blue"),
+            Err(ParseError::MissingId(String::from("Game x: 3 blue")))
+        );
+        assert_eq!(
+            Game::parse("Game 1: 3 blue; red 4"),
+            Err(ParseError::InvalidCubes(String::from("red 4")))
+        );
+        assert_eq!(
+            Game::parse("Game 1: 3 blue;"),
+            Err(ParseError::InvalidCubes(String::new()))
+        );
+        assert_eq!(
+            Game::parse("Game 1: 3 light blue"),
+            Err(ParseError::InvalidCubes(String::from("3 light blue")))
+        );
+        assert_eq!(
+            Game::parse("Game 1: 3 blue, 4 blue"),
+            Err(ParseError::RepeatedColor(String::from("blue")))
+        );
     }
 
     #[test]
-    fn test_game_is_valid() {
-        assert!(game_is_valid(
+    fn test_game_is_possible() {
+        let bag = get_default_bag();
+        let is_possible = |draws: &str| {
+            Game::parse(&format!("Game 1: {}", draws))
+                .unwrap()
+                .is_possible(&bag)
+        };
+
+        assert!(is_possible(
             "3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green"
         ));
-        assert!(!game_is_valid(
+        assert!(!is_possible(
             "15 blue, 4 red; 1 red, 2 green, 6 blue; 2 green"
         ));
-        assert!(!game_is_valid(
+        assert!(!is_possible(
             "3 blue, 13 red; 1 red, 2 green, 6 blue; 2 green"
         ));
-        assert!(!game_is_valid(
+        assert!(!is_possible(
             "3 blue, 4 red; 1 red, 2 green, 6 blue; 14 green"
         ));
+        // The bag has no purple cubes at all
+        assert!(!is_possible("1 purple"));
     }
 
     #[test]
-    fn test_get_id_value() {
+    fn test_minimum_bag_and_power() {
+        let game =
+            Game::parse("Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red")
+                .unwrap();
+        let minimum_bag = game.minimum_bag();
+
         assert_eq!(
-            get_id_value(String::from(
-                "Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green"
-            )),
-            5
+            minimum_bag,
+            Cubes::parse("20 red, 13 green, 6 blue").unwrap()
         );
+        assert!(game.is_possible(&minimum_bag));
+        assert!(!game.is_possible(&get_default_bag()));
+        assert_eq!(game.power(&get_default_bag()), 1560);
+    }
+
+    #[test]
+    fn test_load_bag() {
+        assert_eq!(load_bag(DEFAULT_BAG), Ok(get_default_bag()));
         assert_eq!(
-            get_id_value(String::from(
-                "Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red"
-            )),
-            0
+            load_bag("resources/test_bag.txt"),
+            Ok(Cubes::parse("2 red, 100 yellow").unwrap())
         );
     }
 
     #[test]
-    fn test_get_power() {
+    fn test_color_statistics() {
+        let statistics = get_color_statistics(&get_test_games());
+
         assert_eq!(
-            get_power(String::from(
-                "Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red"
-            )),
-            1560
+            statistics["red"],
+            ColorStatistics {
+                total: 61,
+                most: 20,
+                draws: 11
+            }
         );
+        assert_eq!(statistics.len(), 3);
     }
 }
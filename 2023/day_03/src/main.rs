@@ -1,406 +1,273 @@
-use lazy_static::lazy_static;
+use library::grid::Grid;
 use library::{get_filename_arg, get_two_dimensional_vector};
-use regex::Regex;
+
+const GEAR: char = '*';
+const GEAR_NEIGHBOURS: usize = 2;
 
 fn main() {
     let file_name = get_filename_arg();
     let input_vec = get_two_dimensional_vector(&file_name);
+    let schematic = Schematic::new(&input_vec);
 
-    let part_one_sum = part_one_sum(&input_vec);
-    println!("Part 1: {}", part_one_sum);
-
-    let part_two_sum = part_two_sum(&input_vec);
-    println!("Part 2: {}", part_two_sum);
+    println!("Part 1: {}", schematic.part_number_sum());
+    println!("Part 2: {}", schematic.gear_ratio_sum(GEAR));
 }
 
 fn is_symbol(character: char) -> bool {
     !character.is_ascii_digit() && character != '.'
 }
 
-/* isValidPosition and getAdjacentElements based on example from from https://www.geeksforgeeks.org/find-all-adjacent-elements-of-given-element-in-a-2d-array-or-matrix/ */
-fn is_valid_position(y: i32, x: i32, y_length: i32, x_length: i32) -> bool {
-    y >= 0 && x >= 0 && y < y_length && x < x_length
-}
-
-fn get_adjacent_elements(characters: &Vec<Vec<char>>, y: i32, x: i32) -> Vec<char> {
-    let mut adjacent: Vec<char> = vec![];
-    let y_length = characters.len() as i32;
-    let x_length = characters.first().expect("empty 2d array").len() as i32;
-
-    if is_valid_position(y - 1, x - 1, y_length, x_length) {
-        adjacent.push(characters[y as usize - 1][x as usize - 1]);
-    }
-    if is_valid_position(y, x - 1, y_length, x_length) {
-        adjacent.push(characters[y as usize][x as usize - 1]);
-    }
-    if is_valid_position(y, x + 1, y_length, x_length) {
-        adjacent.push(characters[y as usize][x as usize + 1]);
-    }
-    if is_valid_position(y + 1, x, y_length, x_length) {
-        adjacent.push(characters[y as usize + 1][x as usize]);
-    }
-    if is_valid_position(y + 1, x + 1, y_length, x_length) {
-        adjacent.push(characters[y as usize + 1][x as usize + 1]);
-    }
-    if is_valid_position(y + 1, x - 1, y_length, x_length) {
-        adjacent.push(characters[y as usize + 1][x as usize - 1]);
-    }
-    if is_valid_position(y - 1, x + 1, y_length, x_length) {
-        adjacent.push(characters[y as usize - 1][x as usize + 1]);
-    }
-    adjacent
-}
-
-fn is_part_number_digit(input_vec: &Vec<Vec<char>>, y: i32, x: i32) -> bool {
-    let adjacent_elements = get_adjacent_elements(input_vec, y, x);
-
-    for character in adjacent_elements {
-        if is_symbol(character) {
-            return true;
-        }
-    }
-    false
-}
-
-#[derive(Clone)]
-struct Location {
+// A run of digits within a row, covering the columns start..end
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct NumberSpan {
+    row: usize,
     start: usize,
     end: usize,
+    value: u64,
 }
 
-fn get_number_locations_in_line(line: &Vec<char>) -> Vec<Location> {
-    lazy_static! {
-        static ref REGEX: Regex = Regex::new(r"\d+").unwrap();
-    }
-
-    let line_str: String = line.iter().collect();
-    let mut locations: Vec<Location> = Vec::new();
-
-    for field in REGEX.find_iter(&line_str) {
-        locations.push(Location {
-            start: field.start(),
-            end: field.end(),
-        });
-    }
-
-    locations
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Symbol {
+    row: usize,
+    column: usize,
+    character: char,
 }
 
-fn is_valid_part_number(
-    number_location: &Location,
-    line_number: usize,
-    input_vec: &Vec<Vec<char>>,
-) -> bool {
-    for i in number_location.start..number_location.end {
-        if is_part_number_digit(input_vec, line_number as i32, i as i32) {
-            return true;
-        }
-    }
-
-    false
+/* Every number and symbol in the engine schematic, along with which numbers and symbols are next to each other. The
+* cells of each number are labelled with its index as the schematic is scanned, so each symbol finds its neighbouring
+* numbers by looking at the labels around it, and a number touching the symbol with several digits is only counted
+* once.
+*/
+struct Schematic {
+    numbers: Vec<NumberSpan>,
+    symbols: Vec<Symbol>,
+    // Indexes into symbols for each number, and into numbers for each symbol
+    number_symbols: Vec<Vec<usize>>,
+    symbol_numbers: Vec<Vec<usize>>,
 }
 
-fn part_one_sum(input_vec: &Vec<Vec<char>>) -> u32 {
-    let mut sum: u32 = 0;
-    for line_number in 0..input_vec.len() {
-        let number_locations = get_number_locations_in_line(&input_vec[line_number]);
-        for location in number_locations {
-            if is_valid_part_number(&location, line_number, &input_vec) {
-                let line_string: String = input_vec[line_number].iter().collect();
-                let number_string: String = line_string
-                    .chars()
-                    .skip(location.start)
-                    .take(location.end - location.start)
-                    .collect();
-
-                sum += number_string
-                    .parse::<u32>()
-                    .expect("Failed to parse number to string");
+impl Schematic {
+    fn new(input: &[Vec<char>]) -> Self {
+        let mut labels = Grid::from_rows(
+            input
+                .iter()
+                .map(|row| vec![None; row.len()])
+                .collect::<Vec<Vec<Option<usize>>>>(),
+        );
+        let mut numbers: Vec<NumberSpan> = vec![];
+        let mut symbols = vec![];
+
+        for (row, line) in input.iter().enumerate() {
+            let mut current: Option<NumberSpan> = None;
+            for (column, &character) in line.iter().enumerate() {
+                if let Some(digit) = character.to_digit(10) {
+                    let span = current.get_or_insert(NumberSpan {
+                        row,
+                        start: column,
+                        end: column,
+                        value: 0,
+                    });
+                    span.end = column + 1;
+                    span.value = span.value * 10 + digit as u64;
+                    labels.set(row, column, Some(numbers.len()));
+                    continue;
+                }
+
+                numbers.extend(current.take());
+                if is_symbol(character) {
+                    symbols.push(Symbol {
+                        row,
+                        column,
+                        character,
+                    });
+                }
             }
+            numbers.extend(current.take());
         }
-    }
 
-    sum
-}
-
-#[derive(Clone)]
-struct TwoDimensionalLocation {
-    row_location: Location,
-    y: usize,
-}
-
-fn get_number_locations(input_vec: &Vec<Vec<char>>) -> Vec<TwoDimensionalLocation> {
-    let mut number_locations: Vec<TwoDimensionalLocation> = Vec::new();
-    for i in 0..input_vec.len() {
-        let locations = get_number_locations_in_line(&input_vec[i]);
-        for location in locations {
-            number_locations.push(TwoDimensionalLocation {
-                y: i,
-                row_location: location,
-            })
+        let mut number_symbols = vec![vec![]; numbers.len()];
+        let mut symbol_numbers = vec![vec![]; symbols.len()];
+        for (i, symbol) in symbols.iter().enumerate() {
+            for row in symbol.row.saturating_sub(1)..=symbol.row + 1 {
+                for column in symbol.column.saturating_sub(1)..=symbol.column + 1 {
+                    let Some(&Some(number)) = labels.get(row, column) else {
+                        continue;
+                    };
+                    if !symbol_numbers[i].contains(&number) {
+                        symbol_numbers[i].push(number);
+                        number_symbols[number].push(i);
+                    }
+                }
+            }
         }
-    }
-
-    number_locations
-}
 
-struct Coordinate {
-    x: usize,
-    y: usize,
-}
-
-fn get_asterisk_coordinates(input_vec: &Vec<Vec<char>>) -> Vec<Coordinate> {
-    let mut locations: Vec<Coordinate> = Vec::new();
-    for y in 0..input_vec.len() {
-        for x in 0..input_vec[y].len() {
-            if input_vec[y][x] == '*' {
-                locations.push(Coordinate { x, y });
-            }
+        Self {
+            numbers,
+            symbols,
+            number_symbols,
+            symbol_numbers,
         }
     }
 
-    locations
-}
-
-fn get_adjacent_number_locations(
-    coord: Coordinate,
-    number_locations: &Vec<TwoDimensionalLocation>,
-    input_vec: &Vec<Vec<char>>,
-) -> Vec<TwoDimensionalLocation> {
-    let mut adjacent_numbers: Vec<TwoDimensionalLocation> = Vec::new();
-
-    if coord.y >= 1 {
-        let mut above_numbers: Vec<TwoDimensionalLocation> = number_locations
+    // Numbers next to at least one symbol
+    fn part_numbers(&self) -> impl Iterator<Item = &NumberSpan> {
+        self.numbers
             .iter()
-            .filter(|location| {
-                location.y == coord.y - 1
-                    && ((location.row_location.start >= coord.x - 1
-                        && location.row_location.start <= coord.x + 1)
-                        || (location.row_location.end > coord.x - 1
-                            && location.row_location.end < coord.x + 2))
-            })
-            .cloned()
-            .collect();
-        adjacent_numbers.append(&mut above_numbers);
+            .zip(&self.number_symbols)
+            .filter(|(_, symbols)| !symbols.is_empty())
+            .map(|(number, _)| number)
     }
 
-    if coord.x + 1 < input_vec[coord.y].len() {
-        let mut right_numbers: Vec<TwoDimensionalLocation> = number_locations
-            .iter()
-            .filter(|location| location.y == coord.y && location.row_location.start == coord.x + 1)
-            .cloned()
-            .collect();
-        adjacent_numbers.append(&mut right_numbers);
+    fn part_number_sum(&self) -> u64 {
+        self.part_numbers().map(|number| number.value).sum()
     }
 
-    if coord.y + 1 < input_vec.len() {
-        let mut below_numbers: Vec<TwoDimensionalLocation> = number_locations
+    // The symbols next to exactly `count` numbers, along with those numbers
+    fn symbols_with_neighbours(
+        &self,
+        count: usize,
+    ) -> impl Iterator<Item = (&Symbol, Vec<&NumberSpan>)> {
+        self.symbols
             .iter()
-            .filter(|location| {
-                location.y == coord.y + 1
-                    && ((location.row_location.start >= coord.x - 1
-                        && location.row_location.start <= coord.x + 1)
-                        || (location.row_location.end > coord.x - 1
-                            && location.row_location.end < coord.x + 2))
-            })
-            .cloned()
-            .collect();
-        adjacent_numbers.append(&mut below_numbers);
+            .zip(&self.symbol_numbers)
+            .filter(move |(_, numbers)| numbers.len() == count)
+            .map(|(symbol, numbers)| (symbol, numbers.iter().map(|&i| &self.numbers[i]).collect()))
     }
 
-    if coord.x >= 1 {
-        let mut left_numbers: Vec<TwoDimensionalLocation> = number_locations
-            .iter()
-            .filter(|location| location.y == coord.y && location.row_location.end == coord.x)
-            .cloned()
-            .collect();
-        adjacent_numbers.append(&mut left_numbers);
+    // The product of the two numbers next to each gear, where a gear is any of the given symbol next to exactly two
+    fn gear_ratios(&self, character: char) -> impl Iterator<Item = u64> + '_ {
+        self.symbols_with_neighbours(GEAR_NEIGHBOURS)
+            .filter(move |(symbol, _)| symbol.character == character)
+            .map(|(_, numbers)| numbers.iter().map(|number| number.value).product())
     }
 
-    adjacent_numbers
-}
-
-fn get_number_from_two_dimensional_location(
-    input_vec: &Vec<Vec<char>>,
-    location: &TwoDimensionalLocation,
-) -> u32 {
-    let line_string: String = input_vec[location.y].iter().collect();
-    let number_string: String = line_string
-        .chars()
-        .skip(location.row_location.start)
-        .take(location.row_location.end - location.row_location.start)
-        .collect();
-
-    number_string
-        .parse::<u32>()
-        .expect("Failed to parse number to string")
-}
-
-fn part_two_sum(input_vec: &Vec<Vec<char>>) -> u32 {
-    let asterisks = get_asterisk_coordinates(&input_vec);
-    let numbers = get_number_locations(&input_vec);
-
-    let mut sum = 0;
-
-    for asterisk in asterisks {
-        let adjacent_numbers = get_adjacent_number_locations(asterisk, &numbers, input_vec);
-
-        if adjacent_numbers.len() == 2 {
-            sum += get_number_from_two_dimensional_location(&input_vec, &adjacent_numbers[0])
-                * get_number_from_two_dimensional_location(&input_vec, &adjacent_numbers[1]);
-        }
+    fn gear_ratio_sum(&self, character: char) -> u64 {
+        self.gear_ratios(character).sum()
     }
-
-    sum
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_is_symbol() {
-        assert!(is_symbol('+'));
-        assert!(!is_symbol('.'));
-        assert!(!is_symbol('9'));
+    fn parse(lines: &[&str]) -> Vec<Vec<char>> {
+        lines.iter().map(|line| line.chars().collect()).collect()
     }
 
     #[test]
-    fn test_is_valid_position() {
-        assert!(is_valid_position(0, 0, 5, 5));
-        assert!(!is_valid_position(-1, 0, 5, 5));
-        assert!(!is_valid_position(0, -1, 5, 5));
-        assert!(!is_valid_position(5, 0, 5, 5));
-        assert!(!is_valid_position(0, 5, 5, 5));
+    fn test_parts() {
+        let schematic = Schematic::new(&get_two_dimensional_vector("resources/test_input.txt"));
+
+        assert_eq!(schematic.part_number_sum(), 4361);
+        assert_eq!(schematic.gear_ratio_sum(GEAR), 467835);
     }
 
     #[test]
-    fn test_get_adjacent_elements() {
-        let mut char_vec: Vec<Vec<char>> = vec![];
-        char_vec.push(vec!['a', 'b']);
-        char_vec.push(vec!['f', 'g']);
-
-        let adjacent_elements = get_adjacent_elements(&char_vec, 0, 0);
-
-        assert_eq!(adjacent_elements.len(), 3);
-        assert!(adjacent_elements.contains(&'b'));
-        assert!(adjacent_elements.contains(&'g'));
-        assert!(adjacent_elements.contains(&'f'));
+    fn test_is_symbol() {
+        assert!(is_symbol('+'));
+        assert!(!is_symbol('.'));
+        assert!(!is_symbol('9'));
     }
 
     #[test]
-    fn test_is_part_number_digit() {
-        let first_line = vec!['4', '6', '7', '.', '.', '1', '1', '4'];
-        let second_line = vec!['.', '.', '.', '*', '.', '.', '.', '.'];
-        let input_vec = vec![first_line, second_line];
+    fn test_number_spans() {
+        let schematic = Schematic::new(&parse(&["467..114", "...22..."]));
 
-        assert!(is_part_number_digit(&input_vec, 0, 2));
-        assert!(!is_part_number_digit(&input_vec, 0, 5));
+        assert_eq!(
+            schematic.numbers,
+            vec![
+                NumberSpan {
+                    row: 0,
+                    start: 0,
+                    end: 3,
+                    value: 467
+                },
+                NumberSpan {
+                    row: 0,
+                    start: 5,
+                    end: 8,
+                    value: 114
+                },
+                NumberSpan {
+                    row: 1,
+                    start: 3,
+                    end: 5,
+                    value: 22
+                },
+            ]
+        );
     }
 
     #[test]
-    fn test_get_number_locations_in_line() {
-        let line = vec!['4', '6', '7', '.', '.', '1', '1'];
-        let number_locations = get_number_locations_in_line(&line);
-
-        assert_eq!(number_locations.len(), 2);
-        assert_eq!(number_locations[0].start, 0);
-        assert_eq!(number_locations[0].end, 3);
-        assert_eq!(number_locations[1].start, 5);
-        assert_eq!(number_locations[1].end, 7);
-    }
+    fn test_symbols() {
+        let schematic = Schematic::new(&parse(&["467.*114", "...#...."]));
 
-    #[test]
-    fn test_is_valid_part_number() {
-        let first_line = vec!['4', '6', '7', '.', '.', '1', '1', '4'];
-        let second_line = vec!['.', '.', '.', '*', '.', '.', '.', '.'];
-        let input_vec = vec![first_line, second_line];
-
-        assert!(is_valid_part_number(
-            &Location { start: 0, end: 3 },
-            0,
-            &input_vec
-        ));
-        assert!(!is_valid_part_number(
-            &Location { start: 5, end: 8 },
-            0,
-            &input_vec
-        ));
+        assert_eq!(
+            schematic.symbols,
+            vec![
+                Symbol {
+                    row: 0,
+                    column: 4,
+                    character: '*'
+                },
+                Symbol {
+                    row: 1,
+                    column: 3,
+                    character: '#'
+                },
+            ]
+        );
     }
 
     #[test]
-    fn test_get_number_locations() {
-        let first_line = vec!['4', '6', '7', '.', '.', '1', '1', '4'];
-        let second_line = vec!['.', '.', '.', '2', '2', '.', '.', '.'];
-        let input_vec = vec![first_line, second_line];
-        let number_locations = get_number_locations(&input_vec);
-
-        assert_eq!(number_locations.len(), 3);
-
-        assert_eq!(number_locations[0].y, 0);
-        assert_eq!(number_locations[0].row_location.start, 0);
-        assert_eq!(number_locations[0].row_location.end, 3);
-
-        assert_eq!(number_locations[1].y, 0);
-        assert_eq!(number_locations[1].row_location.start, 5);
-        assert_eq!(number_locations[1].row_location.end, 8);
+    fn test_part_numbers() {
+        let schematic = Schematic::new(&parse(&["467..114", "...*...."]));
+        let part_numbers: Vec<u64> = schematic
+            .part_numbers()
+            .map(|number| number.value)
+            .collect();
 
-        assert_eq!(number_locations[2].y, 1);
-        assert_eq!(number_locations[2].row_location.start, 3);
-        assert_eq!(number_locations[2].row_location.end, 5);
+        assert_eq!(part_numbers, vec![467]);
     }
 
     #[test]
-    fn test_get_asterisk_coordinates() {
-        let first_line = vec!['4', '6', '7', '.', '*', '1', '1', '4'];
-        let second_line = vec!['.', '.', '.', '*', '.', '.', '.', '.'];
-        let input_vec = vec![first_line, second_line];
-        let coordinates = get_asterisk_coordinates(&input_vec);
-
-        assert_eq!(coordinates.len(), 2);
-
-        assert_eq!(coordinates[0].y, 0);
-        assert_eq!(coordinates[0].x, 4);
+    fn test_number_next_to_two_symbols_counts_once() {
+        let schematic = Schematic::new(&parse(&["*..", "12#"]));
 
-        assert_eq!(coordinates[1].y, 1);
-        assert_eq!(coordinates[1].x, 3);
+        assert_eq!(schematic.part_number_sum(), 12);
+        assert_eq!(schematic.number_symbols[0], vec![0, 1]);
     }
 
     #[test]
-    fn test_get_adjacent_number_locations() {
-        let first_line = vec!['4', '6', '7', '.', '*', '1', '1', '4'];
-        let second_line = vec!['.', '.', '.', '*', '.', '.', '.', '.'];
-        let third_line = vec!['.', '.', '3', '5', '.', '.', '.', '.'];
-        let input_vec = vec![first_line, second_line, third_line];
-        let number_locations = get_number_locations(&input_vec);
-
-        let adjacent_numbers =
-            get_adjacent_number_locations(Coordinate { x: 3, y: 1 }, &number_locations, &input_vec);
-
-        assert_eq!(adjacent_numbers.len(), 2);
-
-        assert_eq!(adjacent_numbers[0].y, 0);
-        assert_eq!(adjacent_numbers[0].row_location.start, 0);
-        assert_eq!(adjacent_numbers[0].row_location.end, 3);
+    fn test_symbols_with_neighbours() {
+        let schematic = Schematic::new(&parse(&["467.*114", "...*....", "..35...."]));
+        let symbols: Vec<(Symbol, Vec<u64>)> = schematic
+            .symbols_with_neighbours(2)
+            .map(|(symbol, numbers)| (*symbol, numbers.iter().map(|n| n.value).collect()))
+            .collect();
 
-        assert_eq!(adjacent_numbers[1].y, 2);
-        assert_eq!(adjacent_numbers[1].row_location.start, 2);
-        assert_eq!(adjacent_numbers[1].row_location.end, 4);
+        assert_eq!(
+            symbols,
+            vec![(
+                Symbol {
+                    row: 1,
+                    column: 3,
+                    character: '*'
+                },
+                vec![467, 35]
+            )]
+        );
+        assert_eq!(schematic.symbols_with_neighbours(1).count(), 1);
+        assert_eq!(schematic.symbols_with_neighbours(3).count(), 0);
     }
 
     #[test]
-    fn test_get_number_from_two_dimensional_location() {
-        let first_line = vec!['4', '6', '7', '.', '*', '1', '1', '4'];
-        let second_line = vec!['.', '.', '.', '*', '.', '.', '.', '.'];
-        let input_vec = vec![first_line, second_line];
-        let location = TwoDimensionalLocation {
-            y: 0,
-            row_location: Location { start: 0, end: 3 },
-        };
+    fn test_gear_ratios_for_other_symbols() {
+        let schematic = Schematic::new(&parse(&["2.3", ".#.", "4*5"]));
 
-        assert_eq!(
-            get_number_from_two_dimensional_location(&input_vec, &location),
-            467
-        );
+        assert_eq!(schematic.gear_ratio_sum('*'), 20);
+        assert_eq!(schematic.gear_ratio_sum('#'), 0);
+        assert_eq!(schematic.symbols_with_neighbours(4).count(), 1);
+        assert_eq!(Schematic::new(&parse(&["2#3"])).gear_ratio_sum('#'), 6);
     }
 }
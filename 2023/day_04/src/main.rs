@@ -1,139 +1,354 @@
-use std::collections::HashSet;
+use std::fmt;
 
-use lazy_static::lazy_static;
-use library::{get_filename_arg, get_lines};
-use regex::Regex;
+use library::{get_filename_and_options, get_lines};
 
 fn main() {
-    let file_path = get_filename_arg();
+    /* --report prints the matches, copies and points of every card for both parts
+     * --score=doubling|linear picks how part 1 scores a card's matches
+     * --cap=<n> stops part 2 from ever having more than n of any one card
+     */
+    let (file_path, options) = get_filename_and_options(&["report", "score", "cap"]);
     let lines = get_lines(&file_path);
+    let cards = parse_cards(&lines).unwrap_or_else(|error| panic!("{}", error));
 
-    println!("Part 1: {}", part_one_sum(&lines));
-    println!("Part 2: {}", part_two_sum(&lines));
-}
+    let mut points = PrizeRules::POINTS;
+    if let Some(name) = options.value("score") {
+        points.score = get_score(name).unwrap_or_else(|| panic!("Unknown score {}", name));
+    }
+    let mut copies = PrizeRules::COPIES;
+    if let Some(cap) = options.parsed_value("cap") {
+        copies.copies = Copies::Capped(cap);
+    }
 
-fn get_numbers_section(line: &String) -> String {
-    lazy_static! {
-        static ref REGEX: Regex = Regex::new(r"  +").unwrap();
+    for (part, rules) in [(1, points), (2, copies)] {
+        let reports = cascade(&cards, &rules);
+        if options.is_set("report") {
+            for report in &reports {
+                println!("{}", report);
+            }
+        }
+        let total = match part {
+            1 => reports.iter().map(|report| report.points).sum::<u64>(),
+            _ => reports.iter().map(|report| report.copies).sum(),
+        };
+        println!("Part {}: {}", part, total);
     }
-    let numbers_section: &str = line.split(":").collect::<Vec<&str>>()[1];
-    REGEX.replace_all(&numbers_section, " ").to_string()
 }
 
-fn get_number_vec(numbers: &str) -> Vec<u32> {
-    let mut parsed_numbers: Vec<u32> = Vec::new();
-    let number_strings: Vec<&str> = numbers
-        .split(" ")
-        .filter(|element| *element != "")
-        .collect();
+#[derive(PartialEq, Eq, Debug)]
+enum CardError {
+    MissingHeader(String),
+    MissingSeparator(String),
+    InvalidNumber(String),
+    // Only numbers below 128 fit in the sets of numbers
+    NumberTooLarge(u32),
+    RepeatedNumber(u32),
+    OutOfOrder { expected: usize, found: usize },
+}
 
-    for number_string in number_strings {
-        parsed_numbers.push(
-            number_string
-                .parse()
-                .expect("Failed to parse string to number"),
-        );
+impl fmt::Display for CardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHeader(line) => {
+                write!(f, "\"{}\" does not start with \"Card <id>:\"", line)
+            }
+            Self::MissingSeparator(line) => {
+                write!(f, "\"{}\" does not have exactly one | in it", line)
+            }
+            Self::InvalidNumber(number) => write!(f, "\"{}\" is not a number", number),
+            Self::NumberTooLarge(number) => write!(f, "{} is larger than 127", number),
+            Self::RepeatedNumber(number) => write!(f, "{} is listed twice on one side", number),
+            Self::OutOfOrder { expected, found } => {
+                write!(f, "Expected card {} but found card {}", expected, found)
+            }
+        }
     }
-
-    parsed_numbers
 }
 
-fn get_match_count(line: &String) -> u32 {
-    let numbers_section = get_numbers_section(&line);
-    let sections: Vec<&str> = numbers_section.split("|").collect();
-    let winning_numbers: HashSet<u32> = HashSet::from_iter(get_number_vec(sections[0]).into_iter());
-    let player_numbers = get_number_vec(sections[1]);
-    let mut matches = 0;
+// The numbers on each side of the card are kept as bitsets, with bit n set when n is on the card
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Scratchcard {
+    id: usize,
+    winning: u128,
+    held: u128,
+}
 
-    for number in player_numbers {
-        if winning_numbers.contains(&number) {
-            matches += 1;
+impl Scratchcard {
+    // e.g. "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53"
+    fn parse(line: &str) -> Result<Self, CardError> {
+        let (header, numbers) = line
+            .split_once(':')
+            .ok_or_else(|| CardError::MissingHeader(String::from(line)))?;
+        let id = header
+            .strip_prefix("Card")
+            .and_then(|id| id.trim().parse().ok())
+            .ok_or_else(|| CardError::MissingHeader(String::from(line)))?;
+        let sides: Vec<&str> = numbers.split('|').collect();
+        if sides.len() != 2 {
+            return Err(CardError::MissingSeparator(String::from(line)));
         }
+
+        Ok(Self {
+            id,
+            winning: get_number_set(sides[0])?,
+            held: get_number_set(sides[1])?,
+        })
     }
 
-    matches
+    fn matches(&self) -> u32 {
+        (self.winning & self.held).count_ones()
+    }
 }
 
-fn get_line_score(line: &String) -> u32 {
-    let match_count = get_match_count(&line);
-    if match_count == 0 {
-        return 0;
+fn get_number_set(numbers: &str) -> Result<u128, CardError> {
+    let mut set = 0;
+    for number in numbers.split_whitespace() {
+        let number: u32 = number
+            .parse()
+            .map_err(|_| CardError::InvalidNumber(String::from(number)))?;
+        if number >= u128::BITS {
+            return Err(CardError::NumberTooLarge(number));
+        }
+        if set & (1 << number) != 0 {
+            return Err(CardError::RepeatedNumber(number));
+        }
+        set |= 1 << number;
     }
 
-    let base: u32 = 2;
-    base.pow(match_count - 1)
+    Ok(set)
 }
 
-fn part_one_sum(lines: &Vec<String>) -> u32 {
-    let mut sum = 0;
+// Checks that the cards are numbered 1, 2, 3... in the order they are listed, which copies won rely on
+fn parse_cards(lines: &[String]) -> Result<Vec<Scratchcard>, CardError> {
+    lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            let card = Scratchcard::parse(line)?;
+            if card.id != i + 1 {
+                return Err(CardError::OutOfOrder {
+                    expected: i + 1,
+                    found: card.id,
+                });
+            }
+            Ok(card)
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Copies {
+    // Cards only win points
+    None,
+    // A card with n matches wins a copy of each of the n cards after it, for every copy of it there is
+    Forward,
+    // As Forward, but there are never more than this many of any one card, counting the original
+    Capped(u64),
+}
+
+#[derive(Clone, Copy)]
+struct PrizeRules {
+    copies: Copies,
+    // Points a single card is worth for its number of matches
+    score: fn(u32) -> u64,
+}
 
-    for line in lines {
-        sum += get_line_score(&line);
+impl PrizeRules {
+    const POINTS: Self = Self {
+        copies: Copies::None,
+        score: doubling_score,
+    };
+    const COPIES: Self = Self {
+        copies: Copies::Forward,
+        score: no_score,
+    };
+}
+
+fn get_score(name: &str) -> Option<fn(u32) -> u64> {
+    match name {
+        "doubling" => Some(doubling_score),
+        "linear" => Some(linear_score),
+        _ => None,
     }
+}
 
-    sum
+// One point for the first match, then doubled for each match after it
+fn doubling_score(matches: u32) -> u64 {
+    match matches {
+        0 => 0,
+        _ => 1u64.checked_shl(matches - 1).unwrap_or(u64::MAX),
+    }
 }
 
-fn part_two_sum(lines: &Vec<String>) -> u32 {
-    let mut copies_histogram = vec![1; lines.len()];
+// One point for each match
+fn linear_score(matches: u32) -> u64 {
+    matches as u64
+}
 
-    for (i, line) in lines.iter().enumerate() {
-        let match_count = get_match_count(line);
-        let copies = copies_histogram[i];
+fn no_score(_: u32) -> u64 {
+    0
+}
 
-        let mut j = i + 1;
-        while j <= i + match_count as usize && i < lines.len() {
-            copies_histogram[j] += copies;
-            j += 1;
-        }
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct CardReport {
+    id: usize,
+    matches: u32,
+    copies: u64,
+    // Points for all the copies of the card together
+    points: u64,
+}
+
+impl fmt::Display for CardReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Card {}: {} matches, {} copies, {} points",
+            self.id, self.matches, self.copies, self.points
+        )
     }
+}
 
-    let mut sum = 0;
+/* Scratches the cards in order. Copies only ever go to later cards, so by the time a card is reached every copy of it
+* has already been won. Copies of cards past the end of the table are not won.
+*/
+fn cascade(cards: &[Scratchcard], rules: &PrizeRules) -> Vec<CardReport> {
+    let mut copies = vec![1u64; cards.len()];
+    let mut reports = Vec::with_capacity(cards.len());
+
+    for (i, card) in cards.iter().enumerate() {
+        let matches = card.matches();
+        let won = i + 1..cards.len().min(i + 1 + matches as usize);
+        for j in won {
+            copies[j] = match rules.copies {
+                Copies::None => copies[j],
+                Copies::Forward => copies[j].saturating_add(copies[i]),
+                Copies::Capped(cap) => copies[j].saturating_add(copies[i]).min(cap),
+            };
+        }
 
-    for count in copies_histogram {
-        sum += count;
+        reports.push(CardReport {
+            id: card.id,
+            matches,
+            copies: copies[i],
+            points: (rules.score)(matches).saturating_mul(copies[i]),
+        });
     }
 
-    sum
+    reports
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_get_numbers_section() {
-        let line = String::from("Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1");
+    fn get_test_cards() -> Vec<Scratchcard> {
+        parse_cards(&get_lines("resources/test_input.txt")).unwrap()
+    }
 
-        assert_eq!(
-            get_numbers_section(&line),
-            " 1 21 53 59 44 | 69 82 63 72 16 21 14 1"
-        )
+    fn get_line_score(line: &str) -> u64 {
+        doubling_score(Scratchcard::parse(line).unwrap().matches())
     }
 
     #[test]
-    fn test_get_number_vec() {
-        let input = " 1 21 ";
+    fn test_parts() {
+        let cards = get_test_cards();
+        let points = cascade(&cards, &PrizeRules::POINTS);
+        let copies = cascade(&cards, &PrizeRules::COPIES);
 
-        let numbers = get_number_vec(input);
+        assert_eq!(points.iter().map(|r| r.points).sum::<u64>(), 13);
+        assert_eq!(copies.iter().map(|r| r.copies).sum::<u64>(), 30);
+    }
+
+    #[test]
+    fn test_parse() {
+        let card = Scratchcard::parse("Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1").unwrap();
 
-        assert_eq!(numbers.len(), 2);
-        assert_eq!(numbers[0], 1);
-        assert_eq!(numbers[1], 21);
+        assert_eq!(card.id, 3);
+        assert_eq!(card.winning, 1 << 1 | 1 << 21 | 1 << 53 | 1 << 59 | 1 << 44);
+        assert_eq!(card.held.count_ones(), 8);
+        assert_eq!(card.matches(), 2);
     }
 
     #[test]
     fn test_get_line_score() {
         assert_eq!(
-            get_line_score(&String::from(
-                "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53"
-            )),
+            get_line_score("Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53"),
             8
         );
+        assert_eq!(get_line_score("Card 3: 1 2 3 | 4 5 6"), 0);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Scratchcard::parse("Card 3: 1 2 3 | 4 | 5 | 6"),
+            Err(CardError::MissingSeparator(String::from(
+                "Card 3: 1 2 3 | 4 | 5 | 6"
+            )))
+        );
+        assert_eq!(
+            Scratchcard::parse("1 2 | 3"),
+            Err(CardError::MissingHeader(String::from("1 2 | 3")))
+        );
+        assert_eq!(
+            Scratchcard::parse("Card 1: 1 x | 3"),
+            Err(CardError::InvalidNumber(String::from("x")))
+        );
+        assert_eq!(
+            Scratchcard::parse("Card 1: 1 128 | 3"),
+            Err(CardError::NumberTooLarge(128))
+        );
+        assert_eq!(
+            Scratchcard::parse("Card 1: 1 2 | 3 3"),
+            Err(CardError::RepeatedNumber(3))
+        );
+    }
+
+    #[test]
+    fn test_cards_out_of_order() {
+        let lines = vec![String::from("Card 1: 1 | 1"), String::from("Card 3: 1 | 1")];
+
+        assert_eq!(
+            parse_cards(&lines),
+            Err(CardError::OutOfOrder {
+                expected: 2,
+                found: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let reports = cascade(&get_test_cards(), &PrizeRules::COPIES);
+
+        assert_eq!(
+            reports.iter().map(|r| r.copies).collect::<Vec<_>>(),
+            vec![1, 2, 4, 8, 14, 1]
+        );
+        assert_eq!(
+            reports[0].to_string(),
+            "Card 1: 4 matches, 1 copies, 0 points"
+        );
+    }
+
+    #[test]
+    fn test_alternative_rules() {
+        let cards = get_test_cards();
+        let capped = cascade(
+            &cards,
+            &PrizeRules {
+                copies: Copies::Capped(3),
+                score: get_score("linear").unwrap(),
+            },
+        );
 
         assert_eq!(
-            get_line_score(&String::from("Card 3: 1 2 3 | 4 | 5 | 6")),
-            0
+            capped.iter().map(|r| r.copies).collect::<Vec<_>>(),
+            vec![1, 2, 3, 3, 3, 1]
         );
+        // Matches 4, 2, 2, 1, 0, 0 times the copies
+        assert_eq!(capped.iter().map(|r| r.points).sum::<u64>(), 4 + 4 + 6 + 3);
+        assert!(get_score("squared").is_none());
     }
 }
@@ -1,194 +1,473 @@
-use std::{collections::HashMap, u64::MAX};
+use std::fmt;
 
-use library::{get_filename_arg, get_lines};
-use regex::Regex;
+use library::{get_filename_and_options, get_lines};
 
 fn main() {
-    let file_name = get_filename_arg();
+    // --report prints the seed each answer comes from and every number that maps to the same location
+    let (file_name, options) = get_filename_and_options(&["report"]);
     let mut lines = get_lines(&file_name);
     lines.retain(|f| !f.is_empty());
 
-    let mut maps: Vec<HashMap<Range, Range>> = vec![];
-    let mut current_map: HashMap<Range, Range> = HashMap::new();
-    let line_header_regex = Regex::new(r".*-to.* map:").unwrap();
-    for i in 2..lines.len() {
-        if line_header_regex.is_match(lines[i].as_str()) || lines[i].is_empty() {
-            maps.push(current_map);
-            current_map = HashMap::new();
-        } else {
-            let numbers = get_numbers_from_strings(&split_line(&lines[i]));
-            current_map.insert(
-                Range {
-                    start: numbers[1],
-                    end: numbers[1] + numbers[2],
-                },
-                Range {
-                    start: numbers[0],
-                    end: numbers[0] + numbers[2],
-                },
-            );
-        }
-    }
-    maps.push(current_map);
+    let almanac = Almanac::parse(&lines).unwrap_or_else(|error| panic!("{}", error));
+    let seed_to_location = almanac.seed_to_location();
 
-    let part_one_seeds = get_part_one_seeds(&lines[0]);
+    let part_one_seeds: Vec<Range> = get_part_one_seeds(&lines[0])
+        .into_iter()
+        .map(|seed| Range {
+            start: seed,
+            end: seed + 1,
+        })
+        .collect();
     let part_two_seeds = get_part_two_seeds(&lines[0]);
 
-    let part_one = get_min_location_part_one(part_one_seeds, &maps);
-    println!("Part 1: {}", part_one);
-
-    let part_two = get_min_location_part_two(part_two_seeds, &maps);
-    println!("Part 2: {}", part_two);
-}
-
-fn get_numbers_from_strings(strings: &Vec<String>) -> Vec<u64> {
-    let mut numbers: Vec<u64> = vec![];
-
-    for item in strings {
-        numbers.push(item.parse().expect("Failed to parse into number"));
+    for (part, seeds) in [(1, part_one_seeds), (2, part_two_seeds)] {
+        let (seed, location) = seed_to_location
+            .min_location(&seeds)
+            .expect("No seeds to plant");
+        println!("Part {}: {}", part, location);
+        if options.is_set("report") {
+            let sources: Vec<String> = seed_to_location
+                .reverse(location)
+                .iter()
+                .map(|source| source.to_string())
+                .collect();
+            println!(
+                "Seed: {} (every number that maps to {}: {})",
+                seed,
+                location,
+                sources.join(", ")
+            );
+        }
     }
+}
 
-    numbers
+fn get_numbers_from_strings(strings: &[String]) -> Result<Vec<u64>, AlmanacError> {
+    strings
+        .iter()
+        .map(|item| {
+            item.parse()
+                .map_err(|_| AlmanacError::InvalidNumber(item.clone()))
+        })
+        .collect()
 }
 
-fn split_line(line: &String) -> Vec<String> {
-    line.split(" ").map(|s| s.to_string()).collect()
+fn split_line(line: &str) -> Vec<String> {
+    line.split_whitespace().map(|s| s.to_string()).collect()
 }
 
-fn get_part_one_seeds(seeds_line: &String) -> Vec<u64> {
-    let mut number_strings: Vec<String> = split_line(&seeds_line);
+fn get_part_one_seeds(seeds_line: &str) -> Vec<u64> {
+    let mut number_strings: Vec<String> = split_line(seeds_line);
     number_strings.remove(0);
-    get_numbers_from_strings(&number_strings)
+    get_numbers_from_strings(&number_strings).unwrap_or_else(|error| panic!("{}", error))
 }
 
-fn get_part_two_seeds(seeds_line: &String) -> Vec<Range> {
-    let seed_numbers = get_part_one_seeds(seeds_line);
-    let mut seeds: Vec<Range> = vec![];
-
-    for (index, seed) in seed_numbers.iter().enumerate() {
-        if index % 2 == 0 {
-            seeds.push(Range {
-                start: *seed,
-                end: seed + seed_numbers[index + 1],
-            });
-        }
-    }
-
-    seeds
+fn get_part_two_seeds(seeds_line: &str) -> Vec<Range> {
+    get_part_one_seeds(seeds_line)
+        .chunks(2)
+        .map(|pair| Range {
+            start: pair[0],
+            end: pair[0] + pair[1],
+        })
+        .collect()
 }
 
-#[derive(Hash, Eq, PartialEq)]
+// The numbers from start up to but not including end
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
 struct Range {
     start: u64,
     end: u64,
 }
 
-fn get_mapped_value(value: u64, map: &HashMap<Range, Range>) -> u64 {
-    let map_keys = map.keys();
+#[derive(PartialEq, Eq, Debug)]
+enum AlmanacError {
+    MissingSeeds,
+    InvalidNumber(String),
+    // A line of a map that is not three numbers, or whose ranges do not fit in a u64
+    InvalidMapping(String),
+    OverlappingSources {
+        layer: String,
+        first: Range,
+        second: Range,
+    },
+}
 
-    for map_key in map_keys {
-        if value >= map_key.start && value < map_key.end {
-            let difference = value - map_key.start;
-            return map.get(map_key).expect("No value found for key").start + difference;
+impl fmt::Display for AlmanacError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSeeds => write!(f, "The almanac does not start with the seeds"),
+            Self::InvalidNumber(number) => write!(f, "Failed to parse {} into number", number),
+            Self::InvalidMapping(line) => write!(f, "Invalid map line \"{}\"", line),
+            Self::OverlappingSources {
+                layer,
+                first,
+                second,
+            } => write!(
+                f,
+                "The {} map sends {}..{} and {}..{} to different places, but they overlap",
+                layer, first.start, first.end, second.start, second.end
+            ),
         }
     }
+}
 
-    value
+/* A function from numbers to numbers that adds a fixed offset to each of a list of sorted ranges. The ranges cover
+* every number from 0 up to u64::MAX, with an offset of 0 wherever the almanac does not say otherwise.
+*/
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct PiecewiseMap {
+    pieces: Vec<(Range, i128)>,
 }
 
-fn get_min_location_part_one(seeds: Vec<u64>, maps: &Vec<HashMap<Range, Range>>) -> u64 {
-    let mut min_value: u64 = MAX;
+impl PiecewiseMap {
+    fn identity() -> Self {
+        Self {
+            pieces: vec![(
+                Range {
+                    start: 0,
+                    end: u64::MAX,
+                },
+                0,
+            )],
+        }
+    }
 
-    for seed in seeds {
-        let mut value = seed;
+    // Each mapping is (source range, destination start)
+    fn from_layer(layer: &str, mappings: &[(Range, u64)]) -> Result<Self, AlmanacError> {
+        let mut mappings = mappings.to_vec();
+        mappings.sort_by_key(|(source, _)| source.start);
+        for pair in mappings.windows(2) {
+            if pair[1].0.start < pair[0].0.end {
+                return Err(AlmanacError::OverlappingSources {
+                    layer: String::from(layer),
+                    first: pair[0].0,
+                    second: pair[1].0,
+                });
+            }
+        }
 
-        for map in maps.iter() {
-            value = get_mapped_value(value, map);
+        let mut pieces = vec![];
+        let mut covered = 0;
+        for (source, destination) in mappings {
+            if source.start == source.end {
+                continue;
+            }
+            if covered < source.start {
+                pieces.push((
+                    Range {
+                        start: covered,
+                        end: source.start,
+                    },
+                    0,
+                ));
+            }
+            pieces.push((source, destination as i128 - source.start as i128));
+            covered = source.end;
         }
+        if covered < u64::MAX {
+            pieces.push((
+                Range {
+                    start: covered,
+                    end: u64::MAX,
+                },
+                0,
+            ));
+        }
+
+        Ok(Self::merged(pieces))
+    }
 
-        if value < min_value {
-            min_value = value;
+    // Joins neighbouring pieces with the same offset, so equal functions have equal pieces
+    fn merged(pieces: Vec<(Range, i128)>) -> Self {
+        let mut merged: Vec<(Range, i128)> = vec![];
+        for (range, offset) in pieces {
+            match merged.last_mut() {
+                Some((last, last_offset)) if *last_offset == offset && last.end == range.start => {
+                    last.end = range.end;
+                }
+                _ => merged.push((range, offset)),
+            }
         }
+        Self { pieces: merged }
     }
 
-    min_value
-}
+    fn get_piece(&self, value: u64) -> (Range, i128) {
+        let index = self.pieces.partition_point(|(range, _)| range.end <= value);
+        self.pieces[index.min(self.pieces.len() - 1)]
+    }
 
-fn get_min_location_part_two(seeds: Vec<Range>, maps: &Vec<HashMap<Range, Range>>) -> u64 {
-    let mut min_value: u64 = MAX;
+    /* This map followed by `next`, as a single map. Each piece of this one is cut up wherever its image crosses from
+     * one piece of `next` into another, and those parts get the two offsets added together.
+     */
+    fn then(&self, next: &Self) -> Self {
+        let mut pieces = vec![];
+        for &(range, offset) in &self.pieces {
+            let mut start = range.start;
+            while start < range.end {
+                let image = (start as i128 + offset) as u64;
+                let (next_range, next_offset) = next.get_piece(image);
+                let length = (range.end - start).min(next_range.end - image).max(1);
+                pieces.push((
+                    Range {
+                        start,
+                        end: start + length,
+                    },
+                    offset + next_offset,
+                ));
+                start += length;
+            }
+        }
 
-    for seed in seeds {
-        for i in seed.start..seed.end {
-            let mut value = i;
-            for map in maps.iter() {
-                value = get_mapped_value(value, map);
+        Self::merged(pieces)
+    }
+
+    /* The lowest value any of the seeds maps to, and the lowest seed that maps to it. Within a piece the lowest value
+     * always comes from the lowest seed, so only the start of each seed range within each piece needs checking.
+     */
+    fn min_location(&self, seeds: &[Range]) -> Option<(u64, u64)> {
+        let mut best: Option<(u64, u64)> = None;
+        for seeds in seeds {
+            let first = self
+                .pieces
+                .partition_point(|(range, _)| range.end <= seeds.start);
+            for &(range, _) in self.pieces[first..]
+                .iter()
+                .take_while(|(range, _)| range.start < seeds.end)
+            {
+                let seed = seeds.start.max(range.start);
+                let location = self.apply(seed);
+                if best.is_none_or(|(best_seed, best_location)| {
+                    (location, seed) < (best_location, best_seed)
+                }) {
+                    best = Some((seed, location));
+                }
             }
+        }
+        best
+    }
+
+    fn apply(&self, value: u64) -> u64 {
+        let (_, offset) = self.get_piece(value);
+        (value as i128 + offset) as u64
+    }
+
+    // Every number that maps to `value`, lowest first. There can be several when two ranges map onto the same place
+    fn reverse(&self, value: u64) -> Vec<u64> {
+        self.pieces
+            .iter()
+            .filter_map(|&(range, offset)| {
+                let source = value as i128 - offset;
+                (source >= range.start as i128 && source < range.end as i128)
+                    .then_some(source as u64)
+            })
+            .collect()
+    }
+}
+
+struct Almanac {
+    // Each named map in order, e.g. "seed-to-soil"
+    layers: Vec<(String, PiecewiseMap)>,
+}
 
-            if value < min_value {
-                min_value = value;
+impl Almanac {
+    // Every line of the almanac but the blank ones, starting with the seeds, which are checked for and then skipped
+    fn parse(lines: &[String]) -> Result<Self, AlmanacError> {
+        if !lines.first().is_some_and(|line| line.starts_with("seeds:")) {
+            return Err(AlmanacError::MissingSeeds);
+        }
+
+        let mut layers: Vec<(String, Vec<(Range, u64)>)> = vec![];
+        for line in &lines[1..] {
+            if let Some(name) = line.strip_suffix(" map:") {
+                layers.push((String::from(name), vec![]));
+                continue;
             }
+
+            let invalid = || AlmanacError::InvalidMapping(line.clone());
+            let numbers = get_numbers_from_strings(&split_line(line))?;
+            let (Some((_, mappings)), &[destination, source, length]) =
+                (layers.last_mut(), numbers.as_slice())
+            else {
+                return Err(invalid());
+            };
+            let source_end = source.checked_add(length).ok_or_else(invalid)?;
+            destination.checked_add(length).ok_or_else(invalid)?;
+            mappings.push((
+                Range {
+                    start: source,
+                    end: source_end,
+                },
+                destination,
+            ));
         }
+
+        let layers = layers
+            .into_iter()
+            .map(|(name, mappings)| {
+                let map = PiecewiseMap::from_layer(&name, &mappings)?;
+                Ok((name, map))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { layers })
     }
 
-    min_value
+    fn seed_to_location(&self) -> PiecewiseMap {
+        self.layers
+            .iter()
+            .fold(PiecewiseMap::identity(), |map, (_, layer)| map.then(layer))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn get_test_lines() -> Vec<String> {
+        let mut lines = get_lines("resources/test_input.txt");
+        lines.retain(|f| !f.is_empty());
+        lines
+    }
+
+    #[test]
+    fn test_parts() {
+        let lines = get_test_lines();
+        let seed_to_location = Almanac::parse(&lines).unwrap().seed_to_location();
+        let part_one_seeds: Vec<Range> = get_part_one_seeds(&lines[0])
+            .into_iter()
+            .map(|seed| Range {
+                start: seed,
+                end: seed + 1,
+            })
+            .collect();
+
+        assert_eq!(
+            seed_to_location.min_location(&part_one_seeds),
+            Some((13, 35))
+        );
+        assert_eq!(
+            seed_to_location.min_location(&get_part_two_seeds(&lines[0])),
+            Some((82, 46))
+        );
+    }
+
     #[test]
     fn test_get_numbers_from_strings() {
         let strings = vec![String::from("3"), String::from("4"), String::from("5")];
-        let numbers = get_numbers_from_strings(&strings);
+        let numbers = get_numbers_from_strings(&strings).unwrap();
 
-        assert_eq!(numbers.len(), strings.len());
-        assert_eq!(numbers[0], 3);
-        assert_eq!(numbers[1], 4);
-        assert_eq!(numbers[2], 5);
+        assert_eq!(numbers, vec![3, 4, 5]);
+        assert_eq!(
+            get_numbers_from_strings(&[String::from("x")]),
+            Err(AlmanacError::InvalidNumber(String::from("x")))
+        );
     }
 
     #[test]
     fn test_split_line() {
-        let split_up_line = split_line(&String::from("seeds: 79 14 55 13"));
+        let split_up_line = split_line("seeds: 79 14 55 13");
 
-        assert_eq!(split_up_line.len(), 5);
-        assert_eq!(split_up_line[0], "seeds:");
-        assert_eq!(split_up_line[1], "79");
-        assert_eq!(split_up_line[2], "14");
-        assert_eq!(split_up_line[3], "55");
-        assert_eq!(split_up_line[4], "13");
+        assert_eq!(split_up_line, vec!["seeds:", "79", "14", "55", "13"]);
     }
 
     #[test]
     fn test_get_part_one_seeds() {
-        let part_one_seeds = get_part_one_seeds(&String::from("seeds: 79 14 55 13"));
+        let part_one_seeds = get_part_one_seeds("seeds: 79 14 55 13");
 
-        assert_eq!(part_one_seeds.len(), 4);
-        assert_eq!(part_one_seeds[0], 79);
-        assert_eq!(part_one_seeds[1], 14);
-        assert_eq!(part_one_seeds[2], 55);
-        assert_eq!(part_one_seeds[3], 13);
+        assert_eq!(part_one_seeds, vec![79, 14, 55, 13]);
     }
 
     #[test]
     fn test_get_mapped_value() {
-        let map = HashMap::from([(Range { start: 1, end: 3 }, Range { start: 20, end: 22 })]);
+        let map = PiecewiseMap::from_layer("test", &[(Range { start: 1, end: 3 }, 20)]).unwrap();
 
-        assert_eq!(get_mapped_value(0, &map), 0);
-        assert_eq!(get_mapped_value(1, &map), 20);
-        assert_eq!(get_mapped_value(2, &map), 21);
-        assert_eq!(get_mapped_value(3, &map), 3);
+        assert_eq!(map.apply(0), 0);
+        assert_eq!(map.apply(1), 20);
+        assert_eq!(map.apply(2), 21);
+        assert_eq!(map.apply(3), 3);
     }
 
     #[test]
     fn test_get_part_two_seeds() {
-        let part_two_seeds = get_part_two_seeds(&String::from("seeds: 79 14 55 13"));
+        let part_two_seeds = get_part_two_seeds("seeds: 79 14 55 13");
+
+        assert_eq!(
+            part_two_seeds,
+            vec![Range { start: 79, end: 93 }, Range { start: 55, end: 68 }]
+        );
+    }
 
-        assert_eq!(part_two_seeds.len(), 2);
-        assert_eq!(part_two_seeds[0].start, 79);
-        assert_eq!(part_two_seeds[0].end, 93);
-        assert_eq!(part_two_seeds[1].start, 55);
-        assert_eq!(part_two_seeds[1].end, 68);
+    #[test]
+    fn test_composition_matches_layers() {
+        let almanac = Almanac::parse(&get_test_lines()).unwrap();
+        let seed_to_location = almanac.seed_to_location();
+
+        for seed in 0..200 {
+            let location = almanac
+                .layers
+                .iter()
+                .fold(seed, |value, (_, layer)| layer.apply(value));
+            assert_eq!(seed_to_location.apply(seed), location);
+        }
+        assert_eq!(almanac.layers[0].0, "seed-to-soil");
+    }
+
+    #[test]
+    fn test_reverse() {
+        let seed_to_location = Almanac::parse(&get_test_lines())
+            .unwrap()
+            .seed_to_location();
+
+        assert_eq!(seed_to_location.reverse(35), vec![13]);
+        assert_eq!(seed_to_location.reverse(46), vec![82]);
+        for location in 0..200 {
+            for seed in seed_to_location.reverse(location) {
+                assert_eq!(seed_to_location.apply(seed), location);
+            }
+        }
+
+        // Both 0 and 5 end up at 5
+        let map = PiecewiseMap::from_layer("test", &[(Range { start: 0, end: 1 }, 5)]).unwrap();
+        assert_eq!(map.reverse(5), vec![0, 5]);
+        assert_eq!(map.reverse(0), vec![]);
+    }
+
+    #[test]
+    fn test_overlapping_sources() {
+        let lines: Vec<String> = ["seeds: 1", "a-to-b map:", "10 0 5", "20 4 2"]
+            .iter()
+            .map(|line| String::from(*line))
+            .collect();
+
+        assert!(matches!(
+            Almanac::parse(&lines),
+            Err(AlmanacError::OverlappingSources {
+                first: Range { start: 0, end: 5 },
+                second: Range { start: 4, end: 6 },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_mappings() {
+        let parse = |line: &str| {
+            let lines = vec![
+                String::from("seeds: 1"),
+                String::from("a-to-b map:"),
+                String::from(line),
+            ];
+            Almanac::parse(&lines).err()
+        };
+
+        assert_eq!(
+            parse("1 2"),
+            Some(AlmanacError::InvalidMapping(String::from("1 2")))
+        );
+        assert_eq!(
+            parse("0 18446744073709551615 2"),
+            Some(AlmanacError::InvalidMapping(String::from(
+                "0 18446744073709551615 2"
+            )))
+        );
+        assert!(Almanac::parse(&[String::from("a-to-b map:")]).is_err());
     }
 }
@@ -1,173 +1,254 @@
-use std::u64::MAX;
+use std::ops::RangeInclusive;
 
-use lazy_static::lazy_static;
-use library::{get_filename_arg, get_lines};
-use regex::Regex;
+use library::num::isqrt;
+use library::{get_filename_and_options, get_lines};
 
 fn main() {
-    let file_name = get_filename_arg();
+    // --intervals prints the range of hold times that beat the record in each race
+    let (file_name, options) = get_filename_and_options(&["intervals"]);
     let lines = get_lines(&file_name);
     let part_one_times = get_numbers(&lines[0]);
     let part_two_distances = get_numbers(&lines[1]);
     let part_one_races = get_boat_races(part_one_times, part_two_distances);
-
-    let part_one = get_part_one_product(part_one_races);
-    println!("Part 1: {}", part_one);
-
     let part_two_race = BoatRace {
         time: get_single_number(&lines[0]),
         distance: get_single_number(&lines[1]),
     };
-    let part_two = get_win_count(part_two_race);
-    println!("Part 2: {}", part_two);
-}
 
-fn distance(time: u64, max_time: u64) -> u64 {
-    return (max_time - time) * time;
+    if options.is_set("intervals") {
+        for race in part_one_races.iter().chain([&part_two_race]) {
+            match race.winning_holds() {
+                Some(holds) => println!(
+                    "Time {}, record {}: hold for {} to {} ms",
+                    race.time,
+                    race.distance,
+                    holds.start(),
+                    holds.end()
+                ),
+                None => println!(
+                    "Time {}, record {}: the record can not be beaten",
+                    race.time, race.distance
+                ),
+            }
+        }
+    }
+
+    let part_one = get_part_one_product(&part_one_races);
+    println!("Part 1: {}", part_one);
+
+    let part_two = part_two_race.win_count();
+    println!("Part 2: {}", part_two);
 }
 
-lazy_static! {
-    static ref REGEX: Regex = Regex::new(r"\d+").unwrap();
+// None if the distance is too large for a u128, which is further than any record
+fn distance(time: u128, max_time: u128) -> Option<u128> {
+    time.checked_mul(max_time - time)
 }
 
-fn get_numbers(line: &String) -> Vec<u64> {
-    REGEX
-        .find_iter(line)
-        .map(|n| {
-            n.as_str()
-                .parse()
-                .expect("Could not parse string to number")
-        })
+fn get_numbers(line: &str) -> Vec<u128> {
+    line.split_whitespace()
+        .skip(1)
+        .map(|n| n.parse().expect("Could not parse string to number"))
         .collect()
 }
 
-fn get_single_number(line: &String) -> u64 {
-    REGEX
-        .find_iter(line)
-        .map(|n| n.as_str())
+// The numbers on the line with the spaces between them taken out
+fn get_single_number(line: &str) -> u128 {
+    line.split_whitespace()
+        .skip(1)
         .collect::<Vec<&str>>()
         .join("")
         .parse()
         .expect("Failed to parse line into number")
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 struct BoatRace {
-    time: u64,
-    distance: u64,
+    time: u128,
+    distance: u128,
 }
 
-fn get_boat_races(times: Vec<u64>, distances: Vec<u64>) -> Vec<BoatRace> {
-    assert_eq!(times.len(), distances.len());
-
-    let mut boat_races: Vec<BoatRace> = vec![];
-    for i in 0..times.len() {
-        boat_races.push(BoatRace {
-            time: times[i],
-            distance: distances[i],
-        });
+impl BoatRace {
+    fn wins(&self, hold: u128) -> bool {
+        distance(hold, self.time).is_none_or(|travelled| travelled > self.distance)
     }
 
-    boat_races
-}
-
-fn get_win_count(boat_race: BoatRace) -> u64 {
-    let mut min = MAX;
-    let mut max = 0;
+    /* Holding for h ms travels h * (time - h), so the winning holds are the integers strictly between the roots of
+     * h^2 - time * h + distance = 0, which are (time +- sqrt(time^2 - 4 * distance)) / 2. The integer square root can be
+     * off from the real one by less than one, so the estimate of the shortest hold is nudged onto the exact boundary,
+     * and the longest hold mirrors it around time / 2. When time^2 does not fit in a u128 the boundary is found with a
+     * binary search instead, since the distance travelled only rises up to time / 2.
+     */
+    fn winning_holds(&self) -> Option<RangeInclusive<u128>> {
+        let half = self.time / 2;
+        if !self.wins(half) {
+            return None;
+        }
 
-    for i in 0..boat_race.time {
-        if distance(i, boat_race.time) > boat_race.distance {
-            if min == MAX {
-                min = i;
+        let discriminant = self
+            .time
+            .checked_mul(self.time)
+            .zip(self.distance.checked_mul(4))
+            .map(|(square, four_distance)| square - four_distance);
+        let shortest = match discriminant {
+            Some(discriminant) => {
+                let mut shortest = (self.time - isqrt(discriminant)) / 2;
+                while !self.wins(shortest) {
+                    shortest += 1;
+                }
+                while shortest > 0 && self.wins(shortest - 1) {
+                    shortest -= 1;
+                }
+                shortest
             }
-        } else if min != MAX {
-            max = i;
-            break;
-        }
+            None => {
+                let (mut low, mut high) = (0, half);
+                while low < high {
+                    let middle = low + (high - low) / 2;
+                    if self.wins(middle) {
+                        high = middle;
+                    } else {
+                        low = middle + 1;
+                    }
+                }
+                low
+            }
+        };
+
+        Some(shortest..=self.time - shortest)
     }
 
-    max - min
+    fn win_count(&self) -> u128 {
+        self.winning_holds()
+            .map_or(0, |holds| holds.end() - holds.start() + 1)
+    }
 }
 
-fn get_part_one_product(races: Vec<BoatRace>) -> u64 {
-    let mut product = 1;
-    for race in races {
-        let ways = get_win_count(race);
-        product *= ways;
-    }
+fn get_boat_races(times: Vec<u128>, distances: Vec<u128>) -> Vec<BoatRace> {
+    assert_eq!(times.len(), distances.len());
+
+    times
+        .into_iter()
+        .zip(distances)
+        .map(|(time, distance)| BoatRace { time, distance })
+        .collect()
+}
 
-    product
+fn get_part_one_product(races: &[BoatRace]) -> u128 {
+    races.iter().map(|race| race.win_count()).product()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn get_brute_force_win_count(race: &BoatRace) -> u128 {
+        (0..=race.time).filter(|&hold| race.wins(hold)).count() as u128
+    }
+
     #[test]
     fn test_distance() {
-        assert_eq!(distance(0, 7), 0);
-        assert_eq!(distance(1, 7), 6);
-        assert_eq!(distance(2, 7), 10);
-        assert_eq!(distance(3, 7), 12);
-        assert_eq!(distance(4, 7), 12);
-        assert_eq!(distance(5, 7), 10);
-        assert_eq!(distance(6, 7), 6);
-        assert_eq!(distance(7, 7), 0);
+        assert_eq!(distance(0, 7), Some(0));
+        assert_eq!(distance(1, 7), Some(6));
+        assert_eq!(distance(2, 7), Some(10));
+        assert_eq!(distance(3, 7), Some(12));
+        assert_eq!(distance(4, 7), Some(12));
+        assert_eq!(distance(5, 7), Some(10));
+        assert_eq!(distance(6, 7), Some(6));
+        assert_eq!(distance(7, 7), Some(0));
+        assert_eq!(distance(u128::MAX / 2, u128::MAX), None);
     }
 
     #[test]
     fn test_get_numbers() {
-        let times_string = String::from("Time:      7  15   30");
-        let times = get_numbers(&times_string);
+        let times = get_numbers("Time:      7  15   30");
 
-        assert_eq!(times.len(), 3);
-        assert_eq!(times[0], 7);
-        assert_eq!(times[1], 15);
-        assert_eq!(times[2], 30);
+        assert_eq!(times, vec![7, 15, 30]);
     }
 
     #[test]
     fn test_get_boat_races() {
-        let times = vec![0, 1];
-        let distances = vec![2, 3];
-        let boat_races = get_boat_races(times, distances);
-
-        assert_eq!(boat_races.len(), 2);
-        assert_eq!(boat_races[0].time, 0);
-        assert_eq!(boat_races[0].distance, 2);
-        assert_eq!(boat_races[1].time, 1);
-        assert_eq!(boat_races[1].distance, 3);
+        let boat_races = get_boat_races(vec![0, 1], vec![2, 3]);
+
+        assert_eq!(
+            boat_races,
+            vec![
+                BoatRace {
+                    time: 0,
+                    distance: 2
+                },
+                BoatRace {
+                    time: 1,
+                    distance: 3
+                }
+            ]
+        );
     }
 
     #[test]
     fn test_get_win_count() {
-        assert_eq!(
-            get_win_count(BoatRace {
-                time: 7,
-                distance: 9
-            }),
-            4
-        );
-        assert_eq!(
-            get_win_count(BoatRace {
-                time: 15,
-                distance: 40
-            }),
-            8
-        );
-        assert_eq!(
-            get_win_count(BoatRace {
-                time: 30,
-                distance: 200
-            }),
-            9
-        );
+        let win_count = |time, distance| BoatRace { time, distance }.win_count();
+
+        assert_eq!(win_count(7, 9), 4);
+        assert_eq!(win_count(15, 40), 8);
+        assert_eq!(win_count(30, 200), 9);
+        assert_eq!(win_count(71530, 940200), 71503);
     }
 
     #[test]
     fn test_get_single_number() {
+        assert_eq!(get_single_number("Time:      7  15   30"), 71530);
+    }
+
+    #[test]
+    fn test_winning_holds() {
+        let race = BoatRace {
+            time: 7,
+            distance: 9,
+        };
+
+        assert_eq!(race.winning_holds(), Some(2..=5));
+        // Holding for 3 ms exactly matches the record rather than beating it
         assert_eq!(
-            get_single_number(&String::from("Time:      7  15   30")),
-            71530
+            BoatRace {
+                time: 6,
+                distance: 9
+            }
+            .winning_holds(),
+            None
         );
     }
+
+    #[test]
+    fn test_matches_brute_force() {
+        for time in 0..80 {
+            for distance in 0..=time * time / 4 + 2 {
+                let race = BoatRace { time, distance };
+                assert_eq!(
+                    race.win_count(),
+                    get_brute_force_win_count(&race),
+                    "{:?}",
+                    race
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_races() {
+        let races = [
+            (u64::MAX as u128, 1 << 100),
+            ((u64::MAX as u128) + 1, 1 << 120),
+            (u128::MAX, u128::MAX - 1),
+            (u128::MAX, 0),
+            (1 << 100, u128::MAX),
+        ];
+        for (time, distance) in races {
+            let race = BoatRace { time, distance };
+            let holds = race.winning_holds().unwrap();
+
+            assert!(race.wins(*holds.start()) && race.wins(*holds.end()));
+            assert!(!race.wins(holds.start() - 1) && !race.wins(holds.end() + 1));
+        }
+    }
 }
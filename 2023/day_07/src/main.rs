@@ -1,15 +1,12 @@
-use std::{cmp::Ordering, collections::HashMap};
-
-use lazy_static::lazy_static;
 use library::{get_filename_arg, get_lines};
 
 fn main() {
     let file_name = get_filename_arg();
     let lines = get_lines(&file_name);
-    let mut hands = get_hands(lines);
+    let hands = get_hands(lines);
 
-    println!("Part 1: {}", get_sum(&mut hands, part_one_compare_cards));
-    println!("Part 2: {}", get_sum(&mut hands, part_two_compare_cards));
+    println!("Part 1: {}", get_sum(&hands, &HandRules::part_one()));
+    println!("Part 2: {}", get_sum(&hands, &HandRules::part_two()));
 }
 
 struct Hand {
@@ -30,175 +27,122 @@ fn get_hands(lines: Vec<String>) -> Vec<Hand> {
     hands
 }
 
-fn get_hand_histogram(cards: &String) -> HashMap<char, u8> {
-    let mut histogram = HashMap::from([
-        ('A', 0),
-        ('K', 0),
-        ('Q', 0),
-        ('T', 0),
-        ('9', 0),
-        ('8', 0),
-        ('7', 0),
-        ('6', 0),
-        ('5', 0),
-        ('4', 0),
-        ('3', 0),
-        ('2', 0),
-        ('J', 0),
-    ]);
-
-    for card in cards.chars() {
-        histogram.insert(
-            card,
-            histogram
-                .get(&card)
-                .expect(format!("Invalid card value found: {}", card).as_str())
-                + 1,
-        );
-    }
-
-    histogram
+const MAX_CARD_VALUES: usize = 16;
+const MAX_HAND_SIZE: usize = 15;
+
+/* Everything that differs between variants of the game. `card_order` lists the cards from lowest to highest,
+* `wildcards` are left out of the histogram handed to the classifier and passed as a separate count instead, and
+* `classifier` turns the histogram into a hand type where a larger value beats a smaller one.
+*/
+struct HandRules {
+    card_order: Vec<char>,
+    wildcards: Vec<char>,
+    classifier: fn(&[u8], u8) -> u8,
 }
 
-fn score_helper(counts: Vec<u8>) -> i64 {
-    if counts.contains(&5) {
-        return 6;
-    }
-    if counts.contains(&4) {
-        return 5;
-    }
-    if counts.contains(&3) {
-        if counts.contains(&2) {
-            return 4;
+impl HandRules {
+    fn new(card_order: &str, wildcards: &str, classifier: fn(&[u8], u8) -> u8) -> Self {
+        let card_order: Vec<char> = card_order.chars().collect();
+        if card_order.len() > MAX_CARD_VALUES {
+            panic!("At most {} card values are supported", MAX_CARD_VALUES);
+        }
+
+        Self {
+            card_order,
+            wildcards: wildcards.chars().collect(),
+            classifier,
         }
-        return 3;
     }
 
-    let two_count: usize = counts
-        .into_iter()
-        .filter(|value| *value == 2)
-        .collect::<Vec<u8>>()
-        .len();
-    match two_count {
-        2 => 2,
-        1 => 1,
-        _ => 0,
+    fn part_one() -> Self {
+        Self::new("23456789TJQKA", "", classify)
     }
-}
 
-fn part_one_hand_score(cards: &String) -> i64 {
-    let histogram = get_hand_histogram(cards);
-    let counts = histogram.values().cloned().collect();
-    score_helper(counts)
-}
+    fn part_two() -> Self {
+        Self::new("J23456789TQKA", "J", classify)
+    }
 
-fn part_two_hand_score(cards: &String) -> i64 {
-    let mut histogram = get_hand_histogram(cards);
-    let wild_count = histogram.get(&'J').unwrap().clone();
-    histogram.insert('J', 0);
-
-    let max_key = histogram
-        .keys()
-        .into_iter()
-        .reduce(|a, b| {
-            if histogram.get(a).unwrap() > histogram.get(b).unwrap() {
-                a
+    fn card_value(&self, card: char) -> usize {
+        self.card_order
+            .iter()
+            .position(|&c| c == card)
+            .unwrap_or_else(|| panic!("Invalid card value found: {}", card))
+    }
+
+    fn hand_type(&self, cards: &str) -> u8 {
+        let mut histogram = [0u8; MAX_CARD_VALUES];
+        let mut wild_count = 0;
+        for card in cards.chars() {
+            let value = self.card_value(card);
+            if self.wildcards.contains(&card) {
+                wild_count += 1;
             } else {
-                b
+                histogram[value] += 1;
             }
-        })
-        .unwrap();
-    histogram.insert(*max_key, histogram.get(max_key).unwrap() + wild_count);
-
-    let counts = histogram.values().cloned().collect();
-    score_helper(counts)
-}
-
-lazy_static! {
-    static ref PART_ONE_HIGH_CARD: HashMap<char, i8> = HashMap::from([
-        ('A', 12),
-        ('K', 11),
-        ('Q', 10),
-        ('J', 9),
-        ('T', 8),
-        ('9', 7),
-        ('8', 6),
-        ('7', 5),
-        ('6', 4),
-        ('5', 3),
-        ('4', 2),
-        ('3', 1),
-        ('2', 0),
-    ]);
-}
-
-lazy_static! {
-    static ref PART_TWO_HIGH_CARD: HashMap<char, i8> = HashMap::from([
-        ('A', 12),
-        ('K', 11),
-        ('Q', 10),
-        ('T', 9),
-        ('9', 8),
-        ('8', 7),
-        ('7', 6),
-        ('6', 5),
-        ('5', 4),
-        ('4', 3),
-        ('3', 2),
-        ('2', 1),
-        ('J', 0),
-    ]);
-}
+        }
 
-fn compare_cards(
-    a: &String,
-    b: &String,
-    hand_score: fn(&String) -> i64,
-    high_card: &HashMap<char, i8>,
-) -> Ordering {
-    let difference = hand_score(a) - hand_score(b);
-    if difference > 0 {
-        return Ordering::Greater;
-    }
-    if difference < 0 {
-        return Ordering::Less;
+        let mut counts: Vec<u8> = histogram.into_iter().filter(|&count| count > 0).collect();
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        (self.classifier)(&counts, wild_count)
     }
-    for i in 0..a.len() {
-        let difference = high_card.get(&a.chars().nth(i).unwrap()).unwrap()
-            - high_card.get(&b.chars().nth(i).unwrap()).unwrap();
-        if difference > 0 {
-            return Ordering::Greater;
+
+    /* Packs each card's value four bits at a time below a field of its own for the hand type, so comparing keys
+     * compares hands for any hand type the classifier returns.
+     */
+    fn key(&self, cards: &str) -> u128 {
+        if cards.chars().count() > MAX_HAND_SIZE {
+            panic!("At most {} cards per hand are supported", MAX_HAND_SIZE);
         }
-        if difference < 0 {
-            return Ordering::Less;
+
+        let mut card_values = 0;
+        for card in cards.chars() {
+            card_values = (card_values << 4) | self.card_value(card) as u128;
         }
+        card_values <<= 4 * (MAX_HAND_SIZE - cards.chars().count());
+        ((self.hand_type(cards) as u128) << (4 * MAX_HAND_SIZE)) | card_values
     }
-
-    Ordering::Equal
-}
-
-fn part_one_compare_cards(a: &String, b: &String) -> Ordering {
-    compare_cards(a, b, part_one_hand_score, &PART_ONE_HIGH_CARD)
 }
 
-fn part_two_compare_cards(a: &String, b: &String) -> Ordering {
-    compare_cards(a, b, part_two_hand_score, &PART_TWO_HIGH_CARD)
+// Standard ranking from five of a kind down to high card, with every wildcard joining the largest group
+fn classify(counts: &[u8], wild_count: u8) -> u8 {
+    let largest = counts.first().copied().unwrap_or(0) + wild_count;
+    let second = counts.get(1).copied().unwrap_or(0);
+
+    match (largest, second) {
+        (5.., _) => 6,
+        (4, _) => 5,
+        (3, 2) => 4,
+        (3, _) => 3,
+        (2, 2) => 2,
+        (2, _) => 1,
+        _ => 0,
+    }
 }
 
-fn get_sum(hands: &mut Vec<Hand>, comparison: fn(&String, &String) -> Ordering) -> u64 {
-    hands.sort_by(|a, b| comparison(&a.cards, &b.cards));
-    let mut sum = 0;
-    for i in 0..hands.len() {
-        let rank = i as u64 + 1;
-        sum += rank * hands[i].bid;
-    }
-    sum
+fn get_sum(hands: &[Hand], rules: &HandRules) -> u64 {
+    let mut ranked: Vec<(u128, u64)> = hands
+        .iter()
+        .map(|hand| (rules.key(&hand.cards), hand.bid))
+        .collect();
+    ranked.sort_unstable();
+
+    ranked
+        .iter()
+        .enumerate()
+        .map(|(i, (_, bid))| (i as u64 + 1) * bid)
+        .sum()
 }
 
 #[cfg(test)]
 mod tests {
+    use std::cmp::Ordering;
+
     use super::*;
 
+    fn compare(rules: &HandRules, a: &str, b: &str) -> Ordering {
+        rules.key(a).cmp(&rules.key(b))
+    }
+
     #[test]
     fn test_get_hands() {
         let lines = vec![String::from("32T3K 765"), String::from("KK677 28")];
@@ -212,108 +156,118 @@ mod tests {
     }
 
     #[test]
-    fn test_get_hand_histogram() {
-        let cards = String::from("AKQT98765432J");
-        let histogram = get_hand_histogram(&cards);
+    fn test_card_value() {
+        let rules = HandRules::part_one();
 
-        for key in histogram.keys() {
-            assert_eq!(histogram.get(key), Some(&1));
+        for (i, card) in "23456789TJQKA".chars().enumerate() {
+            assert_eq!(rules.card_value(card), i);
         }
+        assert_eq!(HandRules::part_two().card_value('J'), 0);
     }
 
     #[test]
     #[should_panic(expected = "Invalid card value found: f")]
-    fn test_get_hand_histogram_invalid_card() {
-        let cards = String::from("f");
-        let _histogram = get_hand_histogram(&cards);
+    fn test_card_value_invalid_card() {
+        HandRules::part_one().key("f");
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(&[5], 0), 6);
+        assert_eq!(classify(&[4, 1], 0), 5);
+        assert_eq!(classify(&[3, 2], 0), 4);
+        assert_eq!(classify(&[3, 1, 1], 0), 3);
+        assert_eq!(classify(&[2, 2, 1], 0), 2);
+        assert_eq!(classify(&[2, 1, 1, 1], 0), 1);
+        assert_eq!(classify(&[1, 1, 1, 1, 1], 0), 0);
+        assert_eq!(classify(&[], 5), 6);
+        assert_eq!(classify(&[2, 2], 1), 4);
     }
 
     #[test]
-    fn test_score_helper() {
-        let six_score_counts = vec![0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0];
-        let five_score_counts = vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0];
-        let four_score_counts = vec![0, 0, 2, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0];
-        let three_score_counts = vec![0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0, 3];
-        let two_score_counts = vec![1, 0, 2, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0];
-        let one_score_counts = vec![0, 0, 1, 0, 0, 0, 0, 2, 0, 1, 1, 0, 0];
-        let zero_score_counts = vec![0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
-
-        assert_eq!(score_helper(six_score_counts), 6);
-        assert_eq!(score_helper(five_score_counts), 5);
-        assert_eq!(score_helper(four_score_counts), 4);
-        assert_eq!(score_helper(three_score_counts), 3);
-        assert_eq!(score_helper(two_score_counts), 2);
-        assert_eq!(score_helper(one_score_counts), 1);
-        assert_eq!(score_helper(zero_score_counts), 0);
+    fn test_part_one_hand_type() {
+        let rules = HandRules::part_one();
+
+        assert_eq!(rules.hand_type("AAAAA"), 6);
+        assert_eq!(rules.hand_type("AA8AA"), 5);
+        assert_eq!(rules.hand_type("23332"), 4);
+        assert_eq!(rules.hand_type("TTT98"), 3);
+        assert_eq!(rules.hand_type("23432"), 2);
+        assert_eq!(rules.hand_type("A23A4"), 1);
+        assert_eq!(rules.hand_type("23456"), 0);
+        assert_eq!(rules.hand_type("JJ234"), 1);
     }
 
     #[test]
-    fn test_part_one_hand_score() {
-        assert_eq!(part_one_hand_score(&String::from("AAAAA")), 6);
-        assert_eq!(part_one_hand_score(&String::from("AA8AA")), 5);
-        assert_eq!(part_one_hand_score(&String::from("23332")), 4);
-        assert_eq!(part_one_hand_score(&String::from("TTT98")), 3);
-        assert_eq!(part_one_hand_score(&String::from("23432")), 2);
-        assert_eq!(part_one_hand_score(&String::from("A23A4")), 1);
-        assert_eq!(part_one_hand_score(&String::from("23456")), 0);
+    fn test_part_two_hand_type() {
+        let rules = HandRules::part_two();
+
+        assert_eq!(rules.hand_type("JJJAA"), 6);
+        assert_eq!(rules.hand_type("JJ8AA"), 5);
+        assert_eq!(rules.hand_type("233J2"), 4);
+        assert_eq!(rules.hand_type("TTJ98"), 3);
+        assert_eq!(rules.hand_type("23432"), 2);
+        assert_eq!(rules.hand_type("J23A4"), 1);
+        assert_eq!(rules.hand_type("23456"), 0);
+        assert_eq!(rules.hand_type("JJJJJ"), 6);
     }
 
     #[test]
-    fn test_part_two_hand_score() {
-        assert_eq!(part_two_hand_score(&String::from("JJJAA")), 6);
-        assert_eq!(part_two_hand_score(&String::from("JJ8AA")), 5);
-        assert_eq!(part_two_hand_score(&String::from("233J2")), 4);
-        assert_eq!(part_two_hand_score(&String::from("TTJ98")), 3);
-        assert_eq!(part_two_hand_score(&String::from("23432")), 2);
-        assert_eq!(part_two_hand_score(&String::from("J23A4")), 1);
-        assert_eq!(part_two_hand_score(&String::from("23456")), 0);
+    fn test_part_one_compare() {
+        let rules = HandRules::part_one();
+
+        assert_eq!(compare(&rules, "T55J5", "KTJJT"), Ordering::Greater);
+        assert_eq!(compare(&rules, "32T3K", "KK677"), Ordering::Less);
+        assert_eq!(compare(&rules, "QQQJA", "T55J5"), Ordering::Greater);
+        assert_eq!(compare(&rules, "KTJJT", "KK677"), Ordering::Less);
+        assert_eq!(compare(&rules, "KTJJT", "KTJJT"), Ordering::Equal);
     }
 
     #[test]
-    fn test_part_one_compare_cards() {
-        assert_eq!(
-            part_one_compare_cards(&String::from("T55J5"), &String::from("KTJJT")),
-            Ordering::Greater
-        );
-        assert_eq!(
-            part_one_compare_cards(&String::from("32T3K"), &String::from("KK677")),
-            Ordering::Less
-        );
-        assert_eq!(
-            part_one_compare_cards(&String::from("QQQJA"), &String::from("T55J5")),
-            Ordering::Greater
-        );
-        assert_eq!(
-            part_one_compare_cards(&String::from("KTJJT"), &String::from("KK677")),
-            Ordering::Less
-        );
-        assert_eq!(
-            part_one_compare_cards(&String::from("KTJJT"), &String::from("KTJJT")),
-            Ordering::Equal
-        );
+    fn test_part_two_compare() {
+        let rules = HandRules::part_two();
+
+        assert_eq!(compare(&rules, "KTJJT", "KK677"), Ordering::Greater);
+        assert_eq!(compare(&rules, "32T3K", "KK677"), Ordering::Less);
+        assert_eq!(compare(&rules, "KTJJT", "QQQJA"), Ordering::Greater);
+        assert_eq!(compare(&rules, "T55J5", "QQQJA"), Ordering::Less);
+        assert_eq!(compare(&rules, "KTJJT", "KTJJT"), Ordering::Equal);
     }
 
     #[test]
-    fn test_part_two_compare_cards() {
-        assert_eq!(
-            part_two_compare_cards(&String::from("KTJJT"), &String::from("KK677")),
-            Ordering::Greater
-        );
-        assert_eq!(
-            part_two_compare_cards(&String::from("32T3K"), &String::from("KK677")),
-            Ordering::Less
-        );
-        assert_eq!(
-            part_two_compare_cards(&String::from("KTJJT"), &String::from("QQQJA")),
-            Ordering::Greater
-        );
-        assert_eq!(
-            part_two_compare_cards(&String::from("T55J5"), &String::from("QQQJA")),
-            Ordering::Less
-        );
-        assert_eq!(
-            part_two_compare_cards(&String::from("KTJJT"), &String::from("KTJJT")),
-            Ordering::Equal
-        );
+    fn test_get_sum() {
+        let hands = get_hands(get_lines("resources/test_input.txt"));
+
+        assert_eq!(get_sum(&hands, &HandRules::part_one()), 6440);
+        assert_eq!(get_sum(&hands, &HandRules::part_two()), 5905);
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        // Aces low with both jacks and twos wild
+        let rules = HandRules::new("A23456789TJQK", "J2", classify);
+
+        assert_eq!(rules.hand_type("J2AKQ"), 3);
+        assert_eq!(compare(&rules, "A3457", "K3457"), Ordering::Less);
+
+        // Jacks are left out of the groups and the classifier ignores them, so even a pair of jacks counts for nothing
+        let rules = HandRules::new("23456789TJQKA", "J", |counts, _| classify(counts, 0));
+        assert_eq!(rules.hand_type("JJ234"), 0);
+        assert_eq!(compare(&rules, "JJ234", "22345"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_large_hand_types() {
+        // Scores each hand by the square of its largest group, which goes well past four bits
+        let rules = HandRules::new("23456789TJQKA", "", |counts, _| {
+            counts.first().map_or(0, |&count| count * count * 10)
+        });
+
+        assert_eq!(rules.hand_type("22222"), 250);
+        assert_eq!(rules.hand_type("2222A"), 160);
+        assert_eq!(compare(&rules, "22222", "AAAAK"), Ordering::Greater);
+        assert_eq!(compare(&rules, "2222A", "AAA23"), Ordering::Greater);
+        assert_eq!(compare(&rules, "2222A", "3333A"), Ordering::Less);
+        assert_eq!(rules.key("22222") >> (4 * MAX_HAND_SIZE), 250);
     }
 }
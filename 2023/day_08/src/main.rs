@@ -1,81 +1,130 @@
+use library::intern::Interner;
+use library::num::crt;
 use library::{get_filename_arg, get_lines};
-use std::collections::HashMap;
 
 fn main() {
     let file_name = get_filename_arg();
     let lines = get_lines(&file_name);
     let right_left: Vec<char> = lines[0].replace("\n", "").chars().collect();
     let map_lines: Vec<String> = lines[2..lines.len()]
-        .into_iter()
+        .iter()
         .map(|f| f.replace("\n", ""))
         .collect();
 
-    let mappings = get_mappings(map_lines);
+    let network = get_mappings(map_lines);
     println!(
         "Part 1: {}",
-        get_distance_to_end(&String::from("AAA"), &right_left, &mappings, |f| f == "ZZZ")
+        get_distance_to_end("AAA", &right_left, &network, |f| f == "ZZZ")
     );
 
-    let mut distances = vec![];
-    for key in mappings.keys() {
-        if key.chars().collect::<Vec<char>>()[2] == 'A' {
-            distances.push(get_distance_to_end(key, &right_left, &mappings, |f| {
-                f.contains("Z")
-            }));
+    let analyses: Vec<WalkAnalysis> = network
+        .keys()
+        .filter(|key| key.ends_with('A'))
+        .map(|key| analyze_walk(key, &right_left, &network, |f| f.ends_with('Z')))
+        .collect();
+
+    if analyses
+        .iter()
+        .all(|analysis| analysis.supports_lcm_shortcut())
+    {
+        let cycle_lengths = analyses
+            .iter()
+            .map(|analysis| analysis.cycle_length)
+            .collect();
+        println!("Part 2: {}", least_common_multiple(cycle_lengths));
+    } else {
+        match earliest_common_hit(&analyses) {
+            Some(steps) => println!("Part 2: {}", steps),
+            None => println!("Part 2: The ghosts never all reach end nodes at once"),
         }
     }
-
-    println!("Part 2: {}", least_common_multiple(distances));
 }
 
 struct Node {
-    l: String,
-    r: String,
+    l: u32,
+    r: u32,
+}
+
+/* Nodes are interned so the walks can step through plain vectors. Every key gets its id before any of the targets
+* are interned, so the ids 0..nodes.len() are exactly the nodes that have a mapping.
+*/
+struct Network {
+    names: Interner,
+    nodes: Vec<Node>,
 }
 
-fn get_mappings(lines: Vec<String>) -> HashMap<String, Node> {
-    let mut mappings = HashMap::new();
+impl Network {
+    fn id(&self, key: &str) -> u32 {
+        self.names.get(key).expect("No node found for key")
+    }
+
+    fn node(&self, id: u32) -> &Node {
+        self.nodes.get(id as usize).expect("No node found for key")
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &str> {
+        (0..self.nodes.len() as u32).map(|id| self.names.name(id))
+    }
 
-    for line in lines {
+    // Evaluates the end condition once per node rather than once per step
+    fn end_nodes<F>(&self, end_condition: F) -> Vec<bool>
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.names
+            .iter()
+            .map(|(_, name)| end_condition(name))
+            .collect()
+    }
+}
+
+fn get_mappings(lines: Vec<String>) -> Network {
+    let mut names = Interner::new();
+    let mut targets = vec![];
+
+    for line in &lines {
         let split: Vec<&str> = line.split(" = (").collect();
-        let key = split[0].to_string();
+        names.intern(split[0]);
         let values: Vec<&str> = split[1].split(", ").collect();
-        mappings.insert(
-            key,
-            Node {
-                l: values[0].to_string(),
-                r: values[1].to_string().replace(")", ""),
-            },
-        );
+        targets.push((values[0].to_string(), values[1].replace(")", "")));
     }
 
-    mappings
+    let nodes = targets
+        .iter()
+        .map(|(l, r)| Node {
+            l: names.intern(l),
+            r: names.intern(r),
+        })
+        .collect();
+
+    Network { names, nodes }
 }
 
 fn get_distance_to_end<F>(
-    key: &String,
-    right_left: &Vec<char>,
-    mappings: &HashMap<String, Node>,
+    key: &str,
+    right_left: &[char],
+    network: &Network,
     end_condition: F,
 ) -> usize
 where
-    F: Fn(&String) -> bool,
+    F: Fn(&str) -> bool,
 {
+    let is_end = network.end_nodes(end_condition);
     let mut count = 0;
-    let mut current_key: &String = key;
+    let mut current = network.id(key);
 
     loop {
-        if end_condition(current_key) {
+        if is_end[current as usize] {
             break;
         }
 
         let direction = right_left[count % right_left.len()];
-        let next_node = mappings.get(current_key).expect("No node found for key");
+        let next_node = network.node(current);
 
-        current_key = if direction == 'L' {
-            &next_node.l
+        current = if direction == 'L' {
+            next_node.l
         } else if direction == 'R' {
-            &next_node.r
+            next_node.r
         } else {
             panic!("No node found for key");
         };
@@ -86,6 +135,140 @@ where
     count
 }
 
+/* Where the walk from one start ends up. The walk is in a loop as soon as a (node, instruction index) state repeats,
+* so it visits `prefix_length` states once and then repeats the next `cycle_length` states forever. `hits` holds every
+* step within those first prefix_length + cycle_length steps at which it is on an end node.
+*/
+#[derive(Debug, PartialEq)]
+struct WalkAnalysis {
+    prefix_length: usize,
+    cycle_length: usize,
+    hits: Vec<usize>,
+}
+
+impl WalkAnalysis {
+    fn is_hit(&self, step: usize) -> bool {
+        let step = if step < self.prefix_length + self.cycle_length {
+            step
+        } else {
+            self.prefix_length + (step - self.prefix_length) % self.cycle_length
+        };
+        self.hits.contains(&step)
+    }
+
+    fn cyclic_hits(&self) -> impl Iterator<Item = &usize> {
+        self.hits.iter().filter(|&&hit| hit >= self.prefix_length)
+    }
+
+    /* The shortcut only works when the one end node in the loop is reached exactly every cycle_length steps from the
+     * very start, which the puzzle inputs happen to be built to do.
+     */
+    fn supports_lcm_shortcut(&self) -> bool {
+        self.hits == [self.cycle_length] && self.prefix_length <= self.cycle_length
+    }
+}
+
+/* The loop always takes a whole number of passes through the instructions, so it is enough to watch for a node coming
+* round again at the start of a pass, and then step back to where the loop really began, which is at most one pass
+* earlier.
+*/
+fn analyze_walk<F>(
+    key: &str,
+    right_left: &[char],
+    network: &Network,
+    end_condition: F,
+) -> WalkAnalysis
+where
+    F: Fn(&str) -> bool,
+{
+    let is_end = network.end_nodes(end_condition);
+    // First step at which the walk was on each node at the start of a pass through the instructions
+    let mut first_seen = vec![usize::MAX; network.names.len()];
+    let mut path = vec![];
+    let mut current = network.id(key);
+
+    loop {
+        let instruction = path.len() % right_left.len();
+        if instruction == 0 {
+            let first = first_seen[current as usize];
+            if first != usize::MAX {
+                let cycle_length = path.len() - first;
+                let mut prefix_length = first;
+                while prefix_length > 0
+                    && path[prefix_length - 1] == path[prefix_length - 1 + cycle_length]
+                {
+                    prefix_length -= 1;
+                }
+
+                return WalkAnalysis {
+                    prefix_length,
+                    cycle_length,
+                    hits: (0..prefix_length + cycle_length)
+                        .filter(|&step| is_end[path[step] as usize])
+                        .collect(),
+                };
+            }
+            first_seen[current as usize] = path.len();
+        }
+        path.push(current);
+
+        let next_node = network.node(current);
+        current = match right_left[instruction] {
+            'L' => next_node.l,
+            'R' => next_node.r,
+            x => panic!("Invalid direction {}", x),
+        };
+    }
+}
+
+/* Any step where every walk is on an end node is either one of the hits seen before some walk settled into its loop,
+* or a solution of the congruences step = hit (mod cycle_length) for one looping hit from each walk. The congruences
+* are merged one walk at a time, and merged pairs that give the same remainder are only kept once, so there are never
+* more of them than the least common multiple of the cycle lengths so far.
+*/
+fn earliest_common_hit(analyses: &[WalkAnalysis]) -> Option<usize> {
+    let mut earliest = analyses
+        .iter()
+        .flat_map(|analysis| analysis.hits.iter().copied())
+        .filter(|&step| analyses.iter().all(|analysis| analysis.is_hit(step)))
+        .min();
+
+    let mut congruences: Vec<(i128, i128)> = vec![(0, 1)];
+    for analysis in analyses {
+        let cycle_length = analysis.cycle_length as i128;
+        congruences = congruences
+            .iter()
+            .flat_map(|&congruence| {
+                analysis
+                    .cyclic_hits()
+                    .filter_map(move |&hit| crt(&[congruence, (hit as i128, cycle_length)]))
+            })
+            .collect();
+        congruences.sort_unstable();
+        congruences.dedup();
+    }
+
+    // A looping hit only comes round again once its walk is in the loop, so the step has to be past every prefix
+    let lowest = analyses
+        .iter()
+        .map(|analysis| analysis.prefix_length)
+        .max()
+        .unwrap_or(0) as i128;
+    for (remainder, modulus) in congruences {
+        let step = if remainder >= lowest {
+            remainder
+        } else {
+            remainder + (lowest - remainder + modulus - 1) / modulus * modulus
+        };
+        let step = step as usize;
+        if earliest.is_none_or(|earliest| step < earliest) {
+            earliest = Some(step);
+        }
+    }
+
+    earliest
+}
+
 /* least_common_multiple based on this StackOverflow answer:
  * https://stackoverflow.com/a/31302607 */
 
@@ -121,15 +304,188 @@ mod tests {
             String::from("AAA = (BBB, BBB)"),
             String::from("BBB = (AAA, ZZZ)"),
         ];
-        let mappings = get_mappings(test_input);
+        let network = get_mappings(test_input);
+
+        let a = network.node(network.id("AAA"));
+        let b = network.node(network.id("BBB"));
 
-        let a = mappings.get(&String::from("AAA")).unwrap();
-        let b = mappings.get(&String::from("BBB")).unwrap();
+        assert_eq!(network.names.name(a.l), "BBB");
+        assert_eq!(network.names.name(a.r), "BBB");
+        assert_eq!(network.names.name(b.l), "AAA");
+        assert_eq!(network.names.name(b.r), "ZZZ");
+        assert_eq!(network.keys().collect::<Vec<_>>(), vec!["AAA", "BBB"]);
+    }
+
+    fn get_walk_input(lines: &[&str]) -> (Vec<char>, Network) {
+        let right_left = lines[0].chars().collect();
+        let network = get_mappings(lines[2..].iter().map(|line| String::from(*line)).collect());
+        (right_left, network)
+    }
+
+    fn analyze_ghosts(right_left: &[char], network: &Network) -> Vec<WalkAnalysis> {
+        let mut keys: Vec<&str> = network.keys().filter(|key| key.ends_with('A')).collect();
+        keys.sort();
+        keys.into_iter()
+            .map(|key| analyze_walk(key, right_left, network, |f| f.ends_with('Z')))
+            .collect()
+    }
+
+    #[test]
+    fn test_analyze_walk() {
+        let lines = get_lines("resources/part_two_test_input.txt");
+        let lines: Vec<&str> = lines.iter().map(|line| line.as_str()).collect();
+        let (right_left, network) = get_walk_input(&lines);
+        let analyses = analyze_ghosts(&right_left, &network);
+
+        assert_eq!(
+            analyses[0],
+            WalkAnalysis {
+                prefix_length: 1,
+                cycle_length: 2,
+                hits: vec![2],
+            }
+        );
+        assert_eq!(
+            analyses[1],
+            WalkAnalysis {
+                prefix_length: 1,
+                cycle_length: 6,
+                hits: vec![3, 6],
+            }
+        );
+        assert!(analyses[0].supports_lcm_shortcut());
+        assert!(!analyses[1].supports_lcm_shortcut());
+        assert!(analyses[1].is_hit(9));
+        assert!(!analyses[1].is_hit(10));
+        assert_eq!(earliest_common_hit(&analyses), Some(6));
+    }
+
+    #[test]
+    fn test_analyze_walk_matches_states() {
+        // Simple linear congruential generator so the random networks are the same on every run
+        let mut state: u64 = 1;
+        let mut next = |limit: usize| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize % limit
+        };
+
+        for _ in 0..50 {
+            let node_count = next(12) + 2;
+            let right_left: String = (0..next(5) + 1).map(|_| ['L', 'R'][next(2)]).collect();
+            let mut lines = vec![right_left, String::new()];
+            for node in 0..node_count {
+                let end = if next(3) == 0 { 'Z' } else { 'B' };
+                let (l, r) = (next(node_count), next(node_count));
+                lines.push(format!("N{}{} = (N{}, N{})", node, end, l, r));
+            }
+            // Names are only known once every line is written, so fix up the targets afterwards
+            let names: Vec<String> = lines[2..]
+                .iter()
+                .map(|line| line.split(" = ").next().unwrap().to_string())
+                .collect();
+            for line in lines[2..].iter_mut() {
+                let (key, targets) = line.split_once(" = ").unwrap();
+                let targets: Vec<usize> = targets
+                    .trim_matches(|c| c == '(' || c == ')')
+                    .split(", ")
+                    .map(|target| target[1..].parse().unwrap())
+                    .collect();
+                *line = format!("{} = ({}, {})", key, names[targets[0]], names[targets[1]]);
+            }
+            let lines: Vec<&str> = lines.iter().map(|line| line.as_str()).collect();
+            let (right_left, network) = get_walk_input(&lines);
+
+            // Every (node, instruction index) state in order until one repeats
+            let is_end = network.end_nodes(|f| f.ends_with('Z'));
+            let mut states = vec![];
+            let mut current = network.id(&names[0]);
+            while !states.contains(&(current, states.len() % right_left.len())) {
+                let instruction = states.len() % right_left.len();
+                states.push((current, instruction));
+                let node = network.node(current);
+                current = if right_left[instruction] == 'L' {
+                    node.l
+                } else {
+                    node.r
+                };
+            }
+            let prefix_length = states
+                .iter()
+                .position(|&s| s == (current, states.len() % right_left.len()))
+                .unwrap();
+            let hits: Vec<usize> = (0..states.len())
+                .filter(|&step| is_end[states[step].0 as usize])
+                .collect();
+
+            assert_eq!(
+                analyze_walk(&names[0], &right_left, &network, |f| f.ends_with('Z')),
+                WalkAnalysis {
+                    prefix_length,
+                    cycle_length: states.len() - prefix_length,
+                    hits,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_earliest_common_hit_without_lcm_shortcut() {
+        let (right_left, network) = get_walk_input(&[
+            "L",
+            "",
+            "11A = (11B, 11B)",
+            "11B = (11C, 11C)",
+            "11C = (11Z, 11Z)",
+            "11Z = (11C, 11C)",
+            "22A = (22B, 22B)",
+            "22B = (22Z, 22Z)",
+            "22Z = (22C, 22C)",
+            "22C = (22B, 22B)",
+        ]);
+        let analyses = analyze_ghosts(&right_left, &network);
+
+        assert!(!analyses[0].supports_lcm_shortcut());
+        assert_eq!(earliest_common_hit(&analyses), Some(5));
+    }
+
+    #[test]
+    fn test_earliest_common_hit_many_hits() {
+        // Ten ghosts that each hit an end node on every step of a loop of twelve, which would be 12^10 combinations
+        let mut lines = vec![String::from("L"), String::new()];
+        for ghost in 0..10 {
+            let name = |node: usize| format!("{}{}", (b'A' + ghost) as char, node);
+            lines.push(format!("{}A = ({}Z, {}Z)", ghost, name(0), name(0)));
+            for node in 0..12 {
+                let next = name((node + 1) % 12);
+                lines.push(format!("{}Z = ({}Z, {}Z)", name(node), next, next));
+            }
+        }
+        let lines: Vec<&str> = lines.iter().map(|line| line.as_str()).collect();
+        let (right_left, network) = get_walk_input(&lines);
+        let analyses = analyze_ghosts(&right_left, &network);
+
+        assert_eq!(analyses.len(), 10);
+        assert_eq!(analyses[0].cyclic_hits().count(), 12);
+        assert_eq!(earliest_common_hit(&analyses), Some(1));
+    }
+
+    #[test]
+    fn test_earliest_common_hit_never() {
+        let (right_left, network) = get_walk_input(&[
+            "L",
+            "",
+            "11A = (11Z, 11Z)",
+            "11Z = (11B, 11B)",
+            "11B = (11Z, 11Z)",
+            "22A = (22B, 22B)",
+            "22B = (22Z, 22Z)",
+            "22Z = (22B, 22B)",
+        ]);
+        let analyses = analyze_ghosts(&right_left, &network);
 
-        assert_eq!(a.l, "BBB");
-        assert_eq!(a.r, "BBB");
-        assert_eq!(b.l, "AAA");
-        assert_eq!(b.r, "ZZZ");
+        assert_eq!(earliest_common_hit(&analyses), None);
     }
 
     #[test]
@@ -1,148 +1,348 @@
-use std::i32;
+use std::fmt;
 
-use library::{get_filename_arg, get_lines};
+use library::num::{gcd, Rational};
+use library::{get_filename_and_options, get_lines};
+
+const FAR_INDEX: i128 = 1_000_000_000_000;
 
 fn main() {
-    let file_name = get_filename_arg();
+    /* --report prints the polynomial each history follows and its value at FAR_INDEX
+     * --index=<n> does the same with the value at n instead
+     */
+    let (file_name, options) = get_filename_and_options(&["report", "index"]);
     let input = get_lines(&file_name);
-    let mut lines = vec![];
+    let sequences: Vec<Sequence> = input
+        .iter()
+        .map(|line| Sequence::new(&parse_numbers(line)).unwrap_or_else(|error| panic!("{}", error)))
+        .collect();
 
-    for line in input {
-        lines.push(parse_numbers(line));
+    if options.is_set("report") || options.is_set("index") {
+        let far_index = options.parsed_value("index").unwrap_or(FAR_INDEX);
+        for sequence in &sequences {
+            let coefficients = sequence
+                .coefficients()
+                .unwrap_or_else(|error| panic!("{}", error));
+            let coefficients: Vec<String> = coefficients.iter().map(|c| c.to_string()).collect();
+            let far_value = match sequence.value_at(far_index) {
+                Ok(value) => value.to_string(),
+                Err(error) => error.to_string(),
+            };
+            let uncertain = if sequence.settled { "" } else { " (uncertain)" };
+            println!(
+                "Degree {}{}, coefficients [{}], value at {}: {}",
+                sequence.degree(),
+                uncertain,
+                coefficients.join(", "),
+                far_index,
+                far_value
+            );
+        }
     }
 
-    println!("Part 1: {}", sum(&lines, |f| get_next_value(f)));
-    println!("Part 2: {}", sum(&lines, |f| get_previous_value(f)));
+    println!("Part 1: {}", sum(&sequences, |s| s.next_value()));
+    println!("Part 2: {}", sum(&sequences, |s| s.previous_value()));
 }
 
-fn parse_numbers(input: String) -> Vec<i32> {
-    let mut numbers = vec![];
-    let line: Vec<&str> = input.split(" ").collect();
-    for l in line {
-        numbers.push(
-            l.parse::<i32>()
-                .expect("Failed to parse string into number"),
-        );
-    }
+fn parse_numbers(input: &str) -> Vec<i128> {
+    input
+        .split_whitespace()
+        .map(|l| l.parse().expect("Failed to parse string into number"))
+        .collect()
+}
 
-    numbers
+fn get_differences(line: &[i128]) -> Result<Vec<i128>, SequenceError> {
+    line.windows(2)
+        .map(|pair| pair[1].checked_sub(pair[0]).ok_or(SequenceError::Overflow))
+        .collect()
 }
 
-fn get_differences(line: &Vec<i32>) -> Vec<i32> {
-    let mut differences = vec![];
-    for i in 0..line.len() - 1 {
-        differences.push(line[i + 1] - line[i]);
-    }
+fn is_only_zeroes(diffs: &[i128]) -> bool {
+    diffs.iter().all(|&diff| diff == 0)
+}
 
-    differences
+#[derive(PartialEq, Eq, Debug)]
+enum SequenceError {
+    Empty,
+    Overflow,
 }
 
-fn is_only_zeroes(diffs: &Vec<i32>) -> bool {
-    for diff in diffs {
-        if *diff != 0 {
-            return false;
+impl fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "The sequence has no values"),
+            Self::Overflow => write!(f, "The value does not fit in an i128"),
         }
     }
-    true
 }
 
-fn get_diff_vecs(line: &Vec<i32>) -> Vec<Vec<i32>> {
-    let mut diff_vecs = vec![];
-    let mut diffs = get_differences(line);
+/* The values of a polynomial at 0, 1, 2... stored as the first value of each row of the difference table. Newton's
+* forward difference formula then gives the value at any n as the sum of C(n, k) times the kth of those, and C(n, k) is
+* an integer for every integer n, negative ones included.
+*/
+struct Sequence {
+    leading_differences: Vec<i128>,
+    length: usize,
+    // Whether the difference table reached a row of zeroes, which is the only way to be sure of the degree
+    settled: bool,
+}
+
+impl Sequence {
+    /* When the values run out before a row of zeroes, the single value left in the last row is taken as the highest
+     * difference, which is the lowest degree polynomial through all the values.
+     */
+    fn new(values: &[i128]) -> Result<Self, SequenceError> {
+        if values.is_empty() {
+            return Err(SequenceError::Empty);
+        }
+
+        let mut leading_differences = vec![];
+        let mut diffs = values.to_vec();
+        let mut settled = true;
+        while !is_only_zeroes(&diffs) {
+            leading_differences.push(diffs[0]);
+            if diffs.len() == 1 {
+                settled = false;
+                break;
+            }
+            diffs = get_differences(&diffs)?;
+        }
 
-    while !is_only_zeroes(&diffs) {
-        diff_vecs.push(diffs.clone());
-        diffs = get_differences(&diffs);
+        Ok(Self {
+            leading_differences,
+            length: values.len(),
+            settled,
+        })
     }
 
-    diff_vecs
-}
+    // Sequences of all zeroes count as degree 0
+    fn degree(&self) -> usize {
+        self.leading_differences.len().saturating_sub(1)
+    }
 
-fn get_next_value(line: &Vec<i32>) -> i32 {
-    let diff_vecs = get_diff_vecs(line);
+    fn value_at(&self, index: i128) -> Result<i128, SequenceError> {
+        let mut value: i128 = 0;
+        let mut binomial: i128 = 1;
+        for (k, difference) in (0..).zip(&self.leading_differences) {
+            if k > 0 {
+                binomial = get_next_binomial(binomial, index, k).ok_or(SequenceError::Overflow)?;
+            }
+            value = binomial
+                .checked_mul(*difference)
+                .and_then(|term| value.checked_add(term))
+                .ok_or(SequenceError::Overflow)?;
+        }
 
-    let mut next_value = 0;
-    for i in 0..diff_vecs.len() {
-        next_value += diff_vecs[i].last().unwrap();
+        Ok(value)
     }
-    next_value += line.last().unwrap();
 
-    next_value
-}
+    fn next_value(&self) -> i128 {
+        self.value_at(self.length as i128)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    fn previous_value(&self) -> i128 {
+        self.value_at(-1)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /* The coefficients of the polynomial from the constant term up, found by expanding each n(n - 1)...(n - k + 1) / k!
+     * of the binomials in Newton's formula.
+     */
+    fn coefficients(&self) -> Result<Vec<Rational<i128>>, SequenceError> {
+        let mut coefficients = vec![Rational::zero(); self.leading_differences.len().max(1)];
+        // n(n - 1)...(n - k + 1) as coefficients from the constant term up, starting with the empty product
+        let mut falling = vec![1i128];
+        let mut factorial: i128 = 1;
 
-fn get_previous_value(line: &Vec<i32>) -> i32 {
-    let diff_vecs = get_diff_vecs(line);
+        for (k, difference) in (0..).zip(&self.leading_differences) {
+            if k > 0 {
+                let mut next = vec![0i128; falling.len() + 1];
+                for (power, coefficient) in falling.iter().enumerate() {
+                    next[power + 1] = next[power + 1]
+                        .checked_add(*coefficient)
+                        .ok_or(SequenceError::Overflow)?;
+                    let shifted = coefficient
+                        .checked_mul(k - 1)
+                        .ok_or(SequenceError::Overflow)?;
+                    next[power] = next[power]
+                        .checked_sub(shifted)
+                        .ok_or(SequenceError::Overflow)?;
+                }
+                falling = next;
+                factorial = factorial.checked_mul(k).ok_or(SequenceError::Overflow)?;
+            }
 
-    let mut previous_value = 0;
-    for i in (0..diff_vecs.len()).rev() {
-        previous_value = diff_vecs[i][0] - previous_value;
+            let scale = Rational::new(*difference, factorial).ok_or(SequenceError::Overflow)?;
+            for (power, coefficient) in falling.iter().enumerate() {
+                coefficients[power] = Rational::from_integer(*coefficient)
+                    .checked_mul(&scale)
+                    .and_then(|term| coefficients[power].checked_add(&term))
+                    .ok_or(SequenceError::Overflow)?;
+            }
+        }
+
+        Ok(coefficients)
     }
+}
 
-    line[0] - previous_value
+/* C(n, k) from C(n, k - 1), which is C(n, k - 1) * (n - k + 1) / k. Dividing out the common factor of C(n, k - 1)
+* and k first leaves a divisor that goes into n - k + 1 exactly, so nothing overflows unless C(n, k) itself does.
+*/
+fn get_next_binomial(previous: i128, n: i128, k: i128) -> Option<i128> {
+    let common = gcd(&previous, &k)?;
+    let top = n.checked_sub(k - 1)?;
+    (previous / common).checked_mul(top / (k / common))
 }
 
-fn sum<F>(lines: &Vec<Vec<i32>>, value_fn: F) -> i32
+fn sum<F>(sequences: &[Sequence], value_fn: F) -> i128
 where
-    F: Fn(&Vec<i32>) -> i32,
+    F: Fn(&Sequence) -> i128,
 {
-    let mut sum = 0;
-    for line in lines {
-        sum += value_fn(line);
-    }
-
-    sum
+    sequences.iter().map(value_fn).sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn get_next_value(values: &[i128]) -> i128 {
+        Sequence::new(values).unwrap().next_value()
+    }
+
+    fn get_previous_value(values: &[i128]) -> i128 {
+        Sequence::new(values).unwrap().previous_value()
+    }
+
+    #[test]
+    fn test_parts() {
+        let sequences: Vec<Sequence> = get_lines("resources/test_input.txt")
+            .iter()
+            .map(|line| Sequence::new(&parse_numbers(line)).unwrap())
+            .collect();
+
+        assert_eq!(sum(&sequences, |s| s.next_value()), 114);
+        assert_eq!(sum(&sequences, |s| s.previous_value()), 2);
+    }
+
     #[test]
     fn test_parse_numbers() {
-        let input = String::from("1 20 42");
-        let lines = parse_numbers(input);
+        let lines = parse_numbers("1 20 -42");
 
-        assert_eq!(lines.len(), 3);
-        assert_eq!(lines[0], 1);
-        assert_eq!(lines[1], 20);
-        assert_eq!(lines[2], 42);
+        assert_eq!(lines, vec![1, 20, -42]);
     }
 
     #[test]
     fn test_get_differences() {
         let input = vec![0, 3, 6, 9, 12, 15];
-        let differences = get_differences(&input);
-        let next_differences = get_differences(&differences);
-
-        assert_eq!(differences.len(), input.len() - 1);
-        assert_eq!(next_differences.len(), differences.len() - 1);
-
-        for difference in differences {
-            assert_eq!(difference, 3);
-        }
+        let differences = get_differences(&input).unwrap();
+        let next_differences = get_differences(&differences).unwrap();
 
-        for next_difference in next_differences {
-            assert_eq!(next_difference, 0);
-        }
+        assert_eq!(differences, vec![3; 5]);
+        assert_eq!(next_differences, vec![0; 4]);
+        assert_eq!(
+            get_differences(&[i128::MIN, i128::MAX]),
+            Err(SequenceError::Overflow)
+        );
     }
 
     #[test]
     fn test_is_only_zeroes() {
-        assert!(!is_only_zeroes(&vec![0, 1, 0, 0]));
-        assert!(is_only_zeroes(&vec![0, 0, 0, 0, 0]));
+        assert!(!is_only_zeroes(&[0, 1, 0, 0]));
+        assert!(is_only_zeroes(&[0, 0, 0, 0, 0]));
     }
 
     #[test]
     fn test_get_next_value() {
-        assert_eq!(get_next_value(&vec![0, 3, 6, 9, 12, 15]), 18);
-        assert_eq!(get_next_value(&vec![1, 3, 6, 10, 15, 21]), 28);
-        assert_eq!(get_next_value(&vec![10, 13, 16, 21, 30, 45]), 68);
+        assert_eq!(get_next_value(&[0, 3, 6, 9, 12, 15]), 18);
+        assert_eq!(get_next_value(&[1, 3, 6, 10, 15, 21]), 28);
+        assert_eq!(get_next_value(&[10, 13, 16, 21, 30, 45]), 68);
     }
 
     #[test]
     fn test_get_previous_value() {
-        assert_eq!(get_previous_value(&vec![0, 3, 6, 9, 12, 15]), -3);
-        assert_eq!(get_previous_value(&vec![1, 3, 6, 10, 15, 21]), 0);
-        assert_eq!(get_previous_value(&vec![10, 13, 16, 21, 30, 45]), 5);
+        assert_eq!(get_previous_value(&[0, 3, 6, 9, 12, 15]), -3);
+        assert_eq!(get_previous_value(&[1, 3, 6, 10, 15, 21]), 0);
+        assert_eq!(get_previous_value(&[10, 13, 16, 21, 30, 45]), 5);
+    }
+
+    #[test]
+    fn test_degree() {
+        assert_eq!(Sequence::new(&[0, 0, 0]).unwrap().degree(), 0);
+        assert_eq!(Sequence::new(&[4, 4, 4]).unwrap().degree(), 0);
+        assert_eq!(Sequence::new(&[0, 3, 6, 9]).unwrap().degree(), 1);
+        assert_eq!(
+            Sequence::new(&[10, 13, 16, 21, 30, 45]).unwrap().degree(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_unsettled_degree() {
+        // The last row of differences has a single value, which is taken as constant
+        let powers = Sequence::new(&[1, 2, 4, 8, 16]).unwrap();
+        assert!(!powers.settled);
+        assert_eq!(powers.degree(), 4);
+        assert_eq!(powers.next_value(), 31);
+        assert_eq!(get_next_value(&[1, 2]), 3);
+        assert_eq!(get_previous_value(&[1, 2]), 0);
+
+        let single = Sequence::new(&[5]).unwrap();
+        assert!(!single.settled);
+        assert_eq!(single.degree(), 0);
+        assert_eq!((single.next_value(), single.previous_value()), (5, 5));
+
+        assert!(Sequence::new(&[0, 3, 6, 9]).unwrap().settled);
+    }
+
+    #[test]
+    fn test_far_values() {
+        let triangular = Sequence::new(&[1, 3, 6, 10, 15, 21]).unwrap();
+        let n = FAR_INDEX;
+
+        assert_eq!(
+            Sequence::new(&[0, 3, 6, 9, 12, 15]).unwrap().value_at(n),
+            Ok(3 * n)
+        );
+        assert_eq!(triangular.value_at(n), Ok((n + 1) * (n + 2) / 2));
+        assert_eq!(triangular.value_at(-n), Ok((-n + 1) * (-n + 2) / 2));
+    }
+
+    #[test]
+    fn test_coefficients() {
+        let rational = |numerator, denominator| Rational::new(numerator, denominator).unwrap();
+
+        // (n + 1)(n + 2) / 2
+        assert_eq!(
+            Sequence::new(&[1, 3, 6, 10, 15, 21])
+                .unwrap()
+                .coefficients(),
+            Ok(vec![rational(1, 1), rational(3, 2), rational(1, 2)])
+        );
+        // n^3 - 2n + 7
+        assert_eq!(
+            Sequence::new(&[7, 6, 11, 28, 63]).unwrap().coefficients(),
+            Ok(vec![
+                rational(7, 1),
+                rational(-2, 1),
+                rational(0, 1),
+                rational(1, 1)
+            ])
+        );
+        assert_eq!(
+            Sequence::new(&[0, 0]).unwrap().coefficients(),
+            Ok(vec![rational(0, 1)])
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(Sequence::new(&[]), Err(SequenceError::Empty)));
+
+        // n^10 is far too large at n = 10^12
+        let values: Vec<i128> = (0..12).map(|n: i128| n.pow(10)).collect();
+        let sequence = Sequence::new(&values).unwrap();
+        assert_eq!(sequence.degree(), 10);
+        assert_eq!(sequence.value_at(12), Ok(12i128.pow(10)));
+        assert_eq!(sequence.value_at(FAR_INDEX), Err(SequenceError::Overflow));
     }
 }
@@ -1,209 +1,348 @@
-use library::{get_filename_arg, get_two_dimensional_vector};
-use regex::Regex;
+use std::fmt;
+
+use library::geometry::{Point, Polygon};
+use library::grid::Grid;
+use library::{get_filename_and_options, get_two_dimensional_vector};
+
+// Connection bits for the sides of a tile a pipe leads out of
+const NORTH: u8 = 1;
+const EAST: u8 = 2;
+const SOUTH: u8 = 4;
+const WEST: u8 = 8;
+const SIDES: [u8; 4] = [NORTH, EAST, SOUTH, WEST];
 
 fn main() {
-    let file_name = get_filename_arg();
-    let mut input = get_two_dimensional_vector(&file_name);
+    /* --render prints the maze with the loop drawn in box-drawing characters and the enclosed tiles marked
+     * --enclosure=pick|scanline picks how part 2 counts the enclosed tiles
+     */
+    let (file_name, options) = get_filename_and_options(&["render", "enclosure"]);
+    let input = get_two_dimensional_vector(&file_name);
+    let maze = PipeMaze::parse(&input).unwrap_or_else(|error| panic!("{}", error));
+    let enclosure = options.value("enclosure").map_or(Enclosure::Pick, |name| {
+        Enclosure::parse(name).unwrap_or_else(|| panic!("Unknown enclosure method {}", name))
+    });
 
-    let start_location = find_start(&input);
+    if options.is_set("render") {
+        print!("{}", maze.render());
+    }
+    println!("Part 1: {}", maze.farthest_distance());
+    println!("Part 2: {}", maze.enclosed_count(enclosure));
+}
 
-    let (mut current_row, mut current_column, mut current_direction) =
-        get_starting_direction(start_location, &input);
+fn get_connections(tile: char) -> Option<u8> {
+    match tile {
+        '|' => Some(NORTH | SOUTH),
+        '-' => Some(EAST | WEST),
+        'L' => Some(NORTH | EAST),
+        'J' => Some(NORTH | WEST),
+        '7' => Some(SOUTH | WEST),
+        'F' => Some(SOUTH | EAST),
+        '.' => Some(0),
+        _ => None,
+    }
+}
 
-    let starting_direction = current_direction.clone();
+fn get_box_drawing(connections: u8) -> char {
+    match connections {
+        c if c == NORTH | SOUTH => '│',
+        c if c == EAST | WEST => '─',
+        c if c == NORTH | EAST => '└',
+        c if c == NORTH | WEST => '┘',
+        c if c == SOUTH | WEST => '┐',
+        c if c == SOUTH | EAST => '┌',
+        _ => ' ',
+    }
+}
 
-    let mut count = 1;
-    while input[current_row][current_column] != 'S' {
-        count += 1;
-        (current_row, current_column, current_direction) =
-            get_next_location(current_row, current_column, current_direction, &mut input);
+fn opposite(side: u8) -> u8 {
+    match side {
+        NORTH => SOUTH,
+        EAST => WEST,
+        SOUTH => NORTH,
+        _ => EAST,
     }
+}
 
-    println!("Part 1: {}", count / 2);
+// The tile through the given side, if it is on the grid
+fn get_neighbour(
+    tiles: &Grid<u8>,
+    (row, column): (usize, usize),
+    side: u8,
+) -> Option<(usize, usize)> {
+    let (row, column) = match side {
+        NORTH => (row.checked_sub(1)?, column),
+        EAST => (row, column + 1),
+        SOUTH => (row + 1, column),
+        _ => (row, column.checked_sub(1)?),
+    };
+    tiles.get(row, column).map(|_| (row, column))
+}
 
-    match current_direction {
-        Directions::North => match starting_direction {
-            Directions::North => {
-                input[current_row][current_column] = '!';
-            }
-            Directions::East => {
-                input[current_row][current_column] = '[';
-            }
-            Directions::West => {
-                input[current_row][current_column] = ']';
-            }
-            _ => {
-                panic!("Invalid S location");
-            }
-        },
-        Directions::South => match starting_direction {
-            Directions::South => {
-                input[current_row][current_column] = '!';
-            }
-            Directions::East => {
-                input[current_row][current_column] = '{';
-            }
-            Directions::West => {
-                input[current_row][current_column] = '}';
-            }
-            _ => {
-                panic!("Invalid S location");
-            }
-        },
-        Directions::East => match starting_direction {
-            Directions::East => {
-                input[current_row][current_column] = '=';
-            }
-            Directions::North => {
-                input[current_row][current_column] = '}';
-            }
-            Directions::South => {
-                input[current_row][current_column] = ']';
-            }
-            _ => {
-                panic!("Invalid S location");
-            }
-        },
-        Directions::West => match starting_direction {
-            Directions::West => {
-                input[current_row][current_column] = '=';
-            }
-            Directions::North => {
-                input[current_row][current_column] = '{';
-            }
-            Directions::South => {
-                input[current_row][current_column] = '[';
+#[derive(PartialEq, Eq, Debug)]
+enum MazeError {
+    InvalidTile(char, usize, usize),
+    NoStart,
+    MultipleStarts,
+    // The start needs exactly two neighbouring pipes leading into it to know which pipe it is
+    AmbiguousStart(u32),
+    BrokenLoop(usize, usize),
+}
+
+impl fmt::Display for MazeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidTile(tile, row, column) => {
+                write!(f, "Invalid tile {} at ({}, {})", tile, row, column)
             }
-            _ => {
-                panic!("Invalid S location");
+            Self::NoStart => write!(f, "No starting point found"),
+            Self::MultipleStarts => write!(f, "More than one starting point found"),
+            Self::AmbiguousStart(count) => write!(
+                f,
+                "{} pipes lead into the starting point instead of 2",
+                count
+            ),
+            Self::BrokenLoop(row, column) => {
+                write!(f, "The loop is broken at ({}, {})", row, column)
             }
-        },
+        }
     }
+}
 
-    count = 0;
-    let collapse_to_wall = Regex::new(r"(\{=*])|(\[=*\})").unwrap();
-    let collapse_to_nothing = Regex::new(r"[\[{]=*[\]}]").unwrap();
-    let wall = Regex::new(r"![^!]*!").unwrap();
-    for line in input {
-        let current_line = line.into_iter().collect::<String>();
-        let walls_collapsed = collapse_to_wall.replace_all(&current_line, "!");
-        let empty_space_collapsed = collapse_to_nothing.replace_all(&walls_collapsed, "");
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Enclosure {
+    // Counts how many times each row crosses the loop, using the tiles that connect north
+    Scanline,
+    // The shoelace area of the loop with Pick's theorem
+    Pick,
+}
 
-        for cap in wall.captures_iter(&empty_space_collapsed) {
-            count += String::from(&cap[0]).len() - 2;
+impl Enclosure {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "scanline" => Some(Self::Scanline),
+            "pick" => Some(Self::Pick),
+            _ => None,
         }
     }
-
-    println!("Part 2: {}", count);
 }
 
-#[derive(PartialEq, Debug, Clone)]
-enum Directions {
-    North,
-    South,
-    East,
-    West,
+struct PipeMaze {
+    // Connection bits of each tile, with the start replaced by the pipe it must be
+    tiles: Grid<u8>,
+    start: (usize, usize),
+    // The loop in order, beginning at the start
+    path: Vec<(usize, usize)>,
 }
 
-fn get_next_location(
-    row: usize,
-    column: usize,
-    direction_traveled: Directions,
-    input: &mut Vec<Vec<char>>,
-) -> (usize, usize, Directions) {
-    match input[row][column] {
-        '|' => {
-            input[row][column] = '!';
-            if direction_traveled == Directions::North {
-                return (row - 1, column, Directions::North);
-            }
-            if direction_traveled == Directions::South {
-                return (row + 1, column, Directions::South);
+impl PipeMaze {
+    fn parse(input: &[Vec<char>]) -> Result<Self, MazeError> {
+        let mut start = None;
+        let mut rows = vec![];
+        for (row, line) in input.iter().enumerate() {
+            let mut tiles = vec![];
+            for (column, &tile) in line.iter().enumerate() {
+                if tile == 'S' {
+                    if start.is_some() {
+                        return Err(MazeError::MultipleStarts);
+                    }
+                    start = Some((row, column));
+                    tiles.push(0);
+                    continue;
+                }
+                tiles.push(get_connections(tile).ok_or(MazeError::InvalidTile(tile, row, column))?);
             }
-            panic!("Invalid direction: traveling {:?} at |", direction_traveled);
+            rows.push(tiles);
         }
-        '-' => {
-            input[row][column] = '=';
-            if direction_traveled == Directions::East {
-                return (row, column + 1, Directions::East);
-            }
-            if direction_traveled == Directions::West {
-                return (row, column - 1, Directions::West);
-            }
-            panic!("Invalid direction: traveling {:?} at -", direction_traveled);
+        let mut tiles = Grid::from_rows(rows);
+        let start = start.ok_or(MazeError::NoStart)?;
+
+        let start_connections = SIDES
+            .into_iter()
+            .filter(|&side| {
+                get_neighbour(&tiles, start, side).is_some_and(|(row, column)| {
+                    tiles.get(row, column).unwrap() & opposite(side) != 0
+                })
+            })
+            .fold(0, |connections, side| connections | side);
+        if start_connections.count_ones() != 2 {
+            return Err(MazeError::AmbiguousStart(start_connections.count_ones()));
         }
-        '7' => {
-            input[row][column] = ']';
-            if direction_traveled == Directions::East {
-                return (row + 1, column, Directions::South);
-            }
-            if direction_traveled == Directions::North {
-                return (row, column - 1, Directions::West);
-            }
-            panic!("Invalid direction: traveling {:?} at 7", direction_traveled);
+        tiles.set(start.0, start.1, start_connections);
+
+        let path = get_loop(&tiles, start)?;
+        Ok(Self { tiles, start, path })
+    }
+
+    fn farthest_distance(&self) -> usize {
+        self.path.len() / 2
+    }
+
+    fn on_loop(&self) -> Grid<bool> {
+        let mut on_loop = Grid::new(self.tiles.width(), self.tiles.height(), false);
+        for &(row, column) in &self.path {
+            on_loop.set(row, column, true);
         }
-        'J' => {
-            input[row][column] = '}';
-            if direction_traveled == Directions::East {
-                return (row - 1, column, Directions::North);
-            }
-            if direction_traveled == Directions::South {
-                return (row, column - 1, Directions::West);
+        on_loop
+    }
+
+    fn enclosed_count(&self, enclosure: Enclosure) -> usize {
+        match enclosure {
+            Enclosure::Scanline => self.enclosed().rows().flatten().filter(|&&e| e).count(),
+            Enclosure::Pick => {
+                let vertices = self
+                    .path
+                    .iter()
+                    .map(|&(row, column)| Point::new(row as i64, column as i64))
+                    .collect();
+                Polygon::from_vertices(vertices).interior_points() as usize
             }
-            panic!("Invalid direction: traveling {:?} at J", direction_traveled);
         }
-        'L' => {
-            input[row][column] = '{';
-            if direction_traveled == Directions::West {
-                return (row - 1, column, Directions::North);
-            }
-            if direction_traveled == Directions::South {
-                return (row, column + 1, Directions::East);
+    }
+
+    /* Moving along a row, every loop tile that connects north is a crossing of the loop, as a horizontal stretch
+     * either starts and ends going the same way and crosses twice or not at all, or turns back the other way and
+     * crosses once. Tiles off the loop are enclosed when there have been an odd number of crossings before them.
+     */
+    fn enclosed(&self) -> Grid<bool> {
+        let on_loop = self.on_loop();
+        let mut enclosed = Grid::new(self.tiles.width(), self.tiles.height(), false);
+        for row in 0..self.tiles.height() {
+            let mut inside = false;
+            for column in 0..self.tiles.width() {
+                if *on_loop.get(row, column).unwrap() {
+                    if self.tiles.get(row, column).unwrap() & NORTH != 0 {
+                        inside = !inside;
+                    }
+                } else if inside {
+                    enclosed.set(row, column, true);
+                }
             }
-            panic!("Invalid direction: traveling {:?} at L", direction_traveled);
         }
-        'F' => {
-            input[row][column] = '[';
-            if direction_traveled == Directions::West {
-                return (row + 1, column, Directions::South);
-            }
-            if direction_traveled == Directions::North {
-                return (row, column + 1, Directions::East);
+        enclosed
+    }
+
+    // The loop in box-drawing characters, with 'S' at the start, 'I' on enclosed tiles and '.' everywhere else
+    fn render(&self) -> String {
+        let on_loop = self.on_loop();
+        let enclosed = self.enclosed();
+        let mut rendered = String::new();
+        for row in 0..self.tiles.height() {
+            for column in 0..self.tiles.width() {
+                rendered.push(if (row, column) == self.start {
+                    'S'
+                } else if *on_loop.get(row, column).unwrap() {
+                    get_box_drawing(*self.tiles.get(row, column).unwrap())
+                } else if *enclosed.get(row, column).unwrap() {
+                    'I'
+                } else {
+                    '.'
+                });
             }
-            panic!("Invalid direction: traveling {:?} at F", direction_traveled);
-        }
-        character => {
-            panic!("Invalid pipe character: {}", character);
+            rendered.push('\n');
         }
+        rendered
     }
 }
 
-fn find_start(input: &Vec<Vec<char>>) -> (usize, usize) {
-    for i in 0..input.len() {
-        for j in 0..input[i].len() {
-            if input[i][j] == 'S' {
-                return (i, j);
-            }
+// Follows the pipes out of the start until they lead back to it
+fn get_loop(tiles: &Grid<u8>, start: (usize, usize)) -> Result<Vec<(usize, usize)>, MazeError> {
+    let mut path = vec![start];
+    let mut current = start;
+    let mut side = SIDES
+        .into_iter()
+        .find(|&side| tiles.get(start.0, start.1).unwrap() & side != 0)
+        .unwrap();
+
+    loop {
+        let broken = MazeError::BrokenLoop(current.0, current.1);
+        let next = get_neighbour(tiles, current, side).ok_or(broken)?;
+        let connections = *tiles.get(next.0, next.1).unwrap();
+        if connections & opposite(side) == 0 {
+            return Err(MazeError::BrokenLoop(next.0, next.1));
         }
+        if next == start {
+            return Ok(path);
+        }
+
+        path.push(next);
+        current = next;
+        side = connections & !opposite(side);
     }
-    panic!("No starting point found");
 }
 
-fn get_starting_direction(
-    start_location: (usize, usize),
-    input: &Vec<Vec<char>>,
-) -> (usize, usize, Directions) {
-    let north_of_start = input[start_location.0 - 1][start_location.1];
-    let south_of_start = input[start_location.0 + 1][start_location.1];
-    let east_of_start = input[start_location.0][start_location.1 + 1];
-
-    if north_of_start == '|' || north_of_start == 'F' || north_of_start == '7' {
-        (start_location.0 - 1, start_location.1, Directions::North)
-    } else if south_of_start == '|' || south_of_start == 'L' || south_of_start == 'J' {
-        (start_location.0 + 1, start_location.1, Directions::South)
-    } else if east_of_start == '-' || east_of_start == '7' || east_of_start == 'J' {
-        (start_location.0, start_location.1 + 1, Directions::East)
-    } else {
-        (start_location.0, start_location.1 - 1, Directions::West)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(lines: &[&str]) -> Result<PipeMaze, MazeError> {
+        let input: Vec<Vec<char>> = lines.iter().map(|line| line.chars().collect()).collect();
+        PipeMaze::parse(&input)
+    }
+
+    #[test]
+    fn test_parts() {
+        let maze =
+            PipeMaze::parse(&get_two_dimensional_vector("resources/test_input.txt")).unwrap();
+
+        assert_eq!(maze.farthest_distance(), 23);
+        assert_eq!(maze.enclosed_count(Enclosure::Scanline), 4);
+        assert_eq!(maze.enclosed_count(Enclosure::Pick), 4);
+    }
+
+    #[test]
+    fn test_start_inference() {
+        let maze = parse(&["-L|F7", "7S-7|", "L|7||", "-L-J|", "L|-JF"]).unwrap();
+
+        assert_eq!(maze.start, (1, 1));
+        assert_eq!(*maze.tiles.get(1, 1).unwrap(), SOUTH | EAST);
+        assert_eq!(maze.farthest_distance(), 4);
+        assert_eq!(maze.path[..3], [(1, 1), (1, 2), (1, 3)]);
+    }
+
+    #[test]
+    fn test_enclosed_methods_agree() {
+        let maze = parse(&[
+            ".F----7F7F7F7F-7....",
+            ".|F--7||||||||FJ....",
+            ".||.FJ||||||||L7....",
+            "FJL7L7LJLJ||LJ.L-7..",
+            "L--J.L7...LJS7F-7L7.",
+            "....F-J..F7FJ|L7L7L7",
+            "....L7.F7||L7|.L7L7|",
+            ".....|FJLJ|FJ|F7|.LJ",
+            "....FJL-7.||.||||...",
+            "....L---J.LJ.LJLJ...",
+        ])
+        .unwrap();
+
+        assert_eq!(maze.enclosed_count(Enclosure::Scanline), 8);
+        assert_eq!(maze.enclosed_count(Enclosure::Pick), 8);
+        assert_eq!(Enclosure::parse("scanline"), Some(Enclosure::Scanline));
+        assert_eq!(Enclosure::parse("flood"), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let maze = parse(&[".....", ".S-7.", ".|.|.", ".L-J.", "....."]).unwrap();
+
+        assert_eq!(maze.render(), ".....\n.S─┐.\n.│I│.\n.└─┘.\n.....\n");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse(&["F7", "LJ"]).err(), Some(MazeError::NoStart));
+        assert_eq!(parse(&["S7", "LS"]).err(), Some(MazeError::MultipleStarts));
+        assert_eq!(
+            parse(&["S7", "LX"]).err(),
+            Some(MazeError::InvalidTile('X', 1, 1))
+        );
+        assert_eq!(
+            parse(&[".|.", "-S-", ".|."]).err(),
+            Some(MazeError::AmbiguousStart(4))
+        );
+        assert_eq!(
+            parse(&["S-7", "|..", "L-J"]).err(),
+            Some(MazeError::BrokenLoop(1, 2))
+        );
     }
 }
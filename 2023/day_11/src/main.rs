@@ -1,100 +1,145 @@
-use std::cmp::{max, min};
+use library::{get_filename_and_options, get_two_dimensional_vector};
 
-use library::{get_filename_arg, get_two_dimensional_vector};
+const PART_ONE_FACTOR: u64 = 2;
+const PART_TWO_FACTOR: u64 = 1000000;
 
 fn main() {
-    let file_name = get_filename_arg();
+    // --pair=5,9 also prints the distance between galaxies 5 and 9
+    let (file_name, options) = get_filename_and_options(&["pair"]);
     let input = get_two_dimensional_vector(&file_name);
-
-    let mut empty_columns = vec![];
-    for i in 0..input.len() {
-        let column = get_column(&input, i);
-        if !column.contains(&'#') {
-            empty_columns.push(i);
-        }
+    let map = GalaxyMap::new(&input);
+
+    println!("Part 1: {}", map.total_distance(PART_ONE_FACTOR));
+    println!("Part 2: {}", map.total_distance(PART_TWO_FACTOR));
+
+    if let Some(pair) = options.value("pair") {
+        let (a, b) = pair
+            .split_once(',')
+            .and_then(|(a, b)| Some((a.parse().ok()?, b.parse().ok()?)))
+            .unwrap_or_else(|| panic!("Invalid galaxy pair {}", pair));
+        println!(
+            "Galaxies {} and {}: {} apart in part 1, {} apart in part 2",
+            a,
+            b,
+            map.distance(a, b, PART_ONE_FACTOR),
+            map.distance(a, b, PART_TWO_FACTOR)
+        );
     }
+}
 
-    let mut empty_rows = vec![];
-    for i in 0..input.len() {
-        if !input[i].contains(&'#') {
-            empty_rows.push(i);
-        }
-    }
+/* Galaxies are numbered from 1 in reading order, the same way the puzzle numbers them. Every empty row or column is
+* replaced by `factor` of them when expanding, so a galaxy's expanded row is its row plus (factor - 1) for every empty
+* row above it. The counts of empty rows and columns before each index are computed once up front.
+*/
+struct GalaxyMap {
+    galaxies: Vec<(usize, usize)>,
+    empty_rows_before: Vec<u64>,
+    empty_columns_before: Vec<u64>,
+}
 
-    let mut star_locations = vec![];
+impl GalaxyMap {
+    fn new(input: &[Vec<char>]) -> Self {
+        let width = input.first().map_or(0, |row| row.len());
+        if input.iter().any(|row| row.len() != width) {
+            panic!("All rows of the image must be the same length");
+        }
 
-    for i in 0..input.len() {
-        for j in 0..input[i].len() {
-            if input[i][j] == '#' {
-                star_locations.push((i, j));
+        let empty_rows: Vec<bool> = input.iter().map(|row| !row.contains(&'#')).collect();
+        let empty_columns: Vec<bool> = (0..width)
+            .map(|i| !get_column(input, i).contains(&'#'))
+            .collect();
+
+        let mut galaxies = vec![];
+        for (i, row) in input.iter().enumerate() {
+            for (j, &cell) in row.iter().enumerate() {
+                if cell == '#' {
+                    galaxies.push((i, j));
+                }
             }
         }
-    }
 
-    let mut part_one_sum = 0;
-    let mut part_two_sum = 0;
-
-    for i in 0..star_locations.len() {
-        for j in i + 1..star_locations.len() {
-            part_one_sum += get_distance(
-                star_locations[i],
-                star_locations[j],
-                &empty_rows,
-                &empty_columns,
-                2,
-            );
-            part_two_sum += get_distance(
-                star_locations[i],
-                star_locations[j],
-                &empty_rows,
-                &empty_columns,
-                1000000,
-            );
+        Self {
+            galaxies,
+            empty_rows_before: prefix_counts(&empty_rows),
+            empty_columns_before: prefix_counts(&empty_columns),
         }
     }
 
-    println!("Part 1: {}", part_one_sum);
-    println!("Part 2: {}", part_two_sum);
-}
+    // Every empty row above a galaxy is one of its rows, so subtracting them last can not underflow, even for factor 0
+    fn expanded(&self, galaxy: (usize, usize), factor: u64) -> (u64, u64) {
+        let (row, column) = galaxy;
+        let (rows_before, columns_before) = (
+            self.empty_rows_before[row],
+            self.empty_columns_before[column],
+        );
+        (
+            row as u64 + factor * rows_before - rows_before,
+            column as u64 + factor * columns_before - columns_before,
+        )
+    }
 
-fn get_column(vector: &Vec<Vec<char>>, index: usize) -> Vec<char> {
-    vector.iter().map(|x| x[index]).collect()
+    /* Rows and columns contribute to the distance independently, so the sum over all pairs is the sum of the pairwise
+     * differences of the sorted rows plus that of the sorted columns.
+     */
+    fn total_distance(&self, factor: u64) -> u64 {
+        let (mut rows, mut columns): (Vec<u64>, Vec<u64>) = self
+            .galaxies
+            .iter()
+            .map(|&galaxy| self.expanded(galaxy, factor))
+            .unzip();
+        rows.sort_unstable();
+        columns.sort_unstable();
+
+        sum_of_differences(&rows) + sum_of_differences(&columns)
+    }
+
+    // Distance between the galaxies with the given numbers, counting from 1
+    fn distance(&self, a: usize, b: usize, factor: u64) -> u64 {
+        let get = |number: usize| {
+            number
+                .checked_sub(1)
+                .and_then(|index| self.galaxies.get(index))
+                .unwrap_or_else(|| panic!("No galaxy numbered {}", number))
+        };
+        let (row_a, column_a) = self.expanded(*get(a), factor);
+        let (row_b, column_b) = self.expanded(*get(b), factor);
+
+        row_a.abs_diff(row_b) + column_a.abs_diff(column_b)
+    }
 }
 
-fn get_distance(
-    a: (usize, usize),
-    b: (usize, usize),
-    empty_rows: &Vec<usize>,
-    empty_columns: &Vec<usize>,
-    multiplier: u64,
-) -> u64 {
-    let (x_a, y_a) = a;
-    let (x_b, y_b) = b;
-    let mut distance = 0;
-
-    for i in min(x_a, x_b)..max(x_a, x_b) {
-        if empty_rows.contains(&i) {
-            distance += multiplier;
-        } else {
-            distance += 1;
-        }
+// Number of true values before each index, with one extra entry at the end for the total
+fn prefix_counts(values: &[bool]) -> Vec<u64> {
+    let mut counts = vec![0];
+    for &value in values {
+        counts.push(counts[counts.len() - 1] + value as u64);
     }
+    counts
+}
 
-    for i in min(y_a, y_b)..max(y_a, y_b) {
-        if empty_columns.contains(&i) {
-            distance += multiplier;
-        } else {
-            distance += 1;
-        }
+// Each value is larger than or equal to all the values before it, so it adds its own value for each of them
+fn sum_of_differences(sorted: &[u64]) -> u64 {
+    let mut total = 0;
+    let mut before = 0;
+    for (i, &value) in sorted.iter().enumerate() {
+        total += value * i as u64 - before;
+        before += value;
     }
+    total
+}
 
-    distance
+fn get_column(vector: &[Vec<char>], index: usize) -> Vec<char> {
+    vector.iter().map(|x| x[index]).collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn get_test_map() -> GalaxyMap {
+        GalaxyMap::new(&get_two_dimensional_vector("resources/test_input.txt"))
+    }
+
     #[test]
     fn test_get_column() {
         let test_input = vec![vec!['a', 'b', 'c'], vec!['d', 'e', 'f']];
@@ -106,10 +151,60 @@ mod tests {
     }
 
     #[test]
-    fn test_get_distance() {
-        assert_eq!(
-            get_distance((0, 3), (1, 7), &vec![3, 7], &vec![2, 5, 8], 2),
-            6
-        );
+    fn test_total_distance() {
+        let map = get_test_map();
+
+        assert_eq!(map.galaxies.len(), 9);
+        assert_eq!(map.total_distance(1), 292);
+        assert_eq!(map.total_distance(2), 374);
+        assert_eq!(map.total_distance(10), 1030);
+        assert_eq!(map.total_distance(100), 8410);
+    }
+
+    #[test]
+    fn test_distance() {
+        let map = get_test_map();
+
+        assert_eq!(map.distance(5, 9, 2), 9);
+        assert_eq!(map.distance(1, 7, 2), 15);
+        assert_eq!(map.distance(3, 6, 2), 17);
+        assert_eq!(map.distance(8, 9, 2), 5);
+        assert_eq!(map.distance(9, 8, 2), 5);
+    }
+
+    #[test]
+    fn test_total_distance_matches_pairs() {
+        let map = get_test_map();
+        let count = map.galaxies.len();
+        let pairs: u64 = (1..=count)
+            .flat_map(|a| (a + 1..=count).map(move |b| (a, b)))
+            .map(|(a, b)| map.distance(a, b, 1000000))
+            .sum();
+
+        assert_eq!(map.total_distance(1000000), pairs);
+    }
+
+    #[test]
+    fn test_rectangular_image() {
+        let input = vec![
+            "#...#.".chars().collect(),
+            "......".chars().collect(),
+            ".#....".chars().collect(),
+        ];
+        let map = GalaxyMap::new(&input);
+
+        assert_eq!(map.distance(1, 3, 2), 4);
+        assert_eq!(map.distance(2, 3, 3), 11);
+        assert_eq!(map.total_distance(3), 24);
+    }
+
+    #[test]
+    fn test_zero_factor() {
+        let map = get_test_map();
+
+        // Empty rows and columns disappear entirely
+        assert_eq!(map.expanded((8, 7), 0), (6, 5));
+        assert_eq!(map.expanded((9, 4), 0), (7, 3));
+        assert_eq!(map.total_distance(0), 210);
     }
 }
@@ -1,94 +1,262 @@
-use library::get_lines;
-use std::collections::HashMap;
-use substring::Substring;
+use library::{get_filename_and_options, get_lines};
+
+const UNFOLD_FACTOR: usize = 5;
+const UNFOLD_SEPARATOR: u8 = b'?';
 
 /* This is adapted from a solution by HyperNeutrino https://youtu.be/g3Ms5e7Jdqo?si=FnFBRIBU1ZYgyNc1 */
 fn main() {
-    let file_name = String::from("resources/input.txt");
-    let input = get_lines(&file_name);
-    let mut cache: HashMap<String, u64> = HashMap::new();
-
-    let mut part_one_sum = 0;
-    let mut part_two_sum = 0;
-
-    for line in input {
-        let substrings = line.split(" ").collect::<Vec<&str>>();
-        let mut configuration = substrings[0].to_string();
-        let part_one_numbers = substrings[1]
-            .split(",")
-            .map(|n| {
-                n.parse::<usize>()
-                    .expect("Failed to parse string into number")
-            })
-            .collect::<Vec<usize>>();
-        part_one_sum += count(&configuration, &part_one_numbers, &mut cache);
-
-        configuration.push_str("?");
-        configuration = configuration.repeat(5);
-        configuration.pop();
-        let mut numbers_section = String::from(substrings[1]);
-        numbers_section.push_str(",");
-        numbers_section = numbers_section.repeat(5);
-        numbers_section.pop();
-        let part_two_numbers = numbers_section
-            .split(",")
-            .map(|n| {
-                n.parse::<usize>()
-                    .expect("Failed to parse string into number")
-            })
-            .collect::<Vec<usize>>();
-        part_two_sum += count(&configuration, &part_two_numbers, &mut cache);
+    // --enumerate prints every arrangement of each row before it is unfolded
+    let (file_name, options) = get_filename_and_options(&["enumerate"]);
+    let rows: Vec<Row> = get_lines(&file_name)
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Row::parse(line))
+        .collect();
+
+    if options.is_set("enumerate") {
+        for row in &rows {
+            for arrangement in row.arrangements() {
+                println!("{}", arrangement);
+            }
+            println!();
+        }
     }
 
+    let part_one_sum: u64 = rows.iter().map(|row| row.count_arrangements()).sum();
+    let part_two_sum: u64 = rows
+        .iter()
+        .map(|row| {
+            row.unfold(UNFOLD_FACTOR, UNFOLD_SEPARATOR)
+                .count_arrangements()
+        })
+        .sum();
+
     println!("Part 1: {}", part_one_sum);
     println!("Part 2: {}", part_two_sum);
 }
 
-fn count(configuration: &String, numbers: &Vec<usize>, cache: &mut HashMap<String, u64>) -> u64 {
-    if configuration == "" {
-        return if numbers.len() == 0 { 1 } else { 0 };
+// A row of springs, each b'.', b'#' or b'?', and the sizes of the groups of damaged springs in it
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Row {
+    springs: Vec<u8>,
+    groups: Vec<usize>,
+}
+
+impl Row {
+    // e.g. "???.### 1,1,3"
+    fn parse(line: &str) -> Self {
+        let (springs, groups) = line
+            .split_once(' ')
+            .unwrap_or_else(|| panic!("Invalid row {}", line));
+        if let Some(spring) = springs.bytes().find(|spring| !b".#?".contains(spring)) {
+            panic!("Invalid spring {}", spring as char);
+        }
+
+        Self {
+            springs: springs.bytes().collect(),
+            groups: groups
+                .split(',')
+                .map(|n| n.parse().expect("Failed to parse string into number"))
+                .collect(),
+        }
     }
-    if numbers.len() == 0 {
-        return if configuration.contains("#") { 0 } else { 1 };
+
+    // The springs repeated `factor` times with `separator` between each copy, and the groups repeated to match
+    fn unfold(&self, factor: usize, separator: u8) -> Self {
+        let mut springs = Vec::with_capacity((self.springs.len() + 1) * factor);
+        for copy in 0..factor {
+            if copy > 0 {
+                springs.push(separator);
+            }
+            springs.extend_from_slice(&self.springs);
+        }
+
+        Self {
+            springs,
+            groups: self.groups.repeat(factor),
+        }
     }
 
-    let mut key = configuration.clone();
-    key.push_str(
-        &numbers
-            .iter()
-            .map(|f| f.to_string())
-            .collect::<Vec<String>>()
-            .join(","),
-    );
+    // Whether the group `group` can start at `position`, ending before an operational or unknown spring
+    fn fits(&self, position: usize, group: usize, operational_before: &[usize]) -> bool {
+        let end = position + self.groups[group];
+        end <= self.springs.len()
+            && operational_before[end] == operational_before[position]
+            && self.springs.get(end) != Some(&b'#')
+    }
+
+    /* ways[position][group] is the number of arrangements of the springs from `position` on that hold the groups from
+     * `group` on. A spring that may be operational leaves the groups as they are for the next position, and one that
+     * may be damaged can start the next group if that fits, skipping the spring after the group since it has to be
+     * operational. Filled in from the end of the row backwards, with one extra position standing for the empty rest of
+     * the row.
+     */
+    fn get_ways(&self) -> Vec<Vec<u64>> {
+        let length = self.springs.len();
+        let group_count = self.groups.len();
+        let operational_before = self.get_operational_before();
+
+        let mut ways = vec![vec![0u64; group_count + 1]; length + 1];
+        ways[length][group_count] = 1;
+        for position in (0..length).rev() {
+            let spring = self.springs[position];
+            for group in 0..=group_count {
+                let mut total = 0;
+                if spring != b'#' {
+                    total += ways[position + 1][group];
+                }
+                if spring != b'.'
+                    && group < group_count
+                    && self.fits(position, group, &operational_before)
+                {
+                    let next = (position + self.groups[group] + 1).min(length);
+                    total += ways[next][group + 1];
+                }
+                ways[position][group] = total;
+            }
+        }
+
+        ways
+    }
+
+    fn count_arrangements(&self) -> u64 {
+        self.get_ways()[0][0]
+    }
+
+    /* Every arrangement written out with the unknown springs filled in. Only branches with at least one arrangement
+     * are followed, so the work is proportional to the output, but that grows quickly and is only practical for rows
+     * that have not been unfolded.
+     */
+    fn arrangements(&self) -> Vec<String> {
+        let ways = self.get_ways();
+        let operational_before = self.get_operational_before();
+        let mut arrangements = vec![];
+        if ways[0][0] == 0 {
+            return arrangements;
+        }
+
+        let mut stack = vec![(0, 0, vec![])];
+        while let Some((position, group, mut springs)) = stack.pop() {
+            if position >= self.springs.len() {
+                arrangements.push(String::from_utf8(springs).unwrap());
+                continue;
+            }
 
-    if cache.contains_key(&key) {
-        return *cache.get(&key).unwrap();
+            let spring = self.springs[position];
+            let start_group = spring != b'.'
+                && group < self.groups.len()
+                && ways[(position + self.groups[group] + 1).min(self.springs.len())][group + 1] > 0
+                && self.fits(position, group, &operational_before);
+            if spring != b'#' && ways[position + 1][group] > 0 {
+                let mut operational = springs.clone();
+                operational.push(b'.');
+                stack.push((position + 1, group, operational));
+            }
+            if start_group {
+                let end = position + self.groups[group];
+                springs.resize(end, b'#');
+                if end < self.springs.len() {
+                    springs.push(b'.');
+                }
+                stack.push((end + 1, group + 1, springs));
+            }
+        }
+
+        arrangements.sort();
+        arrangements
+    }
+
+    // The number of springs known to be operational before each position
+    fn get_operational_before(&self) -> Vec<usize> {
+        let mut operational_before = vec![0; self.springs.len() + 1];
+        for (i, &spring) in self.springs.iter().enumerate() {
+            operational_before[i + 1] = operational_before[i] + usize::from(spring == b'.');
+        }
+        operational_before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(line: &str) -> u64 {
+        Row::parse(line).count_arrangements()
+    }
+
+    fn count_unfolded(line: &str) -> u64 {
+        Row::parse(line)
+            .unfold(UNFOLD_FACTOR, UNFOLD_SEPARATOR)
+            .count_arrangements()
     }
 
-    let mut result = 0;
-    let config_chars: Vec<char> = configuration.chars().collect();
+    #[test]
+    fn test_parts() {
+        let rows: Vec<Row> = get_lines("resources/test_input.txt")
+            .iter()
+            .map(|line| Row::parse(line))
+            .collect();
 
-    if config_chars[0] == '.' || config_chars[0] == '?' {
-        result += count(
-            &String::from(configuration.substring(1, configuration.len())),
-            numbers,
-            cache,
+        assert_eq!(
+            rows.iter().map(|row| row.count_arrangements()).sum::<u64>(),
+            21
+        );
+        assert_eq!(
+            rows.iter()
+                .map(|row| row
+                    .unfold(UNFOLD_FACTOR, UNFOLD_SEPARATOR)
+                    .count_arrangements())
+                .sum::<u64>(),
+            525152
         );
     }
 
-    if (config_chars[0] == '#' || config_chars[0] == '?')
-        && numbers[0] <= configuration.len()
-        && !configuration.substring(0, numbers[0]).contains('.')
-        && (numbers[0] == configuration.len() || config_chars[numbers[0]] != '#')
-    {
-        result += count(
-            &String::from(configuration.substring(numbers[0] + 1, configuration.len())),
-            &Vec::from(&numbers[1..numbers.len()]),
-            cache,
+    #[test]
+    fn test_count_arrangements() {
+        assert_eq!(count("???.### 1,1,3"), 1);
+        assert_eq!(count(".??..??...?##. 1,1,3"), 4);
+        assert_eq!(count("?###???????? 3,2,1"), 10);
+        assert_eq!(count("#.# 2"), 0);
+        assert_eq!(count("... 1"), 0);
+    }
+
+    #[test]
+    fn test_count_unfolded() {
+        assert_eq!(count_unfolded("???.### 1,1,3"), 1);
+        assert_eq!(count_unfolded(".??..??...?##. 1,1,3"), 16384);
+        assert_eq!(count_unfolded("?###???????? 3,2,1"), 506250);
+    }
+
+    #[test]
+    fn test_unfold() {
+        let row = Row::parse(".# 1");
+
+        assert_eq!(
+            row.unfold(3, b'?'),
+            Row {
+                springs: b".#?.#?.#".to_vec(),
+                groups: vec![1, 1, 1]
+            }
         );
+        // Both groups can go in either copy, or one in each, and a damaged separator can hold one of them too
+        let row = Row::parse("??? 1");
+        assert_eq!(row.unfold(2, b'.').count_arrangements(), 11);
+        assert_eq!(row.unfold(2, b'?').count_arrangements(), 15);
     }
 
-    cache.insert(key, result);
+    #[test]
+    fn test_arrangements() {
+        assert_eq!(Row::parse("???.### 1,1,3").arrangements(), vec!["#.#.###"]);
+        assert_eq!(Row::parse("?#?? 2,1").arrangements(), vec!["##.#"]);
+        assert_eq!(
+            Row::parse("???? 1,1").arrangements(),
+            vec!["#.#.", "#..#", ".#.#"]
+        );
 
-    result
+        let row = Row::parse("?###???????? 3,2,1");
+        let arrangements = row.arrangements();
+        assert_eq!(arrangements.len() as u64, row.count_arrangements());
+        assert!(arrangements.contains(&String::from(".###.##.#...")));
+        assert!(arrangements.contains(&String::from(".###....##.#")));
+    }
 }
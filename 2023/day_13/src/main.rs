@@ -1,92 +1,219 @@
-use std::{cmp::min, fs::read_to_string};
+use std::fs::read_to_string;
 
 use library::get_filename_arg;
 
+const PART_ONE_SMUDGES: u32 = 0;
+const PART_TWO_SMUDGES: u32 = 1;
+
 fn main() {
     let file_name = get_filename_arg();
-    let input: String = read_to_string(file_name).unwrap().parse().unwrap();
-    let input: Vec<&str> = input.split("\n\n").collect();
-
-    let mut part_one_total = 0;
-    let mut part_two_total = 0;
+    let input = read_to_string(file_name).expect("Failed to read file");
+    let patterns = parse_patterns(&input);
 
-    for block in input {
-        let grid: Vec<String> = block
-            .split("\n")
-            .filter(|l| *l != "")
-            .map(|l| String::from(l))
-            .collect();
+    println!("Part 1: {}", summarize(&patterns, PART_ONE_SMUDGES));
+    println!("Part 2: {}", summarize(&patterns, PART_TWO_SMUDGES));
+}
 
-        part_one_total += get_reflection_point_part_one(&grid) * 100;
-        part_two_total += get_reflection_point_part_two(&grid) * 100;
+fn parse_patterns(input: &str) -> Vec<Pattern> {
+    input
+        .split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .map(Pattern::parse)
+        .collect()
+}
 
-        let columns_grid = get_columns_grid(&grid);
-        part_one_total += get_reflection_point_part_one(&columns_grid);
-        part_two_total += get_reflection_point_part_two(&columns_grid);
-    }
+fn summarize(patterns: &[Pattern], smudges: u32) -> usize {
+    patterns
+        .iter()
+        .flat_map(|pattern| pattern.reflections(smudges))
+        .map(|reflection| reflection.summary())
+        .sum()
+}
 
-    println!("Part 1: {part_one_total}");
-    println!("Part 1: {part_two_total}");
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Orientation {
+    // Line between two rows
+    Horizontal,
+    // Line between two columns
+    Vertical,
 }
 
-fn get_above_and_below(grid: &Vec<String>, index: usize) -> (Vec<&String>, Vec<&String>) {
-    let above: Vec<&String> = grid[..index]
-        .into_iter()
-        .rev()
-        .filter(|s| **s != "")
-        .collect();
-    let below: Vec<&String> = grid[index..].into_iter().filter(|s| **s != "").collect();
-
-    let min_length = min(above.len(), below.len());
-    let above = above[..min_length].to_vec();
-    let below = below[..min_length].to_vec();
-    (above, below)
+/* A line with `position` rows above it or columns to the left of it. Each smudge is given as the two cells, as (row,
+* column), that differ across the line, since cleaning either one of them makes the reflection perfect.
+*/
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Reflection {
+    orientation: Orientation,
+    position: usize,
+    smudges: Vec<((usize, usize), (usize, usize))>,
 }
 
-fn get_reflection_point_part_one(grid: &Vec<String>) -> usize {
-    for i in 1..grid.len() {
-        let (above, below) = get_above_and_below(grid, i);
-        if above == below {
-            return i;
+impl Reflection {
+    fn summary(&self) -> usize {
+        match self.orientation {
+            Orientation::Horizontal => self.position * 100,
+            Orientation::Vertical => self.position,
         }
     }
-
-    0
 }
 
-fn get_reflection_point_part_two(grid: &Vec<String>) -> usize {
-    for i in 1..grid.len() {
-        let (above, below) = get_above_and_below(grid, i);
+// Each row and column stored as a bitmask of its rocks, so comparing two of them is a single xor
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Pattern {
+    rows: Vec<u64>,
+    columns: Vec<u64>,
+}
 
-        let mut differences = 0;
+impl Pattern {
+    fn parse(block: &str) -> Self {
+        let lines: Vec<&str> = block.lines().filter(|line| !line.is_empty()).collect();
+        let width = lines.first().map_or(0, |line| line.len());
+        if width > 64 || lines.len() > 64 {
+            panic!("Patterns can be at most 64 cells wide and high");
+        }
 
-        for (x, y) in above.iter().zip(below.iter()) {
-            for (a, b) in x.chars().zip(y.chars()) {
-                if a != b {
-                    differences += 1;
+        let mut rows = vec![0; lines.len()];
+        let mut columns = vec![0; width];
+        for (row, line) in lines.iter().enumerate() {
+            if line.len() != width {
+                panic!("All rows of a pattern must be the same length");
+            }
+            for (column, cell) in line.chars().enumerate() {
+                match cell {
+                    '#' => {
+                        rows[row] |= 1 << column;
+                        columns[column] |= 1 << row;
+                    }
+                    '.' => {}
+                    x => panic!("Invalid pattern cell {}", x),
                 }
             }
         }
 
-        if differences == 1 {
-            return i;
-        }
+        Self { rows, columns }
+    }
+
+    // Every line, in either orientation, across which exactly `smudges` cells differ from their mirror image
+    fn reflections(&self, smudges: u32) -> Vec<Reflection> {
+        let horizontal = find_lines(&self.rows, smudges)
+            .into_iter()
+            .map(|position| Reflection {
+                orientation: Orientation::Horizontal,
+                position,
+                smudges: differences(&self.rows, position),
+            });
+        let vertical = find_lines(&self.columns, smudges)
+            .into_iter()
+            .map(|position| Reflection {
+                orientation: Orientation::Vertical,
+                position,
+                smudges: differences(&self.columns, position)
+                    .into_iter()
+                    .map(|((a_column, a_row), (b_column, b_row))| {
+                        ((a_row, a_column), (b_row, b_column))
+                    })
+                    .collect(),
+            });
+
+        horizontal.chain(vertical).collect()
     }
+}
+
+fn find_lines(lines: &[u64], smudges: u32) -> Vec<usize> {
+    (1..lines.len())
+        .filter(|&position| {
+            mirrored_pairs(lines, position)
+                .map(|(a, b)| (lines[a] ^ lines[b]).count_ones())
+                .sum::<u32>()
+                == smudges
+        })
+        .collect()
+}
 
-    0
+// Index pairs reflected onto each other by a line after `position` lines, stopping at whichever edge is nearest
+fn mirrored_pairs(lines: &[u64], position: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..position.min(lines.len() - position)).map(move |i| (position - 1 - i, position + i))
 }
 
-fn get_columns_grid(grid: &Vec<String>) -> Vec<String> {
-    let char_grid: Vec<Vec<char>> = grid.iter().map(|l| l.chars().collect()).collect();
-    let mut columns_vec: Vec<String> = vec![];
-    for i in 0..char_grid[0].len() {
-        let mut char_vec = vec![];
-        for j in (0..char_grid.len()).rev() {
-            char_vec.push(char_grid[j][i]);
+// The differing cells across a line, as (line index, index within the line) for both sides
+fn differences(lines: &[u64], position: usize) -> Vec<((usize, usize), (usize, usize))> {
+    let mut cells = vec![];
+    for (a, b) in mirrored_pairs(lines, position) {
+        let mut difference = lines[a] ^ lines[b];
+        while difference != 0 {
+            let bit = difference.trailing_zeros() as usize;
+            cells.push(((a, bit), (b, bit)));
+            difference &= difference - 1;
         }
-        let char_vec: String = char_vec.into_iter().collect();
-        columns_vec.push(char_vec);
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_patterns() -> Vec<Pattern> {
+        parse_patterns(&read_to_string("resources/test_input.txt").unwrap())
     }
 
-    columns_vec
+    #[test]
+    fn test_summarize() {
+        let patterns = get_test_patterns();
+
+        assert_eq!(patterns.len(), 2);
+        assert_eq!(summarize(&patterns, 0), 405);
+        assert_eq!(summarize(&patterns, 1), 400);
+    }
+
+    #[test]
+    fn test_reflections() {
+        let patterns = get_test_patterns();
+
+        assert_eq!(
+            patterns[0].reflections(0),
+            vec![Reflection {
+                orientation: Orientation::Vertical,
+                position: 5,
+                smudges: vec![],
+            }]
+        );
+        assert_eq!(
+            patterns[0].reflections(1),
+            vec![Reflection {
+                orientation: Orientation::Horizontal,
+                position: 3,
+                smudges: vec![((0, 0), (5, 0))],
+            }]
+        );
+        assert_eq!(
+            patterns[1].reflections(1),
+            vec![Reflection {
+                orientation: Orientation::Horizontal,
+                position: 1,
+                smudges: vec![((0, 4), (1, 4))],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_vertical_smudges() {
+        let pattern = Pattern::parse("#..#\n.##.\n#.##");
+        let reflections = pattern.reflections(1);
+
+        assert_eq!(
+            reflections,
+            vec![Reflection {
+                orientation: Orientation::Vertical,
+                position: 2,
+                smudges: vec![((2, 1), (2, 2))],
+            }]
+        );
+        let positions: Vec<usize> = pattern
+            .reflections(2)
+            .iter()
+            .map(|reflection| reflection.position)
+            .collect();
+        assert_eq!(positions, vec![3]);
+    }
 }
@@ -1,171 +1,300 @@
 use std::collections::HashMap;
+use std::fmt;
 
-use library::{get_filename_arg, get_two_dimensional_vector};
+use library::{get_filename_arg, get_two_dimensional_vector, Directions};
+
+const NUM_CYCLES: usize = 1000000000;
+const SPIN_CYCLE: [Directions; 4] = [
+    Directions::UP,
+    Directions::LEFT,
+    Directions::DOWN,
+    Directions::RIGHT,
+];
 
 fn main() {
     let file_name = get_filename_arg();
-    let mut input = get_two_dimensional_vector(&file_name);
-    println!("Part 1: {}", part_one(&mut input));
-    println!("Part 2: {}", part_two(&mut input));
+    let input = get_two_dimensional_vector(&file_name);
+    let platform = Platform::new(&input);
+    println!("Part 1: {}", part_one(&platform));
+    println!("Part 2: {}", part_two(&platform));
 }
 
-// Maybe if I spent more time on this I could come up with cleaner solution,
-// but I think the redundancy is better than have a bunch of repeated match statements
-fn tilt_north(input: &mut Vec<Vec<char>>, row: usize, column: usize) {
-    for i in (1..=row).rev() {
-        if input[i - 1][column] != '.' {
-            return;
-        }
-        input[i - 1][column] = 'O';
-        input[i][column] = '.';
-    }
+fn part_one(platform: &Platform) -> usize {
+    let mut platform = platform.clone();
+    platform.tilt(Directions::UP);
+    platform.load()
 }
 
-fn tilt_south(input: &mut Vec<Vec<char>>, row: usize, column: usize) {
-    for i in row..input.len() - 1 {
-        if input[i + 1][column] != '.' {
-            return;
-        }
-        input[i + 1][column] = 'O';
-        input[i][column] = '.';
-    }
+fn part_two(platform: &Platform) -> usize {
+    platform.load_after_cycles(&SPIN_CYCLE, NUM_CYCLES)
 }
 
-fn tilt_east(input: &mut Vec<Vec<char>>, row: usize, column: usize) {
-    for i in column..input[row].len() - 1 {
-        if input[row][i + 1] != '.' {
-            return;
-        }
-        input[row][i + 1] = 'O';
-        input[row][i] = '.';
-    }
+/* The rocks are kept as bitsets twice over, once per row with a bit for each column and once per column with a bit for
+* each row, so a tilt in any direction works on whole lines at a time. The runs of cells between cube rocks never
+* change, so their masks are worked out once. Tilting a line then only needs to count the round rocks in each run and
+* pack that many bits against the run's end.
+*/
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Platform {
+    width: usize,
+    height: usize,
+    rows: Vec<u128>,
+    columns: Vec<u128>,
+    cubes: Vec<u128>,
+    row_segments: Vec<Vec<u128>>,
+    column_segments: Vec<Vec<u128>>,
 }
 
-fn tilt_west(input: &mut Vec<Vec<char>>, row: usize, column: usize) {
-    for i in (1..=column).rev() {
-        if input[row][i - 1] != '.' {
-            return;
+impl Platform {
+    fn new(input: &[Vec<char>]) -> Self {
+        let height = input.len();
+        let width = input.first().map_or(0, |row| row.len());
+        if width > 128 || height > 128 {
+            panic!("Platforms can be at most 128 cells wide and high");
+        }
+
+        let mut rows = vec![0; height];
+        let mut cubes = vec![0; height];
+        let mut column_cubes = vec![0; width];
+        for (i, row) in input.iter().enumerate() {
+            if row.len() != width {
+                panic!("All rows of the platform must be the same length");
+            }
+            for (j, &cell) in row.iter().enumerate() {
+                match cell {
+                    'O' => rows[i] |= 1 << j,
+                    '#' => {
+                        cubes[i] |= 1 << j;
+                        column_cubes[j] |= 1 << i;
+                    }
+                    '.' => {}
+                    x => panic!("Invalid platform cell {}", x),
+                }
+            }
+        }
+
+        Self {
+            width,
+            height,
+            columns: transpose(&rows, width),
+            rows,
+            row_segments: cubes.iter().map(|&line| segments(line, width)).collect(),
+            column_segments: column_cubes
+                .iter()
+                .map(|&line| segments(line, height))
+                .collect(),
+            cubes,
         }
-        input[row][i - 1] = 'O';
-        input[row][i] = '.';
     }
-}
 
-fn north_cycle(input: &mut Vec<Vec<char>>) {
-    for i in 0..input.len() {
-        for j in 0..input[i].len() {
-            if input[i][j] == 'O' {
-                tilt_north(input, i, j);
+    fn tilt(&mut self, direction: Directions) {
+        match direction {
+            Directions::UP | Directions::DOWN => {
+                roll(
+                    &mut self.columns,
+                    &self.column_segments,
+                    direction == Directions::UP,
+                );
+                self.rows = transpose(&self.columns, self.height);
+            }
+            Directions::LEFT | Directions::RIGHT => {
+                roll(
+                    &mut self.rows,
+                    &self.row_segments,
+                    direction == Directions::LEFT,
+                );
+                self.columns = transpose(&self.rows, self.width);
             }
         }
     }
-}
 
-fn south_cycle(input: &mut Vec<Vec<char>>) {
-    for i in (0..input.len()).rev() {
-        for j in 0..input[i].len() {
-            if input[i][j] == 'O' {
-                tilt_south(input, i, j);
+    // Tilts in each direction of the sequence in turn, returning the load after every tilt
+    fn tilt_sequence(&mut self, sequence: &[Directions]) -> Vec<usize> {
+        sequence
+            .iter()
+            .map(|&direction| {
+                self.tilt(direction);
+                self.load()
+            })
+            .collect()
+    }
+
+    /* Runs the sequence until the rocks end up somewhere they have been before, at which point they are stuck in a
+     * loop and the load after any number of cycles can be looked up. The tilting is done on a copy, so the platform
+     * itself is left as it was. Based on kwshi's solution
+     * https://github.com/kwshi/advent-of-code/blob/main/python/2023/14.py
+     */
+    fn load_after_cycles(&self, sequence: &[Directions], cycles: usize) -> usize {
+        let mut platform = self.clone();
+        let mut seen: HashMap<Vec<u128>, usize> = HashMap::from([(platform.rows.clone(), 0)]);
+        let mut loads = vec![platform.load()];
+
+        for i in 1..=cycles {
+            let step_loads = platform.tilt_sequence(sequence);
+            loads.push(
+                step_loads
+                    .last()
+                    .copied()
+                    .unwrap_or_else(|| platform.load()),
+            );
+
+            if let Some(start) = seen.insert(platform.rows.clone(), i) {
+                return loads[start + (cycles - start) % (i - start)];
             }
         }
+
+        loads[cycles]
+    }
+
+    // Each round rock adds the number of rows from it to the south edge, counting its own row
+    fn load(&self) -> usize {
+        self.rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| (self.height - i) * row.count_ones() as usize)
+            .sum()
     }
 }
 
-fn east_cycle(input: &mut Vec<Vec<char>>) {
-    for i in (0..input[0].len()).rev() {
-        for j in 0..input.len() {
-            if input[j][i] == 'O' {
-                tilt_east(input, j, i);
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (row, cubes) in self.rows.iter().zip(&self.cubes) {
+            for j in 0..self.width {
+                let cell = if row & (1 << j) != 0 {
+                    'O'
+                } else if cubes & (1 << j) != 0 {
+                    '#'
+                } else {
+                    '.'
+                };
+                write!(f, "{cell}")?;
             }
+            writeln!(f)?;
         }
+        Ok(())
     }
 }
 
-fn west_cycle(input: &mut Vec<Vec<char>>) {
-    for i in 0..input[0].len() {
-        for j in 0..input.len() {
-            if input[j][i] == 'O' {
-                tilt_west(input, j, i);
+// Masks of each run of cells without a cube rock along a line of the given length
+fn segments(cubes: u128, length: usize) -> Vec<u128> {
+    let mut segments = vec![];
+    let mut start = 0;
+    for i in 0..=length {
+        if i == length || cubes & (1 << i) != 0 {
+            if i > start {
+                segments.push(low_bits(i - start) << start);
             }
+            start = i + 1;
         }
     }
+    segments
 }
 
-fn cycle(input: &mut Vec<Vec<char>>) {
-    north_cycle(input);
-    west_cycle(input);
-    south_cycle(input);
-    east_cycle(input);
+// Moves every round rock in each line to the low (towards bit 0) or high end of the run it is in
+fn roll(lines: &mut [u128], segments: &[Vec<u128>], towards_low: bool) {
+    for (line, line_segments) in lines.iter_mut().zip(segments) {
+        let mut rolled = 0;
+        for &segment in line_segments {
+            let count = (*line & segment).count_ones() as usize;
+            if count == 0 {
+                continue;
+            }
+            rolled |= if towards_low {
+                low_bits(count) << segment.trailing_zeros()
+            } else {
+                let end = 128 - segment.leading_zeros() as usize;
+                low_bits(count) << (end - count)
+            };
+        }
+        *line = rolled;
+    }
 }
 
-// It's probably possible to make this work with part 2 as well, but Rust tried to fight me the whole way
-fn calculate_load(input: &Vec<Vec<char>>) -> usize {
-    let mut sum = 0;
-    for i in 0..input.len() {
-        let rock_count = input[i]
-            .iter()
-            .filter(|x| **x == 'O')
-            .collect::<Vec<&char>>()
-            .len();
-        sum += (input.len() - i) * rock_count;
+fn low_bits(count: usize) -> u128 {
+    if count >= 128 {
+        u128::MAX
+    } else {
+        (1 << count) - 1
     }
-
-    sum
 }
 
-fn part_one(input: &mut Vec<Vec<char>>) -> usize {
-    for i in 0..input.len() {
-        for j in 0..input[i].len() {
-            if input[i][j] == 'O' {
-                tilt_north(input, i, j);
+fn transpose(lines: &[u128], length: usize) -> Vec<u128> {
+    let mut transposed = vec![0; length];
+    for (i, line) in lines.iter().enumerate() {
+        for (j, other) in transposed.iter_mut().enumerate() {
+            if line & (1 << j) != 0 {
+                *other |= 1 << i;
             }
         }
     }
-
-    calculate_load(input)
+    transposed
 }
 
-// Initially part 2 by observing that 1000 cycles gets you the right answer. This more general soultion
-// is based on kwshi's soluition
-// See https://github.com/kwshi/advent-of-code/blob/main/python/2023/14.py
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-const NUM_CYCLES: usize = 1000000000;
+    fn get_test_platform() -> Platform {
+        Platform::new(&get_two_dimensional_vector("resources/test_input.txt"))
+    }
 
-fn part_two(input: &mut Vec<Vec<char>>) -> usize {
-    let mut state_map: HashMap<String, usize> = HashMap::new();
-    let mut start = 0;
-    let mut length = 0;
-
-    for i in 1..NUM_CYCLES {
-        cycle(input);
-        let current_input: String = input.iter().map(|l| l.iter().collect::<String>()).collect();
-        if state_map.contains_key(&current_input) {
-            start = *state_map.get(&current_input).unwrap();
-            length = i - start;
-            break;
-        } else {
-            state_map.insert(current_input, i);
-        }
+    #[test]
+    fn test_parts() {
+        let platform = get_test_platform();
+
+        assert_eq!(part_one(&platform), 136);
+        assert_eq!(part_two(&platform), 64);
     }
 
-    let current_lines: Vec<char> = state_map
-        .keys()
-        .find(|key| *state_map.get(*key).unwrap() == start + ((NUM_CYCLES - start) % length))
-        .unwrap()
-        .chars()
-        .collect();
+    #[test]
+    fn test_spin_cycle() {
+        let mut platform = get_test_platform();
+        platform.tilt_sequence(&SPIN_CYCLE);
+        let expected = "\
+.....#....
+....#...O#
+...OO##...
+.OO#......
+.....OOO#.
+.O#...O#.#
+....O#....
+......OOOO
+#...O###..
+#..OO#....
+";
 
-    let current_lines: Vec<&[char]> = current_lines.chunks(input[0].len()).collect();
+        assert_eq!(platform.to_string(), expected);
+    }
 
-    let mut sum = 0;
-    for i in 0..current_lines.len() {
-        let rock_count = current_lines[i]
-            .iter()
-            .filter(|x| **x == 'O')
-            .collect::<Vec<&char>>()
-            .len();
-        sum += (input.len() - i) * rock_count;
+    #[test]
+    fn test_tilt_sequence_loads() {
+        let mut platform = get_test_platform();
+        let loads = platform.tilt_sequence(&[Directions::UP, Directions::DOWN, Directions::UP]);
+
+        assert_eq!(loads[0], 136);
+        assert_eq!(loads[2], 136);
+        assert!(loads[1] < loads[0]);
     }
 
-    sum
+    #[test]
+    fn test_load_after_cycles() {
+        let platform = get_test_platform();
+        let mut repeated = get_test_platform();
+        for _ in 0..20 {
+            repeated.tilt_sequence(&SPIN_CYCLE);
+        }
+
+        assert_eq!(platform.load_after_cycles(&SPIN_CYCLE, 0), platform.load());
+        assert_eq!(platform.load_after_cycles(&SPIN_CYCLE, 20), repeated.load());
+        // Asking again gives the same answer, as the platform is not left part way through the cycles
+        assert_eq!(platform.load_after_cycles(&SPIN_CYCLE, 20), repeated.load());
+        assert_eq!(platform, get_test_platform());
+    }
+
+    #[test]
+    fn test_segments() {
+        assert_eq!(segments(0b0100, 5), vec![0b00011, 0b11000]);
+        assert_eq!(segments(0b0001, 3), vec![0b110]);
+        assert_eq!(segments(0, 128), vec![u128::MAX]);
+    }
 }
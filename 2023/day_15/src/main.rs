@@ -1,21 +1,24 @@
-use library::get_filename_arg;
-use regex::Regex;
+use library::get_filename_and_options;
+use std::collections::HashMap;
+use std::fmt;
 use std::fs::read_to_string;
 
+const BOX_COUNT: usize = 256;
+
 fn main() {
-    let file_name = get_filename_arg();
-    let input: String = read_to_string(file_name).unwrap().parse().unwrap();
-    let input: Vec<char> = input.chars().filter(|c| *c != '\n').collect();
-    let input: Vec<&[char]> = input.split(|s| *s == ',').collect();
+    // --trace prints the boxes after every step, the way the puzzle statement walks through the example
+    let (file_name, options) = get_filename_and_options(&["trace"]);
+    let input = read_to_string(file_name).expect("Failed to read file");
+    let input: Vec<&str> = input.trim_end_matches('\n').split(',').collect();
 
     println!("Part 1: {}", part_one(&input));
-    println!("Part 2: {}", part_two(&input));
+    println!("Part 2: {}", part_two(&input, options.is_set("trace")));
 }
 
-fn get_hash_code(word: &[char]) -> usize {
+fn get_hash_code(word: &str) -> usize {
     let mut current_value = 0;
-    for character in word {
-        current_value += *character as usize;
+    for character in word.chars() {
+        current_value += character as usize;
         current_value *= 17;
         current_value %= 256;
     }
@@ -23,64 +26,262 @@ fn get_hash_code(word: &[char]) -> usize {
     current_value
 }
 
-fn part_one(input: &Vec<&[char]>) -> usize {
-    let mut sum = 0;
+fn part_one(input: &[&str]) -> usize {
+    input.iter().map(|step| get_hash_code(step)).sum()
+}
+
+fn part_two(input: &[&str], trace: bool) -> usize {
+    let mut boxes = LensBoxes::new();
     for step in input {
-        sum += get_hash_code(step);
+        let parsed = Step::parse(step).unwrap_or_else(|error| panic!("{}", error));
+        boxes.apply(&parsed);
+        if trace {
+            println!("After \"{}\":\n{}", step, boxes);
+        }
     }
 
-    sum
+    boxes.focusing_power()
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Step {
+    Insert { label: String, focal: usize },
+    Remove { label: String },
+}
+
+#[derive(PartialEq, Eq, Debug)]
+enum StepError {
+    MissingOperation(String),
+    EmptyLabel(String),
+    InvalidFocalLength(String),
 }
 
-fn part_two(input: &Vec<&[char]>) -> usize {
-    let operation_regex = Regex::new(r"=|-").unwrap();
-    let value_regex = Regex::new(r"\d+").unwrap();
-    let mut boxes: Vec<Vec<(String, usize)>> = vec![vec![]; 256];
-
-    for line in input {
-        let line_string: String = line.iter().collect();
-        let operation = operation_regex.find(&line_string).unwrap();
-        let label = &line[0..operation.start()];
-        let hash_code = get_hash_code(label);
-        let label: String = label.iter().collect();
-        let operation = operation.as_str();
-        let value = value_regex.find(&line_string);
-
-        let index = boxes[hash_code].iter().position(|x| x.0 == label);
-        if operation == "-" {
-            if index.is_some() {
-                boxes[hash_code].remove(index.unwrap());
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingOperation(step) => write!(f, "Step \"{}\" has no = or - operation", step),
+            Self::EmptyLabel(step) => write!(f, "Step \"{}\" has no label", step),
+            Self::InvalidFocalLength(step) => {
+                write!(
+                    f,
+                    "Step \"{}\" does not have a focal length from 1 to 9",
+                    step
+                )
             }
-        } else {
-            let value = value.unwrap().as_str().parse().unwrap();
-            if index.is_some() {
-                boxes[hash_code][index.unwrap()].1 = value;
-            } else {
-                boxes[hash_code].push((label, value));
+        }
+    }
+}
+
+impl Step {
+    fn parse(step: &str) -> Result<Self, StepError> {
+        let step = step.trim();
+        let Some(operation) = step.find(['=', '-']) else {
+            return Err(StepError::MissingOperation(String::from(step)));
+        };
+        let label = &step[..operation];
+        if label.is_empty() {
+            return Err(StepError::EmptyLabel(String::from(step)));
+        }
+
+        match &step[operation..] {
+            "-" => Ok(Self::Remove {
+                label: String::from(label),
+            }),
+            value => match value[1..].parse() {
+                Ok(focal @ 1..=9) if value.starts_with('=') => Ok(Self::Insert {
+                    label: String::from(label),
+                    focal,
+                }),
+                _ => Err(StepError::InvalidFocalLength(String::from(step))),
+            },
+        }
+    }
+
+    fn label(&self) -> &str {
+        match self {
+            Self::Insert { label, .. } | Self::Remove { label } => label,
+        }
+    }
+}
+
+/* Removing a lens leaves an empty slot behind rather than shifting every lens after it, so that the slot each label
+* is in can be looked up directly. The slots are compacted once they are mostly empty.
+*/
+#[derive(Clone, Default, Debug)]
+struct LensBox {
+    slots: Vec<Option<(String, usize)>>,
+    positions: HashMap<String, usize>,
+}
+
+impl LensBox {
+    fn insert(&mut self, label: &str, focal: usize) {
+        match self.positions.get(label) {
+            Some(&position) => self.slots[position] = Some((String::from(label), focal)),
+            None => {
+                self.positions.insert(String::from(label), self.slots.len());
+                self.slots.push(Some((String::from(label), focal)));
             }
         }
     }
 
-    let mut sum = 0;
-    for i in 0..boxes.len() {
-        for j in 0..boxes[i].len() {
-            let slot = 1 + j;
-            let focal_length = boxes[i][j].1;
-            let box_num = 1 + i;
-            sum += slot * focal_length * box_num;
+    fn remove(&mut self, label: &str) {
+        let Some(position) = self.positions.remove(label) else {
+            return;
+        };
+        self.slots[position] = None;
+
+        if self.slots.len() > 2 * self.positions.len() + 8 {
+            self.slots.retain(|slot| slot.is_some());
+            for (position, slot) in self.slots.iter().enumerate() {
+                let (label, _) = slot.as_ref().unwrap();
+                self.positions.insert(label.clone(), position);
+            }
         }
     }
 
-    sum
+    // The lenses from front to back
+    fn lenses(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.slots
+            .iter()
+            .flatten()
+            .map(|(label, focal)| (label.as_str(), *focal))
+    }
+}
+
+struct LensBoxes {
+    boxes: Vec<LensBox>,
+}
+
+impl LensBoxes {
+    fn new() -> Self {
+        Self {
+            boxes: vec![LensBox::default(); BOX_COUNT],
+        }
+    }
+
+    fn apply(&mut self, step: &Step) {
+        let lens_box = &mut self.boxes[get_hash_code(step.label())];
+        match step {
+            Step::Insert { label, focal } => lens_box.insert(label, *focal),
+            Step::Remove { label } => lens_box.remove(label),
+        }
+    }
+
+    fn focusing_power(&self) -> usize {
+        self.boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, lens_box)| {
+                lens_box
+                    .lenses()
+                    .enumerate()
+                    .map(move |(j, (_, focal))| (i + 1) * (j + 1) * focal)
+            })
+            .sum()
+    }
+}
+
+// Only the boxes with lenses in them, e.g. "Box 3: [ot 7] [ab 5] [pc 6]"
+impl fmt::Display for LensBoxes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, lens_box) in self.boxes.iter().enumerate() {
+            if lens_box.positions.is_empty() {
+                continue;
+            }
+            write!(f, "Box {}:", i)?;
+            for (label, focal) in lens_box.lenses() {
+                write!(f, " [{} {}]", label, focal)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn get_test_input() -> String {
+        read_to_string("resources/test_input.txt").unwrap()
+    }
+
     #[test]
     fn test_get_hash_code() {
-        let word = vec!['H', 'A', 'S', 'H'];
-        assert_eq!(get_hash_code(&word), 52);
+        assert_eq!(get_hash_code("HASH"), 52);
+    }
+
+    #[test]
+    fn test_parts() {
+        let input = get_test_input();
+        let input: Vec<&str> = input.trim_end_matches('\n').split(',').collect();
+
+        assert_eq!(part_one(&input), 1320);
+        assert_eq!(part_two(&input, false), 145);
+    }
+
+    #[test]
+    fn test_parse_step() {
+        assert_eq!(
+            Step::parse("rn=1"),
+            Ok(Step::Insert {
+                label: String::from("rn"),
+                focal: 1
+            })
+        );
+        assert_eq!(
+            Step::parse("cm-"),
+            Ok(Step::Remove {
+                label: String::from("cm")
+            })
+        );
+        assert_eq!(
+            Step::parse("rn"),
+            Err(StepError::MissingOperation(String::from("rn")))
+        );
+        assert_eq!(
+            Step::parse("=4"),
+            Err(StepError::EmptyLabel(String::from("=4")))
+        );
+        assert_eq!(
+            Step::parse("rn=x"),
+            Err(StepError::InvalidFocalLength(String::from("rn=x")))
+        );
+        assert_eq!(
+            Step::parse("rn-3"),
+            Err(StepError::InvalidFocalLength(String::from("rn-3")))
+        );
+    }
+
+    #[test]
+    fn test_trace_output() {
+        let mut boxes = LensBoxes::new();
+        for step in get_test_input().trim_end_matches('\n').split(',') {
+            boxes.apply(&Step::parse(step).unwrap());
+        }
+
+        assert_eq!(
+            boxes.to_string(),
+            "Box 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]\n"
+        );
+        assert_eq!(boxes.focusing_power(), 145);
+    }
+
+    #[test]
+    fn test_lens_box_keeps_order() {
+        let mut lens_box = LensBox::default();
+        for i in 0..20 {
+            lens_box.insert(&format!("l{}", i), 1);
+        }
+        for i in 0..18 {
+            lens_box.remove(&format!("l{}", i));
+        }
+        lens_box.insert("l19", 5);
+        lens_box.insert("new", 2);
+
+        assert_eq!(
+            lens_box.lenses().collect::<Vec<_>>(),
+            vec![("l18", 1), ("l19", 5), ("new", 2)]
+        );
     }
 }
@@ -1,311 +1,508 @@
-use std::collections::HashSet;
-
-use lazy_static::lazy_static;
-use library::{get_filename_arg, get_two_dimensional_vector, Directions};
-use regex::Regex;
+use library::grid::Grid;
+use library::{get_filename_and_options, get_two_dimensional_vector, Directions};
 
 fn main() {
-    let file_name = get_filename_arg();
+    // --render prints the cells energized by the part one beam, the way the puzzle statement draws them
+    let (file_name, options) = get_filename_and_options(&["render"]);
     let input = get_two_dimensional_vector(&file_name);
+    let contraption = Contraption::new(&input);
+
+    if options.is_set("render") {
+        print!(
+            "{}",
+            render(&contraption.energized(Beam::new(0, 0, Directions::RIGHT)))
+        );
+    }
+    println!("Part 1: {}", part_one(&contraption));
+    println!("Part 2: {}", part_two(&contraption));
+}
 
-    println!("Part 1: {}", part_one(&input));
-    println!("Part 2: {}", part_two(&input));
+fn part_one(contraption: &Contraption) -> usize {
+    contraption.energized_count(Beam::new(0, 0, Directions::RIGHT))
 }
 
-fn part_one(input: &Vec<Vec<char>>) -> usize {
-    let mut input = input.clone();
-    let mut beams = HashSet::new();
-    radiate_right(0, 0, &mut beams, &mut input);
-    get_sum(&input)
+fn part_two(contraption: &Contraption) -> usize {
+    let graph = SegmentGraph::new(contraption);
+    contraption
+        .edge_beams()
+        .into_iter()
+        .map(|beam| graph.energized_count(contraption, beam))
+        .max()
+        .unwrap_or(0)
 }
 
-fn part_two(input: &Vec<Vec<char>>) -> usize {
-    let mut sums = vec![];
-
-    sums.push(get_energized(0, 0, &input, Directions::DOWN));
-    sums.push(get_energized(0, 0, &input, Directions::RIGHT));
-
-    sums.push(get_energized(
-        0,
-        input[0].len() - 1,
-        &input,
-        Directions::DOWN,
-    ));
-    sums.push(get_energized(
-        0,
-        input[0].len() - 1,
-        &input,
-        Directions::LEFT,
-    ));
-
-    sums.push(get_energized(input.len() - 1, 0, &input, Directions::UP));
-    sums.push(get_energized(input.len() - 1, 0, &input, Directions::RIGHT));
-
-    sums.push(get_energized(
-        input.len() - 1,
-        input[0].len() - 1,
-        &input,
-        Directions::UP,
-    ));
-    sums.push(get_energized(
-        input.len() - 1,
-        input[0].len() - 1,
-        &input,
-        Directions::LEFT,
-    ));
-
-    for i in 1..input.len() - 1 {
-        sums.push(get_energized(i, 0, &input, Directions::RIGHT));
-        sums.push(get_energized(
-            i,
-            input[i].len() - 1,
-            &input,
-            Directions::LEFT,
-        ));
+fn direction_index(direction: Directions) -> usize {
+    match direction {
+        Directions::UP => 0,
+        Directions::RIGHT => 1,
+        Directions::DOWN => 2,
+        Directions::LEFT => 3,
     }
+}
 
-    for i in 1..input[0].len() - 1 {
-        sums.push(get_energized(0, i, &input, Directions::DOWN));
-        sums.push(get_energized(input.len() - 1, i, &input, Directions::UP));
+// A beam entering the cell at (row, column) while travelling in `direction`
+#[derive(Clone, Copy)]
+struct Beam {
+    row: usize,
+    column: usize,
+    direction: Directions,
+}
+
+impl Beam {
+    fn new(row: usize, column: usize, direction: Directions) -> Self {
+        Self {
+            row,
+            column,
+            direction,
+        }
     }
+}
 
-    sums.into_iter().max().unwrap()
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct BitSet {
+    words: Vec<u64>,
 }
 
-fn get_sum(input: &Vec<Vec<char>>) -> usize {
-    lazy_static! {
-        static ref PATTERN: Regex = Regex::new(r"[#=?>!]").unwrap();
+impl BitSet {
+    fn new(size: usize) -> Self {
+        Self {
+            words: vec![0; size.div_ceil(64)],
+        }
     }
 
-    let mut sum = 0;
-    for line in input {
-        let row: String = line.iter().collect();
-        let matches: Vec<_> = PATTERN.find_iter(&row).map(|m| m.as_str()).collect();
-        if matches.len() > 0 {
-            sum += matches.len();
+    // Returns whether the value was not already in the set
+    fn insert(&mut self, value: usize) -> bool {
+        let word = &mut self.words[value / 64];
+        let bit = 1 << (value % 64);
+        let inserted = *word & bit == 0;
+        *word |= bit;
+        inserted
+    }
+
+    fn contains(&self, value: usize) -> bool {
+        self.words[value / 64] & (1 << (value % 64)) != 0
+    }
+
+    fn union_with(&mut self, other: &Self) {
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word |= other;
         }
     }
 
-    sum
+    fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
 }
 
-fn beam_string_format(row: usize, column: usize, direction: Directions) -> String {
-    format!("{row},{column},{direction}")
+// Cells are stored row by row, and are referred to by their index in that order
+struct Contraption {
+    width: usize,
+    height: usize,
+    cells: Vec<char>,
 }
 
-fn beams_contains(
-    row: usize,
-    column: usize,
-    direction: Directions,
-    beams: &mut HashSet<String>,
-) -> bool {
-    let beam = beam_string_format(row, column, direction);
-    if beams.contains(&beam) {
-        true
-    } else {
-        beams.insert(beam);
-        false
+impl Contraption {
+    fn new(input: &[Vec<char>]) -> Self {
+        let height = input.len();
+        let width = input.first().map_or(0, |row| row.len());
+        if input.iter().any(|row| row.len() != width) {
+            panic!("All rows of the contraption must be the same length");
+        }
+        if let Some(invalid_char) = input
+            .iter()
+            .flatten()
+            .find(|cell| !matches!(cell, '.' | '/' | '\\' | '|' | '-'))
+        {
+            panic!("Invalid character {invalid_char}");
+        }
+
+        Self {
+            width,
+            height,
+            cells: input.iter().flatten().copied().collect(),
+        }
     }
-}
 
-fn can_increment_row(row: usize, input: &Vec<Vec<char>>) -> bool {
-    row < input.len() - 1
-}
+    fn cell(&self, beam: Beam) -> usize {
+        beam.row * self.width + beam.column
+    }
 
-fn can_increment_column(row: usize, column: usize, input: &Vec<Vec<char>>) -> bool {
-    column < input[row].len() - 1
-}
+    // The neighbouring cell in the given direction, if it is not off the edge
+    fn step(&self, cell: usize, direction: Directions) -> Option<usize> {
+        let (row, column) = (cell / self.width, cell % self.width);
+        match direction {
+            Directions::UP if row > 0 => Some(cell - self.width),
+            Directions::DOWN if row + 1 < self.height => Some(cell + self.width),
+            Directions::LEFT if column > 0 => Some(cell - 1),
+            Directions::RIGHT if column + 1 < self.width => Some(cell + 1),
+            _ => None,
+        }
+    }
 
-fn can_decrement(row_or_column: usize) -> bool {
-    row_or_column > 0
-}
+    // The directions a beam leaves a cell in, with the second only set when the beam is split
+    fn outgoing(&self, cell: usize, direction: Directions) -> (Directions, Option<Directions>) {
+        use Directions::*;
 
-fn radiate_right(
-    row: usize,
-    column: usize,
-    beams: &mut HashSet<String>,
-    input: &mut Vec<Vec<char>>,
-) {
-    if beams_contains(row, column, Directions::RIGHT, beams) {
-        return;
+        match (self.cells[cell], direction) {
+            ('/', UP) => (RIGHT, None),
+            ('/', RIGHT) => (UP, None),
+            ('/', DOWN) => (LEFT, None),
+            ('/', LEFT) => (DOWN, None),
+            ('\\', UP) => (LEFT, None),
+            ('\\', LEFT) => (UP, None),
+            ('\\', DOWN) => (RIGHT, None),
+            ('\\', RIGHT) => (DOWN, None),
+            ('|', LEFT | RIGHT) => (UP, Some(DOWN)),
+            ('-', UP | DOWN) => (LEFT, Some(RIGHT)),
+            _ => (direction, None),
+        }
     }
 
-    for i in column..input[row].len() {
-        match input[row][i] {
-            '.' | '#' => input[row][i] = '#',
-            '-' | '=' => input[row][i] = '=',
-            '/' | '?' => {
-                input[row][i] = '?';
-                if can_decrement(row) {
-                    radiate_up(row - 1, i, beams, input);
-                }
-                return;
+    // Every beam entering from the edge, pointing into the contraption
+    fn edge_beams(&self) -> Vec<Beam> {
+        let mut beams = vec![];
+        for row in 0..self.height {
+            beams.push(Beam::new(row, 0, Directions::RIGHT));
+            beams.push(Beam::new(row, self.width - 1, Directions::LEFT));
+        }
+        for column in 0..self.width {
+            beams.push(Beam::new(0, column, Directions::DOWN));
+            beams.push(Beam::new(self.height - 1, column, Directions::UP));
+        }
+        beams
+    }
+
+    // Follows every beam with an explicit stack, stopping at states that have already been followed
+    fn trace(&self, start: Beam) -> BitSet {
+        let mut visited = BitSet::new(self.cells.len() * 4);
+        let mut energized = BitSet::new(self.cells.len());
+        let mut stack = vec![(self.cell(start), start.direction)];
+
+        while let Some((cell, direction)) = stack.pop() {
+            if !visited.insert(cell * 4 + direction_index(direction)) {
+                continue;
             }
-            '\\' | '>' => {
-                input[row][i] = '>';
-                if can_increment_row(row, input) {
-                    radiate_down(row + 1, i, beams, input);
+            energized.insert(cell);
+
+            let (first, second) = self.outgoing(cell, direction);
+            for direction in [Some(first), second].into_iter().flatten() {
+                if let Some(next) = self.step(cell, direction) {
+                    stack.push((next, direction));
                 }
-                return;
             }
-            '|' | '!' => {
-                input[row][i] = '!';
-                if can_decrement(row) {
-                    radiate_up(row - 1, i, beams, input);
-                }
-                if can_increment_row(row, input) {
-                    radiate_down(row + 1, i, beams, input);
-                }
-                return;
+        }
+
+        energized
+    }
+
+    fn energized_count(&self, start: Beam) -> usize {
+        self.trace(start).len()
+    }
+
+    fn energized(&self, start: Beam) -> Grid<bool> {
+        let energized = self.trace(start);
+        Grid::from_rows(
+            (0..self.height)
+                .map(|row| {
+                    (0..self.width)
+                        .map(|column| energized.contains(row * self.width + column))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    /* Follows a single beam until it reaches a splitter that splits it, adding every cell it passes through to
+     * `cells`, and returns that splitter. A beam that leaves the contraption, or goes round a loop of mirrors, ends
+     * without one.
+     */
+    fn follow_segment(
+        &self,
+        mut cell: usize,
+        mut direction: Directions,
+        cells: &mut BitSet,
+    ) -> Option<usize> {
+        for _ in 0..self.cells.len() * 4 {
+            cells.insert(cell);
+            let (first, second) = self.outgoing(cell, direction);
+            if second.is_some() {
+                return Some(cell);
             }
-            invalid_char => panic!("Invalid character {invalid_char}"),
+            direction = first;
+            cell = self.step(cell, direction)?;
         }
+
+        None
     }
 }
 
-fn radiate_left(
-    row: usize,
-    column: usize,
-    beams: &mut HashSet<String>,
-    input: &mut Vec<Vec<char>>,
-) {
-    if beams_contains(row, column, Directions::LEFT, beams) {
-        return;
-    }
+fn render(energized: &Grid<bool>) -> String {
+    energized
+        .rows()
+        .map(|row| {
+            let mut line: String = row
+                .iter()
+                .map(|&cell| if cell { '#' } else { '.' })
+                .collect();
+            line.push('\n');
+            line
+        })
+        .collect()
+}
 
-    for i in (0..=column).rev() {
-        match input[row][i] {
-            '.' | '#' => input[row][i] = '#',
-            '-' | '=' => input[row][i] = '=',
-            '/' | '?' => {
-                input[row][i] = '?';
-                if can_increment_row(row, input) {
-                    radiate_down(row + 1, i, beams, input);
-                }
-                return;
+/* The beams only branch at splitters, and a splitter sends out the same two beams whichever side it is hit from, so
+* the splitters form a graph whose edges are the straight (or mirrored) segments between them. Collapsing its strongly
+* connected components gives a DAG, and the cells energized from each component are found once, in reverse
+* topological order. Any entry beam then only has to be followed as far as the first splitter it hits.
+*/
+struct SegmentGraph {
+    nodes: Vec<Option<usize>>,
+    components: Vec<usize>,
+    reach: Vec<BitSet>,
+}
+
+impl SegmentGraph {
+    fn new(contraption: &Contraption) -> Self {
+        let mut nodes = vec![None; contraption.cells.len()];
+        let mut splitters = vec![];
+        for (cell, &character) in contraption.cells.iter().enumerate() {
+            if character == '|' || character == '-' {
+                nodes[cell] = Some(splitters.len());
+                splitters.push(cell);
             }
-            '\\' | '>' => {
-                input[row][i] = '>';
-                if can_decrement(row) {
-                    radiate_up(row - 1, i, beams, input);
+        }
+
+        let mut cells = vec![];
+        let mut edges = vec![];
+        for &splitter in &splitters {
+            let mut segment_cells = BitSet::new(contraption.cells.len());
+            segment_cells.insert(splitter);
+            let directions = if contraption.cells[splitter] == '|' {
+                [Directions::UP, Directions::DOWN]
+            } else {
+                [Directions::LEFT, Directions::RIGHT]
+            };
+
+            let mut node_edges = vec![];
+            for direction in directions {
+                let end = contraption.step(splitter, direction).and_then(|next| {
+                    contraption.follow_segment(next, direction, &mut segment_cells)
+                });
+                if let Some(end) = end {
+                    node_edges.push(nodes[end].unwrap());
                 }
-                return;
             }
-            '|' | '!' => {
-                input[row][i] = '!';
-                if can_decrement(row) {
-                    radiate_up(row - 1, i, beams, input);
-                }
-                if can_increment_row(row, input) {
-                    radiate_down(row + 1, i, beams, input);
+            cells.push(segment_cells);
+            edges.push(node_edges);
+        }
+
+        let (components, component_count) = strongly_connected_components(&edges);
+        let mut members = vec![vec![]; component_count];
+        for (node, &component) in components.iter().enumerate() {
+            members[component].push(node);
+        }
+
+        let mut reach: Vec<BitSet> = vec![];
+        for (component, component_members) in members.iter().enumerate() {
+            let mut component_reach = BitSet::new(contraption.cells.len());
+            for &node in component_members {
+                component_reach.union_with(&cells[node]);
+                for &next in &edges[node] {
+                    if components[next] != component {
+                        component_reach.union_with(&reach[components[next]]);
+                    }
                 }
-                return;
             }
-            invalid_char => panic!("Invalid character {invalid_char}"),
+            reach.push(component_reach);
+        }
+
+        Self {
+            nodes,
+            components,
+            reach,
         }
     }
+
+    fn energized_count(&self, contraption: &Contraption, start: Beam) -> usize {
+        let mut energized = BitSet::new(contraption.cells.len());
+        if let Some(end) =
+            contraption.follow_segment(contraption.cell(start), start.direction, &mut energized)
+        {
+            let node = self.nodes[end].unwrap();
+            energized.union_with(&self.reach[self.components[node]]);
+        }
+        energized.len()
+    }
 }
 
-fn radiate_down(
-    row: usize,
-    column: usize,
-    beams: &mut HashSet<String>,
-    input: &mut Vec<Vec<char>>,
-) {
-    if beams_contains(row, column, Directions::DOWN, beams) {
-        return;
+/* Tarjan's algorithm. Components are numbered in the order they are completed, which puts every component after all
+* of the components reachable from it.
+*/
+fn strongly_connected_components(edges: &[Vec<usize>]) -> (Vec<usize>, usize) {
+    struct State<'a> {
+        edges: &'a [Vec<usize>],
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next_index: usize,
+        components: Vec<usize>,
+        component_count: usize,
     }
 
-    for i in row..input.len() {
-        match input[i][column] {
-            '.' | '#' => input[i][column] = '#',
-            '-' | '=' => {
-                input[i][column] = '=';
-                if can_decrement(column) {
-                    radiate_left(i, column - 1, beams, input);
-                }
-                if can_increment_column(row, column, input) {
-                    radiate_right(i, column + 1, beams, input);
+    fn visit(state: &mut State, node: usize) {
+        state.index[node] = Some(state.next_index);
+        state.low[node] = state.next_index;
+        state.next_index += 1;
+        state.stack.push(node);
+        state.on_stack[node] = true;
+
+        for &next in &state.edges[node] {
+            match state.index[next] {
+                None => {
+                    visit(state, next);
+                    state.low[node] = state.low[node].min(state.low[next]);
                 }
-                return;
-            }
-            '/' | '?' => {
-                input[i][column] = '?';
-                if can_decrement(column) {
-                    radiate_left(i, column - 1, beams, input);
+                Some(index) if state.on_stack[next] => {
+                    state.low[node] = state.low[node].min(index);
                 }
-                return;
+                _ => {}
             }
-            '\\' | '>' => {
-                input[i][column] = '>';
-                if can_increment_column(row, column, input) {
-                    radiate_right(i, column + 1, beams, input);
+        }
+
+        if Some(state.low[node]) == state.index[node] {
+            while let Some(member) = state.stack.pop() {
+                state.on_stack[member] = false;
+                state.components[member] = state.component_count;
+                if member == node {
+                    break;
                 }
-                return;
-            }
-            '|' | '!' => {
-                input[i][column] = '!';
             }
-            invalid_char => panic!("Invalid character {invalid_char}"),
+            state.component_count += 1;
         }
     }
+
+    let mut state = State {
+        edges,
+        index: vec![None; edges.len()],
+        low: vec![0; edges.len()],
+        on_stack: vec![false; edges.len()],
+        stack: vec![],
+        next_index: 0,
+        components: vec![0; edges.len()],
+        component_count: 0,
+    };
+    for node in 0..edges.len() {
+        if state.index[node].is_none() {
+            visit(&mut state, node);
+        }
+    }
+
+    (state.components, state.component_count)
 }
 
-fn radiate_up(row: usize, column: usize, beams: &mut HashSet<String>, input: &mut Vec<Vec<char>>) {
-    if beams_contains(row, column, Directions::UP, beams) {
-        return;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_contraption() -> Contraption {
+        Contraption::new(&get_two_dimensional_vector("resources/test_input.txt"))
     }
 
-    for i in (0..=row).rev() {
-        match input[i][column] {
-            '.' | '#' => input[i][column] = '#',
-            '-' | '=' => {
-                input[i][column] = '=';
-                if can_decrement(column) {
-                    radiate_left(i, column - 1, beams, input);
-                }
-                if can_increment_column(row, column, input) {
-                    radiate_right(i, column + 1, beams, input);
-                }
-                return;
-            }
-            '/' | '?' => {
-                input[i][column] = '?';
-                if can_increment_column(row, column, input) {
-                    radiate_right(i, column + 1, beams, input);
-                }
-                return;
-            }
-            '\\' | '>' => {
-                input[i][column] = '>';
-                if can_decrement(column) {
-                    radiate_left(i, column - 1, beams, input);
-                }
-                return;
-            }
-            '|' | '!' => {
-                input[i][column] = '!';
+    #[test]
+    fn test_parts() {
+        let contraption = get_test_contraption();
+
+        assert_eq!(part_one(&contraption), 46);
+        assert_eq!(part_two(&contraption), 51);
+    }
+
+    #[test]
+    fn test_render() {
+        let contraption = get_test_contraption();
+        let energized = contraption.energized(Beam::new(0, 0, Directions::RIGHT));
+        let expected = "\
+######....
+.#...#....
+.#...#####
+.#...##...
+.#...##...
+.#...##...
+.#..####..
+########..
+.#######..
+.#...#.#..
+";
+
+        assert_eq!(render(&energized), expected);
+    }
+
+    #[test]
+    fn test_segment_graph_matches_tracer() {
+        let contraption = get_test_contraption();
+        let graph = SegmentGraph::new(&contraption);
+
+        for beam in contraption.edge_beams() {
+            assert_eq!(
+                graph.energized_count(&contraption, beam),
+                contraption.energized_count(beam)
+            );
+        }
+    }
+
+    #[test]
+    fn test_segment_graph_matches_tracer_on_random_contraptions() {
+        let mut seed: u64 = 16;
+        for _ in 0..20 {
+            let input: Vec<Vec<char>> = (0..25)
+                .map(|_| {
+                    (0..25)
+                        .map(|_| {
+                            seed = seed
+                                .wrapping_mul(6364136223846793005)
+                                .wrapping_add(1442695040888963407);
+                            ['.', '.', '.', '.', '/', '\\', '|', '-'][(seed >> 61) as usize]
+                        })
+                        .collect()
+                })
+                .collect();
+            let contraption = Contraption::new(&input);
+            let graph = SegmentGraph::new(&contraption);
+
+            for beam in contraption.edge_beams() {
+                assert_eq!(
+                    graph.energized_count(&contraption, beam),
+                    contraption.energized_count(beam)
+                );
             }
-            invalid_char => panic!("Invalid character {invalid_char}"),
         }
     }
-}
 
-fn get_energized(
-    row: usize,
-    column: usize,
-    input: &Vec<Vec<char>>,
-    direction: Directions,
-) -> usize {
-    let mut input = input.clone();
-    let mut beams = HashSet::new();
+    #[test]
+    fn test_mirror_loop() {
+        let contraption = Contraption::new(&[
+            "/.\\".chars().collect(),
+            "...".chars().collect(),
+            "\\./".chars().collect(),
+        ]);
+        let graph = SegmentGraph::new(&contraption);
+        let beam = Beam::new(0, 1, Directions::RIGHT);
 
-    match direction {
-        Directions::UP => radiate_up(row, column, &mut beams, &mut input),
-        Directions::DOWN => radiate_down(row, column, &mut beams, &mut input),
-        Directions::LEFT => radiate_left(row, column, &mut beams, &mut input),
-        Directions::RIGHT => radiate_right(row, column, &mut beams, &mut input),
-    };
+        assert_eq!(contraption.energized_count(beam), 8);
+        assert_eq!(graph.energized_count(&contraption, beam), 8);
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        let (components, count) =
+            strongly_connected_components(&[vec![1], vec![2], vec![1, 3], vec![]]);
 
-    get_sum(&input)
+        assert_eq!(count, 3);
+        assert_eq!(components[1], components[2]);
+        assert!(components[3] < components[1]);
+        assert!(components[1] < components[0]);
+    }
 }
@@ -1,6 +1,9 @@
-use std::{cmp::Ordering, collections::HashMap, fmt::Display};
+use std::{cmp::Reverse, collections::BinaryHeap};
 
-use library::{get_filename_arg, get_two_dimensional_number_vector, Directions};
+use library::{get_filename_arg, get_two_dimensional_number_vector};
+
+// Indexed by direction number: up, right, down, left. Adding 2 (mod 4) reverses a direction
+const STEPS: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
 
 fn main() {
     let file_name = get_filename_arg();
@@ -10,179 +13,269 @@ fn main() {
     println!("Part 2: {}", part_two(&input));
 }
 
-#[derive(Clone)]
-struct Node {
-    row: usize,
-    column: usize,
-    direction: Option<Directions>,
-    straight_count: usize,
+fn part_one(input: &[Vec<u32>]) -> u32 {
+    get_least_cost(input, &CrucibleRules::new(1, 3, input))
+        .expect("No route to the goal")
+        .cost
 }
 
-impl Display for Node {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let direction_string = match self.direction {
-            Some(x) => format!("{x}"),
-            None => String::from("NONE"),
-        };
-        write!(
-            f,
-            "{},{},{},{}",
-            self.row, self.column, direction_string, self.straight_count
-        )
-    }
+fn part_two(input: &[Vec<u32>]) -> u32 {
+    get_least_cost(input, &CrucibleRules::new(4, 10, input))
+        .expect("No route to the goal")
+        .cost
 }
 
-fn get_neighbors(
-    node: &Node,
-    input: &Vec<Vec<u32>>,
-    can_move_straight: fn(&Node) -> bool,
-    can_turn: fn(&Node) -> bool,
-) -> Vec<Node> {
-    let mut neighbors = vec![];
-
-    let node_can_move_straight = can_move_straight(&node);
-    let node_can_turn = can_turn(&node);
-
-    if node.direction == Some(Directions::DOWN) {
-        if node_can_move_straight && node.row < input.len() - 1 {
-            neighbors.push(Node {
-                row: node.row + 1,
-                column: node.column,
-                direction: Some(Directions::DOWN),
-                straight_count: node.straight_count + 1,
-            });
-        }
-    } else if node.row < input.len() - 1 && node.direction != Some(Directions::UP) && node_can_turn
-    {
-        neighbors.push(Node {
-            row: node.row + 1,
-            column: node.column,
-            direction: Some(Directions::DOWN),
-            straight_count: 1,
-        });
-    }
+/* How a crucible is allowed to move. It has to go at least min_straight blocks in a direction before it can turn or
+* stop at the goal, and can go at most max_straight blocks before it has to turn. Turning around on the spot counts as
+* a turn, and is only allowed if allow_reverse is set. Positions are (row, column).
+*/
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct CrucibleRules {
+    min_straight: usize,
+    max_straight: usize,
+    allow_reverse: bool,
+    start: (usize, usize),
+    goal: (usize, usize),
+}
 
-    if node.direction == Some(Directions::UP) {
-        if node_can_move_straight && node.row > 0 {
-            neighbors.push(Node {
-                row: node.row - 1,
-                column: node.column,
-                direction: Some(Directions::UP),
-                straight_count: node.straight_count + 1,
-            });
+impl CrucibleRules {
+    // Travelling from the top left to the bottom right of the map, which is what the puzzle asks for
+    fn new(min_straight: usize, max_straight: usize, input: &[Vec<u32>]) -> Self {
+        let height = input.len();
+        let width = input.first().map_or(0, |row| row.len());
+        Self {
+            min_straight,
+            max_straight,
+            allow_reverse: false,
+            start: (0, 0),
+            goal: (height.saturating_sub(1), width.saturating_sub(1)),
         }
-    } else if node.row > 0 && node.direction != Some(Directions::DOWN) && node_can_turn {
-        neighbors.push(Node {
-            row: node.row - 1,
-            column: node.column,
-            direction: Some(Directions::UP),
-            straight_count: 1,
-        });
     }
+}
 
-    if node.direction == Some(Directions::RIGHT) {
-        if node_can_move_straight && node.column < input[node.row].len() - 1 {
-            neighbors.push(Node {
-                row: node.row,
-                column: node.column + 1,
-                direction: Some(Directions::RIGHT),
-                straight_count: node.straight_count + 1,
-            });
-        }
-    } else if node.column < input[node.row].len() - 1
-        && node.direction != Some(Directions::LEFT)
-        && node_can_turn
-    {
-        neighbors.push(Node {
-            row: node.row,
-            column: node.column + 1,
-            direction: Some(Directions::RIGHT),
-            straight_count: 1,
-        });
-    }
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Route {
+    cost: u32,
+    // Every block visited, from the start to the goal
+    path: Vec<(usize, usize)>,
+}
 
-    if node.direction == Some(Directions::LEFT) {
-        if node_can_move_straight && node.column > 0 {
-            neighbors.push(Node {
-                row: node.row,
-                column: node.column - 1,
-                direction: Some(Directions::LEFT),
-                straight_count: node.straight_count + 1,
-            });
+/* Dijkstra's algorithm over (block, direction, blocks moved in that direction) states, which are numbered so that the
+* costs and the previous state on the best route can be kept in plain vectors. The state numbered `start` stands for
+* the crucible sitting at the start before it has moved in any direction.
+*/
+fn get_least_cost(input: &[Vec<u32>], rules: &CrucibleRules) -> Option<Route> {
+    let height = input.len();
+    let width = input.first().map_or(0, |row| row.len());
+    if input.iter().any(|row| row.len() != width) {
+        panic!("All rows of the map must be the same length");
+    }
+    if rules.max_straight == 0 || rules.min_straight > rules.max_straight {
+        panic!("Crucibles need 0 < max_straight and min_straight <= max_straight");
+    }
+    for (row, column) in [rules.start, rules.goal] {
+        if row >= height || column >= width {
+            panic!("Position ({}, {}) is not on the map", row, column);
         }
-    } else if node.column > 0 && node.direction != Some(Directions::RIGHT) && node_can_turn {
-        neighbors.push(Node {
-            row: node.row,
-            column: node.column - 1,
-            direction: Some(Directions::LEFT),
-            straight_count: 1,
-        });
     }
 
-    neighbors
-}
+    let runs = rules.max_straight + 1;
+    let state = |row: usize, column: usize, direction: usize, run: usize| {
+        ((row * width + column) * 4 + direction) * runs + run
+    };
+    let start = height * width * 4 * runs;
 
-fn compare_nodes(a: &Node, b: &Node, cost_so_far: &HashMap<String, u32>) -> Ordering {
-    let a_cost = *cost_so_far.get(&format!("{}", a)).unwrap();
-    let b_cost = *cost_so_far.get(&format!("{}", b)).unwrap();
+    let mut cost_so_far = vec![u32::MAX; start + 1];
+    let mut previous = vec![usize::MAX; start + 1];
+    let mut frontier = BinaryHeap::new();
+    cost_so_far[start] = 0;
+    frontier.push(Reverse((0, start)));
 
-    u32::cmp(&a_cost, &b_cost)
-}
+    while let Some(Reverse((cost, current))) = frontier.pop() {
+        if cost > cost_so_far[current] {
+            continue;
+        }
 
-fn get_least_cost(
-    input: &Vec<Vec<u32>>,
-    can_move_straight: fn(&Node) -> bool,
-    can_turn: fn(&Node) -> bool,
-) -> u32 {
-    let mut frontier = vec![];
-    let mut cost_so_far = HashMap::new();
-    let start_node = Node {
-        row: 0,
-        column: 0,
-        direction: None,
-        straight_count: 0,
-    };
-    frontier.push(start_node.clone());
-    let key = format!("{}", start_node);
-    cost_so_far.insert(key, 0);
-
-    while frontier.len() > 0 {
-        let current = frontier.remove(0);
-        let current_key = format!("{}", current);
-
-        if current.row == input.len() - 1
-            && current.column == input[current.row].len() - 1
-            && can_move_straight(&current)
-        {
-            return *cost_so_far.get(&current_key).unwrap();
+        let (row, column, direction, run) = if current == start {
+            (rules.start.0, rules.start.1, None, 0)
+        } else {
+            let run = current % runs;
+            let direction = current / runs % 4;
+            let block = current / runs / 4;
+            (block / width, block % width, Some(direction), run)
+        };
+
+        if (row, column) == rules.goal && (current == start || run >= rules.min_straight) {
+            return Some(Route {
+                cost,
+                path: get_path(&previous, current, start, runs, width, rules.start),
+            });
         }
 
-        let neighbors = get_neighbors(&current, input, can_move_straight, can_turn);
-        for neighbor in neighbors {
-            let neighbor_key = format!("{}", neighbor);
-            let new_cost =
-                cost_so_far.get(&current_key).unwrap() + input[neighbor.row][neighbor.column];
-            if !cost_so_far.contains_key(&neighbor_key)
-                || new_cost < *cost_so_far.get(&neighbor_key).unwrap()
-            {
-                cost_so_far.insert(neighbor_key, new_cost);
-                frontier.push(neighbor);
-                frontier.sort_by(|a, b| compare_nodes(a, b, &cost_so_far));
+        for (next_direction, &(row_step, column_step)) in STEPS.iter().enumerate() {
+            let next_run = match direction {
+                None => 1,
+                Some(direction) if direction == next_direction => {
+                    if run >= rules.max_straight {
+                        continue;
+                    }
+                    run + 1
+                }
+                Some(direction) => {
+                    if run < rules.min_straight
+                        || ((direction + 2) % 4 == next_direction && !rules.allow_reverse)
+                    {
+                        continue;
+                    }
+                    1
+                }
+            };
+
+            let Some(next_row) = row.checked_add_signed(row_step).filter(|&r| r < height) else {
+                continue;
+            };
+            let Some(next_column) = column
+                .checked_add_signed(column_step)
+                .filter(|&c| c < width)
+            else {
+                continue;
+            };
+
+            let next = state(next_row, next_column, next_direction, next_run);
+            let next_cost = cost + input[next_row][next_column];
+            if next_cost < cost_so_far[next] {
+                cost_so_far[next] = next_cost;
+                previous[next] = current;
+                frontier.push(Reverse((next_cost, next)));
             }
         }
     }
 
-    u32::MAX
+    None
 }
 
-fn part_one(input: &Vec<Vec<u32>>) -> u32 {
-    get_least_cost(input, |node| node.straight_count < 3, |_| true)
+fn get_path(
+    previous: &[usize],
+    end: usize,
+    start: usize,
+    runs: usize,
+    width: usize,
+    start_position: (usize, usize),
+) -> Vec<(usize, usize)> {
+    let mut path = vec![];
+    let mut current = end;
+    while current != start {
+        let block = current / runs / 4;
+        path.push((block / width, block % width));
+        current = previous[current];
+    }
+    path.push(start_position);
+    path.reverse();
+    path
 }
 
-fn part_two(input: &Vec<Vec<u32>>) -> u32 {
-    get_least_cost(
-        input,
-        |node| node.straight_count < 10,
-        |node| node.straight_count >= 4 || (node.row == 0 && node.column == 0),
-    )
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_input() -> Vec<Vec<u32>> {
+        get_two_dimensional_number_vector("resources/test_input.txt")
+    }
+
+    fn parse(lines: &[&str]) -> Vec<Vec<u32>> {
+        lines
+            .iter()
+            .map(|line| line.chars().map(|c| c.to_digit(10).unwrap()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_parts() {
+        let input = get_test_input();
+
+        assert_eq!(part_one(&input), 102);
+        assert_eq!(part_two(&input), 94);
+    }
+
+    #[test]
+    fn test_ultra_crucible_has_to_stop_after_four_blocks() {
+        let input = parse(&[
+            "111111111111",
+            "999999999991",
+            "999999999991",
+            "999999999991",
+            "999999999991",
+        ]);
+
+        assert_eq!(part_two(&input), 71);
+    }
+
+    #[test]
+    fn test_path() {
+        let input = get_test_input();
+        let route = get_least_cost(&input, &CrucibleRules::new(1, 3, &input)).unwrap();
+
+        assert_eq!(route.path.first(), Some(&(0, 0)));
+        assert_eq!(route.path.last(), Some(&(12, 12)));
+        assert_eq!(
+            route.path[1..]
+                .iter()
+                .map(|&(row, column)| input[row][column])
+                .sum::<u32>(),
+            route.cost
+        );
+        assert!(route
+            .path
+            .windows(2)
+            .all(|pair| { pair[0].0.abs_diff(pair[1].0) + pair[0].1.abs_diff(pair[1].1) == 1 }));
+    }
+
+    #[test]
+    fn test_start_and_goal() {
+        let input = parse(&["191", "111"]);
+        let rules = CrucibleRules {
+            start: (0, 2),
+            goal: (0, 0),
+            ..CrucibleRules::new(1, 3, &input)
+        };
+        let route = get_least_cost(&input, &rules).unwrap();
+
+        assert_eq!(route.cost, 4);
+        assert_eq!(route.path, vec![(0, 2), (1, 2), (1, 1), (1, 0), (0, 0)]);
+        assert_eq!(
+            get_least_cost(
+                &input,
+                &CrucibleRules {
+                    goal: (0, 2),
+                    ..rules
+                }
+            )
+            .map(|r| r.cost),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_allow_reverse() {
+        let input = parse(&["1119"]);
+        let rules = CrucibleRules {
+            start: (0, 0),
+            goal: (0, 1),
+            ..CrucibleRules::new(2, 3, &input)
+        };
+
+        assert_eq!(get_least_cost(&input, &rules), None);
+        // Out to the far end and back, since reversing before then would leave it only one block past the goal
+        assert_eq!(
+            get_least_cost(
+                &input,
+                &CrucibleRules {
+                    allow_reverse: true,
+                    ..rules
+                }
+            )
+            .map(|route| route.cost),
+            Some(13)
+        );
+    }
 }
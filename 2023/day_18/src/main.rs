@@ -1,103 +1,437 @@
-use library::{get_filename_arg, get_lines};
-use regex::Regex;
+use std::fmt;
+
+use library::geometry::{Location, Point, Polygon};
+use library::grid::Grid;
+use library::{get_filename_and_options, get_lines, Directions};
+
+const MAX_RENDER_SIZE: i64 = 100;
 
 fn main() {
-    let file_name = get_filename_arg();
+    // --render prints the measurements and a picture of each lagoon
+    let (file_name, options) = get_filename_and_options(&["render"]);
     let input = get_lines(&file_name);
 
-    println!("Part 1: {}", get_capacity(&get_part_one_plan(&input)));
-    println!("Part 2: {}", get_capacity(&get_part_two_plan(&input)));
+    for (part, decoding) in [(1, Decoding::Direction), (2, Decoding::Hex)] {
+        let plan = DigPlan::parse(&input, decoding);
+        if let Err(error) = plan.validate() {
+            panic!("{}", error);
+        }
+        if options.is_set("render") {
+            println!(
+                "Area {}, trench {}, interior {}",
+                plan.area(),
+                plan.perimeter(),
+                plan.interior()
+            );
+            print!("{}", plan.render());
+        }
+        println!("Part {}: {}", part, plan.capacity());
+    }
+}
+
+// Which half of a line such as "R 6 (#70c710)" the instruction is read from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Decoding {
+    // The direction letter and distance, "R 6"
+    Direction,
+    // The hex code, where the first five digits are the distance and the last is the direction, "#70c710"
+    Hex,
 }
 
+#[derive(Clone, Copy, PartialEq)]
 struct Instruction {
-    direction: String,
+    direction: Directions,
     distance: i64,
 }
 
-struct Coordinate {
-    x: i64,
-    y: i64,
+impl fmt::Debug for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.direction, self.distance)
+    }
 }
 
-fn get_capacity(plan: &Vec<Instruction>) -> i64 {
-    let vertices = get_vertices(plan);
-    let perimeter = get_perimeter(plan);
+#[derive(PartialEq, Eq, Debug)]
+enum PlanError {
+    EmptyStep(usize),
+    NotClosed(Point),
+    SelfIntersecting(usize, usize),
+}
 
-    shoelace(vertices) + perimeter / 2 + 1
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyStep(index) => write!(f, "Instruction {} does not dig anything", index),
+            Self::NotClosed(end) => write!(
+                f,
+                "The plan ends at ({}, {}) instead of where it started",
+                end.row, end.column
+            ),
+            Self::SelfIntersecting(a, b) => {
+                write!(f, "The trenches dug by instructions {} and {} cross", a, b)
+            }
+        }
+    }
 }
 
-fn get_part_one_plan(input: &Vec<String>) -> Vec<Instruction> {
-    let mut plan = vec![];
+// The trench is dug starting from (0, 0)
+struct DigPlan {
+    instructions: Vec<Instruction>,
+}
 
-    for line in input {
-        let sections: Vec<&str> = line.split(" ").collect();
-        plan.push(Instruction {
-            direction: String::from(sections[0]),
-            distance: sections[1]
-                .parse::<i64>()
-                .expect("Failed to parse distance into number"),
-        });
+impl DigPlan {
+    fn parse(input: &[String], decoding: Decoding) -> Self {
+        let instructions = input
+            .iter()
+            .map(|line| {
+                let sections: Vec<&str> = line.split(' ').collect();
+                match decoding {
+                    Decoding::Direction => Instruction {
+                        direction: parse_direction(sections[0]),
+                        distance: sections[1]
+                            .parse()
+                            .expect("Failed to parse distance into number"),
+                    },
+                    Decoding::Hex => {
+                        let hex = sections[2]
+                            .strip_prefix("(#")
+                            .and_then(|hex| hex.strip_suffix(')'))
+                            .filter(|hex| hex.len() == 6)
+                            .unwrap_or_else(|| panic!("Invalid hex code {}", sections[2]));
+                        Instruction {
+                            direction: parse_direction(&hex[5..]),
+                            distance: i64::from_str_radix(&hex[..5], 16)
+                                .expect("Failed to parse hex string into number"),
+                        }
+                    }
+                }
+            })
+            .collect();
+
+        Self { instructions }
     }
 
-    plan
-}
+    // Every corner of the trench in order, including the point it ends on
+    fn corners(&self) -> Vec<Point> {
+        let mut corners = vec![Point::new(0, 0)];
+        for instruction in &self.instructions {
+            let last = corners[corners.len() - 1];
+            corners.push(last.step(instruction.direction, instruction.distance));
+        }
+        corners
+    }
+
+    /* Checks that the trench ends where it started and that no two stretches of it touch, other than consecutive ones
+     * meeting at their shared corner. The stretches are horizontal or vertical, so two of them touch exactly when their
+     * bounding boxes overlap.
+     */
+    fn validate(&self) -> Result<(), PlanError> {
+        if let Some(index) = self.instructions.iter().position(|i| i.distance <= 0) {
+            return Err(PlanError::EmptyStep(index));
+        }
 
-fn get_part_two_plan(input: &Vec<String>) -> Vec<Instruction> {
-    let re: Regex = Regex::new(r"[()]").unwrap();
-    let mut plan = vec![];
-    for line in input {
-        let hex: Vec<char> = re
-            .replace_all(line.split(" ").collect::<Vec<&str>>()[2], "")
-            .chars()
+        let corners = self.corners();
+        let end = corners[corners.len() - 1];
+        if end != corners[0] {
+            return Err(PlanError::NotClosed(end));
+        }
+
+        let count = self.instructions.len();
+        let boxes: Vec<(Point, Point)> = corners
+            .windows(2)
+            .map(|pair| {
+                (
+                    Point::new(
+                        pair[0].row.min(pair[1].row),
+                        pair[0].column.min(pair[1].column),
+                    ),
+                    Point::new(
+                        pair[0].row.max(pair[1].row),
+                        pair[0].column.max(pair[1].column),
+                    ),
+                )
+            })
+            .collect();
+        for a in 0..count {
+            for b in a + 1..count {
+                let low = Point::new(
+                    boxes[a].0.row.max(boxes[b].0.row),
+                    boxes[a].0.column.max(boxes[b].0.column),
+                );
+                let high = Point::new(
+                    boxes[a].1.row.min(boxes[b].1.row),
+                    boxes[a].1.column.min(boxes[b].1.column),
+                );
+                if low.row > high.row || low.column > high.column {
+                    continue;
+                }
+
+                let consecutive = b == a + 1 || (a == 0 && b == count - 1);
+                if !consecutive || low != high {
+                    return Err(PlanError::SelfIntersecting(a, b));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn polygon(&self) -> Polygon {
+        let steps: Vec<(Directions, i64)> = self
+            .instructions
+            .iter()
+            .map(|instruction| (instruction.direction, instruction.distance))
             .collect();
-        let direction = String::from(*hex.last().unwrap());
-        let distance =
-            i64::from_str_radix(&hex[1..hex.len() - 1].into_iter().collect::<String>(), 16)
-                .expect("Failed to parse hex string into number");
-        plan.push(Instruction {
-            direction,
-            distance,
-        });
+
+        Polygon::from_steps(Point::new(0, 0), &steps)
     }
 
-    plan
+    // Area enclosed by the centre line of the trench
+    fn area(&self) -> i128 {
+        self.polygon().area()
+    }
+
+    // Number of cubes dug out for the trench itself
+    fn perimeter(&self) -> i128 {
+        self.polygon().boundary_length()
+    }
+
+    // Number of cubes inside the trench, dug out when the interior is excavated
+    fn interior(&self) -> i128 {
+        self.polygon().interior_points()
+    }
+
+    fn capacity(&self) -> i128 {
+        self.polygon().lattice_points()
+    }
+
+    // Draws the lagoon with a cell per cube, or scaled down to about MAX_RENDER_SIZE cells across when compressed
+    fn rasterize(&self, compressed: bool) -> Grid<Location> {
+        let polygon = self.polygon();
+        if !compressed {
+            return polygon.rasterize();
+        }
+
+        let rows = ScaledAxis::new(polygon.vertices().iter().map(|p| p.row).collect());
+        let columns = ScaledAxis::new(polygon.vertices().iter().map(|p| p.column).collect());
+        Polygon::from_vertices(
+            polygon
+                .vertices()
+                .iter()
+                .map(|p| Point::new(rows.position(p.row), columns.position(p.column)))
+                .collect(),
+        )
+        .rasterize()
+    }
+
+    fn render(&self) -> Grid<Location> {
+        let (min, max) = self.polygon().bounding_box();
+        let fits = max.row - min.row < MAX_RENDER_SIZE && max.column - min.column < MAX_RENDER_SIZE;
+        self.rasterize(!fits)
+    }
 }
 
-fn get_perimeter(plan: &Vec<Instruction>) -> i64 {
-    plan.iter().map(|instruction| instruction.distance).sum()
+/* Squashes the rows or columns that have a corner on them into about MAX_RENDER_SIZE cells. Each of them keeps a cell
+* of its own and each gap between them keeps at least one cell, so separate edges never touch, and the cells left over
+* are shared out between the gaps in proportion to their lengths. A gap is never made longer than it really is, so a
+* lagoon small enough to draw as is comes out unchanged. When that would take more than MAX_RENDER_SIZE cells, the gaps
+* are dropped and the values are spread evenly over the cells instead, with neighbouring values sharing a cell once
+* there are more of them than cells.
+*/
+struct ScaledAxis {
+    values: Vec<i64>,
+    positions: Vec<i64>,
 }
 
-fn get_vertices(plan: &Vec<Instruction>) -> Vec<Coordinate> {
-    let mut x = 0;
-    let mut y = 0;
-    let mut vertices = vec![Coordinate { x: 0, y: 0 }];
-
-    for instruction in plan {
-        match instruction.direction.as_str() {
-            "U" | "3" => y += instruction.distance,
-            "D" | "1" => y -= instruction.distance,
-            "R" | "0" => x += instruction.distance,
-            "L" | "2" => x -= instruction.distance,
-            x => panic!("Invalid direction {}", x),
+impl ScaledAxis {
+    fn new(mut values: Vec<i64>) -> Self {
+        values.sort_unstable();
+        values.dedup();
+        let gaps: Vec<i64> = values
+            .windows(2)
+            .map(|pair| pair[1] - pair[0] - 1)
+            .collect();
+        let gap_cells = gaps.iter().filter(|&&gap| gap > 0).count() as i64;
+        let total: i64 = gaps.iter().sum();
+        let count = values.len() as i64;
+        if count + gap_cells > MAX_RENDER_SIZE {
+            let positions = (0..count)
+                .map(|rank| rank * (MAX_RENDER_SIZE - 1) / (count - 1))
+                .collect();
+            return Self { values, positions };
+        }
+        let spare = MAX_RENDER_SIZE - count - gap_cells;
+
+        let mut positions = vec![0];
+        for &gap in &gaps {
+            let cells = if gap == 0 {
+                0
+            } else {
+                1 + (spare * gap / total).min(gap - 1)
+            };
+            positions.push(positions[positions.len() - 1] + cells + 1);
         }
-        vertices.push(Coordinate { x, y });
+
+        Self { values, positions }
     }
 
-    vertices.pop();
+    fn position(&self, value: i64) -> i64 {
+        self.positions[self.values.binary_search(&value).unwrap()]
+    }
+}
 
-    vertices
+fn parse_direction(direction: &str) -> Directions {
+    match direction {
+        "U" | "3" => Directions::UP,
+        "D" | "1" => Directions::DOWN,
+        "R" | "0" => Directions::RIGHT,
+        "L" | "2" => Directions::LEFT,
+        x => panic!("Invalid direction {}", x),
+    }
 }
 
-fn shoelace(vertices: Vec<Coordinate>) -> i64 {
-    let mut xy = 0;
-    let mut yx = 0;
-    for i in 0..vertices.len() - 1 {
-        xy += vertices[i].x * vertices[i + 1].y;
-        yx += vertices[i].y * vertices[i + 1].x;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_plan(decoding: Decoding) -> DigPlan {
+        DigPlan::parse(&get_lines("resources/test_input.txt"), decoding)
+    }
+
+    fn parse(lines: &[&str]) -> DigPlan {
+        let lines: Vec<String> = lines.iter().map(|line| String::from(*line)).collect();
+        DigPlan::parse(&lines, Decoding::Direction)
     }
 
-    xy += vertices.last().unwrap().x * vertices.first().unwrap().y;
-    yx += vertices.last().unwrap().y * vertices.first().unwrap().x;
+    #[test]
+    fn test_parse() {
+        let lines = vec![String::from("R 6 (#70c710)")];
+
+        assert_eq!(
+            DigPlan::parse(&lines, Decoding::Direction).instructions,
+            vec![Instruction {
+                direction: Directions::RIGHT,
+                distance: 6
+            }]
+        );
+        assert_eq!(
+            DigPlan::parse(&lines, Decoding::Hex).instructions,
+            vec![Instruction {
+                direction: Directions::RIGHT,
+                distance: 461937
+            }]
+        );
+    }
+
+    #[test]
+    fn test_measurements() {
+        let plan = get_test_plan(Decoding::Direction);
+
+        assert_eq!(plan.validate(), Ok(()));
+        assert_eq!(plan.area(), 42);
+        assert_eq!(plan.perimeter(), 38);
+        assert_eq!(plan.interior(), 24);
+        assert_eq!(plan.capacity(), 62);
+
+        let plan = get_test_plan(Decoding::Hex);
+        assert_eq!(plan.validate(), Ok(()));
+        assert_eq!(plan.capacity(), 952408144115);
+    }
 
-    (xy - yx).abs() / 2
+    #[test]
+    fn test_validate() {
+        assert_eq!(
+            parse(&["R 2 (#000000)", "D 2 (#000000)"]).validate(),
+            Err(PlanError::NotClosed(Point::new(2, 2)))
+        );
+        assert_eq!(
+            parse(&["R 2 (#000000)", "L 2 (#000000)"]).validate(),
+            Err(PlanError::SelfIntersecting(0, 1))
+        );
+        assert_eq!(
+            parse(&[
+                "R 2 (#000000)",
+                "D 1 (#000000)",
+                "L 1 (#000000)",
+                "U 2 (#000000)",
+                "L 1 (#000000)",
+                "D 1 (#000000)"
+            ])
+            .validate(),
+            Err(PlanError::SelfIntersecting(0, 3))
+        );
+        assert_eq!(
+            parse(&["R 0 (#000000)"]).validate(),
+            Err(PlanError::EmptyStep(0))
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let plan = get_test_plan(Decoding::Direction);
+        let grid = plan.render();
+
+        assert_eq!(grid, plan.rasterize(false));
+        assert_eq!((grid.height(), grid.width()), (10, 7));
+    }
+
+    #[test]
+    fn test_compressed_render() {
+        let plan = get_test_plan(Decoding::Direction);
+
+        assert_eq!(plan.rasterize(true), plan.rasterize(false));
+        let grid = get_test_plan(Decoding::Hex).render();
+        assert!(grid.height() as i64 <= MAX_RENDER_SIZE);
+        assert!(grid.width() as i64 <= MAX_RENDER_SIZE);
+        assert!(grid.height() >= 2 * 6 - 1);
+
+        // The long sides are squashed and the short ones, which fit, are kept as they are
+        let plan = parse(&[
+            "R 1000 (#000000)",
+            "D 10 (#000000)",
+            "L 1000 (#000000)",
+            "U 10 (#000000)",
+        ]);
+        let grid = plan.render();
+        assert_eq!(grid.width(), MAX_RENDER_SIZE as usize);
+        assert_eq!(grid.height(), 11);
+    }
+
+    #[test]
+    fn test_scaled_axis() {
+        let axis = ScaledAxis::new(vec![1000, 0, 10, 1, 10]);
+
+        assert_eq!(axis.values, vec![0, 1, 10, 1000]);
+        // 4 values and 2 gaps leave 94 spare cells, almost all of which go to the long gap
+        assert_eq!(axis.positions, vec![0, 1, 3, 98]);
+        assert_eq!(axis.position(10), 3);
+
+        let axis = ScaledAxis::new(vec![0, 3, 5]);
+        assert_eq!(axis.positions, vec![0, 3, 5]);
+
+        // Too many values for a cell each, so neighbouring ones share
+        let axis = ScaledAxis::new((0..400).map(|value| value * 2).collect());
+        assert_eq!(axis.position(0), 0);
+        assert_eq!(axis.position(2), 0);
+        assert_eq!(axis.position(798), MAX_RENDER_SIZE - 1);
+        assert!(axis.positions.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        // Few enough values for a cell each, but not with a cell for every gap as well
+        let axis = ScaledAxis::new((0..60).map(|value| value * 2).collect());
+        assert_eq!(axis.position(2), 1);
+        assert_eq!(axis.position(118), MAX_RENDER_SIZE - 1);
+    }
+
+    #[test]
+    fn test_render_many_corners() {
+        // A staircase with 151 distinct rows and columns that have a corner on them
+        let mut lines: Vec<String> = (0..150)
+            .flat_map(|_| [String::from("R 2 (#000000)"), String::from("D 2 (#000000)")])
+            .collect();
+        lines.push(String::from("L 300 (#000000)"));
+        lines.push(String::from("U 300 (#000000)"));
+        let grid = DigPlan::parse(&lines, Decoding::Direction).render();
+
+        assert_eq!(grid.height(), MAX_RENDER_SIZE as usize);
+        assert_eq!(grid.width(), MAX_RENDER_SIZE as usize);
+    }
 }
@@ -1,147 +1,604 @@
-use std::collections::HashMap;
+use std::fmt;
 
-use library::{get_filename_arg, get_lines};
+use library::intern::Interner;
+use library::{get_filename_and_options, get_lines};
+
+const MIN_RATING: u64 = 1;
+const MAX_RATING: u64 = 4000;
 
-/* Based on solution by Reddit user Polaric_Spiral https://www.reddit.com/r/adventofcode/comments/18ltr8m/comment/ke48wv2/?utm_source=share&utm_medium=web3x&utm_name=web3xcss&utm_term=1&utm_content=share_button&rdt=35843 */
 fn main() {
-    let file_name = get_filename_arg();
-    let mut workflow_strings = get_lines(&file_name);
-    let mut parts =
-        workflow_strings.split_off(workflow_strings.iter().position(|i| i == "").unwrap());
-    parts.remove(0);
+    // --unreachable reports every rule that no part can ever reach on stderr
+    let (file_name, options) = get_filename_and_options(&["unreachable"]);
+    let lines = get_lines(&file_name);
+    let (workflows, parts) = parse_input(&lines).unwrap_or_else(|error| panic!("{}", error));
+
+    let domain = PartRange::new([MIN_RATING; 4], [MAX_RATING; 4]);
+    let compiled = workflows
+        .compile(&domain)
+        .unwrap_or_else(|error| panic!("{}", error));
 
-    let mut workflows = HashMap::new();
-    for workflow_string in workflow_strings {
-        let replaced = workflow_string.replace("}", "");
-        let splits: Vec<&str> = replaced.split("{").collect();
-        let (key, rule) = (String::from(splits[0]), String::from(splits[1]));
-        let rules: Vec<Rule> = rule.split(",").map(|r| parse_rule(r)).collect();
-        workflows.insert(key, rules);
+    if options.is_set("unreachable") {
+        // Written out in one go, as stderr is not buffered and there can be thousands of them
+        let unreachable: String = compiled
+            .unreachable_rules()
+            .iter()
+            .map(|(workflow, index)| {
+                format!(
+                    "Rule {} of workflow {} can never be reached\n",
+                    index, workflow
+                )
+            })
+            .collect();
+        eprint!("{}", unreachable);
     }
-    println!("Part 1: {}", part_one(&workflows, &parts));
-    println!("Part 2: {}", part_two(&workflows));
+
+    println!("Part 1: {}", part_one(&compiled, &parts));
+    println!("Part 2: {}", compiled.count_accepted(&domain));
 }
 
-fn part_one(workflows: &HashMap<String, Vec<Rule>>, part_strings: &Vec<String>) -> i64 {
-    let mut parts = vec![];
-    for part in part_strings {
-        parts.push(parse_part(part));
-    }
+fn parse_input(lines: &[String]) -> Result<(Workflows, Vec<Part>), WorkflowError> {
+    let split = lines
+        .iter()
+        .position(|line| line.is_empty())
+        .ok_or(WorkflowError::MissingParts)?;
 
+    let workflows = Workflows::parse(&lines[..split])?;
+    let parts = lines[split + 1..]
+        .iter()
+        .map(|line| parse_part(line))
+        .collect::<Result<_, _>>()?;
+
+    Ok((workflows, parts))
+}
+
+fn part_one(compiled: &CompiledWorkflows, parts: &[Part]) -> u64 {
     parts
-        .into_iter()
-        .map(|mut part| {
-            get_accepted_count(&mut part, workflows, None) * part.values().map(|p| p.0).sum::<i64>()
-        })
+        .iter()
+        .filter(|part| compiled.accepts(part))
+        .map(|part| part.iter().sum::<u64>())
         .sum()
 }
 
-fn part_two(workflows: &HashMap<String, Vec<Rule>>) -> i64 {
-    let mut ratings = HashMap::from([
-        (String::from("x"), (1, 4000)),
-        (String::from("m"), (1, 4000)),
-        (String::from("a"), (1, 4000)),
-        (String::from("s"), (1, 4000)),
-    ]);
-    let x = get_accepted_count(&mut ratings, workflows, None);
-    x
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Category {
+    X,
+    M,
+    A,
+    S,
 }
 
-#[derive(Debug)]
+impl Category {
+    fn parse(category: &str) -> Result<Self, WorkflowError> {
+        match category {
+            "x" => Ok(Self::X),
+            "m" => Ok(Self::M),
+            "a" => Ok(Self::A),
+            "s" => Ok(Self::S),
+            x => Err(WorkflowError::InvalidCategory(String::from(x))),
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Self::X => 0,
+            Self::M => 1,
+            Self::A => 2,
+            Self::S => 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Comparison {
+    Lt,
+    Gt,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Target {
+    Accept,
+    Reject,
+    Workflow(u32),
+}
+
+impl Target {
+    fn parse(target: &str, names: &mut Interner) -> Self {
+        match target {
+            "A" => Self::Accept,
+            "R" => Self::Reject,
+            name => Self::Workflow(names.intern(name)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Condition {
+    category: Category,
+    comparison: Comparison,
+    value: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 struct Rule {
-    value: i64,
-    operation: i64,
-    category: String,
-    goto: String,
-}
-
-fn parse_rule(rule: &str) -> Rule {
-    if !rule.contains(":") {
-        return Rule {
-            value: 0,
-            operation: 1,
-            category: String::from("x"),
-            goto: String::from(rule),
+    condition: Option<Condition>,
+    target: Target,
+}
+
+impl Rule {
+    // e.g. "a<2006:qkq", or just the target for a rule that always applies
+    fn parse(rule: &str, names: &mut Interner) -> Result<Self, WorkflowError> {
+        let Some((evaluation, target)) = rule.split_once(':') else {
+            return Ok(Self {
+                condition: None,
+                target: Target::parse(rule, names),
+            });
         };
+
+        let category = Category::parse(evaluation.get(..1).unwrap_or(evaluation))?;
+        let comparison = match evaluation.get(1..2) {
+            Some("<") => Comparison::Lt,
+            Some(">") => Comparison::Gt,
+            _ => return Err(WorkflowError::InvalidComparison(String::from(evaluation))),
+        };
+        let value = &evaluation[2..];
+        let value = value
+            .parse()
+            .map_err(|_| WorkflowError::InvalidNumber(String::from(value)))?;
+
+        Ok(Self {
+            condition: Some(Condition {
+                category,
+                comparison,
+                value,
+            }),
+            target: Target::parse(target, names),
+        })
     }
+}
+
+type Part = [u64; 4];
 
-    let split: Vec<&str> = rule.split(":").collect();
-    let (evaluation, goto) = (split[0], split[1]);
-    let evaluation_chars: Vec<char> = evaluation.chars().collect();
-    let category = String::from(evaluation_chars[0]);
-    let comparison = evaluation_chars[1];
-    let operation = if comparison == '<' { -1 } else { 1 };
-    let value = evaluation[2..evaluation.len()].parse::<i64>().unwrap();
+// e.g. "{x=787,m=2655,a=1222,s=2876}"
+fn parse_part(part: &str) -> Result<Part, WorkflowError> {
+    let invalid = || WorkflowError::InvalidPart(String::from(part));
+    let pairs = part
+        .strip_prefix('{')
+        .and_then(|pairs| pairs.strip_suffix('}'))
+        .ok_or_else(invalid)?;
 
-    Rule {
-        value,
-        operation,
-        category,
-        goto: String::from(goto),
+    let mut ratings = [0; 4];
+    for pair in pairs.split(',') {
+        let (category, rating) = pair.split_once('=').ok_or_else(invalid)?;
+        ratings[Category::parse(category)?.index()] = rating
+            .parse()
+            .map_err(|_| WorkflowError::InvalidNumber(String::from(rating)))?;
     }
+
+    Ok(ratings)
+}
+
+// Inclusive lower and upper bounds for each category, indexed by Category::index
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct PartRange {
+    low: [u64; 4],
+    high: [u64; 4],
 }
 
-fn parse_part(part: &str) -> HashMap<String, (i64, i64)> {
-    let mut current_part = HashMap::new();
-    let line: Vec<&str> = part[1..part.len() - 1].split(",").collect();
-    for pair in line {
-        let split: Vec<&str> = pair.split("=").collect();
-        let rating = split[1].parse().unwrap();
-        current_part.insert(String::from(split[0]), (rating, rating));
+impl PartRange {
+    fn new(low: [u64; 4], high: [u64; 4]) -> Self {
+        Self { low, high }
+    }
+
+    fn is_empty(&self) -> bool {
+        (0..4).any(|i| self.low[i] > self.high[i])
+    }
+
+    fn volume(&self) -> u128 {
+        if self.is_empty() {
+            return 0;
+        }
+        (0..4)
+            .map(|i| (self.high[i] - self.low[i]) as u128 + 1)
+            .product()
+    }
+
+    fn contains(&self, part: &Part) -> bool {
+        (0..4).all(|i| self.low[i] <= part[i] && part[i] <= self.high[i])
+    }
+
+    fn intersect(&self, other: &Self) -> Self {
+        let mut result = *self;
+        for i in 0..4 {
+            result.low[i] = result.low[i].max(other.low[i]);
+            result.high[i] = result.high[i].min(other.high[i]);
+        }
+        result
+    }
+
+    fn empty() -> Self {
+        Self::new([1; 4], [0; 4])
     }
 
-    current_part
+    // Splits into the parts that pass the condition and the parts that fall through to the next rule
+    fn split(&self, condition: &Condition) -> (Self, Self) {
+        let i = condition.category.index();
+        let mut matching = *self;
+        let mut rest = *self;
+        match condition.comparison {
+            Comparison::Lt => {
+                let Some(high) = condition.value.checked_sub(1) else {
+                    return (Self::empty(), rest);
+                };
+                matching.high[i] = matching.high[i].min(high);
+                rest.low[i] = rest.low[i].max(condition.value);
+            }
+            Comparison::Gt => {
+                let Some(low) = condition.value.checked_add(1) else {
+                    return (Self::empty(), rest);
+                };
+                matching.low[i] = matching.low[i].max(low);
+                rest.high[i] = rest.high[i].min(condition.value);
+            }
+        }
+        (matching, rest)
+    }
 }
 
-fn get_accepted_count(
-    ratings: &mut HashMap<String, (i64, i64)>,
-    workflows: &HashMap<String, Vec<Rule>>,
-    workflow: Option<&String>,
-) -> i64 {
-    let workflow = match workflow {
-        Some(x) => x,
-        None => "in",
-    };
+#[derive(Debug, PartialEq)]
+enum WorkflowError {
+    MissingParts,
+    InvalidWorkflow(String),
+    InvalidPart(String),
+    InvalidCategory(String),
+    // A condition that is not a category followed by < or >
+    InvalidComparison(String),
+    InvalidNumber(String),
+    UnknownWorkflow(String),
+    DuplicateWorkflow(String),
+    Cycle(Vec<String>),
+}
 
-    if workflow == "R" {
-        return 0;
+impl fmt::Display for WorkflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingParts => write!(f, "Missing blank line between workflows and parts"),
+            Self::InvalidWorkflow(line) => write!(f, "Invalid workflow \"{}\"", line),
+            Self::InvalidPart(part) => write!(f, "Invalid part \"{}\"", part),
+            Self::InvalidCategory(category) => write!(f, "Invalid category \"{}\"", category),
+            Self::InvalidComparison(condition) => {
+                write!(f, "Invalid comparison in condition \"{}\"", condition)
+            }
+            Self::InvalidNumber(number) => write!(f, "Failed to parse {} into number", number),
+            Self::UnknownWorkflow(name) => write!(f, "Workflow {} does not exist", name),
+            Self::DuplicateWorkflow(name) => write!(f, "Workflow {} is defined twice", name),
+            Self::Cycle(path) => write!(f, "Workflows form a cycle: {}", path.join(" -> ")),
+        }
     }
+}
+
+/* Workflow names are interned, and the rules for each id are stored at that index. Names that only ever appear as a
+* target have an id but no rules.
+*/
+struct Workflows {
+    names: Interner,
+    workflows: Vec<Option<Vec<Rule>>>,
+}
 
-    if workflow == "A" {
-        let mut product = 1;
-        let values: Vec<(i64, i64)> = ratings.values().cloned().collect();
-        for (low, high) in values {
-            product *= high - low + 1;
+impl Workflows {
+    fn parse(lines: &[String]) -> Result<Self, WorkflowError> {
+        let mut names = Interner::new();
+        let mut workflows = vec![];
+        for line in lines {
+            let (name, rules) = line
+                .strip_suffix('}')
+                .and_then(|line| line.split_once('{'))
+                .ok_or_else(|| WorkflowError::InvalidWorkflow(String::from(line)))?;
+            let id = names.intern(name) as usize;
+            let rules = rules
+                .split(',')
+                .map(|rule| Rule::parse(rule, &mut names))
+                .collect::<Result<_, _>>()?;
+            if workflows.len() < names.len() {
+                workflows.resize(names.len(), None);
+            }
+            if workflows[id].replace(rules).is_some() {
+                return Err(WorkflowError::DuplicateWorkflow(String::from(name)));
+            }
         }
-        return product;
-    }
-
-    let mut combinations = 0;
-
-    for Rule {
-        value,
-        operation,
-        category,
-        goto,
-    } in workflows.get(workflow).unwrap()
-    {
-        let (low, high) = ratings.get(category).unwrap();
-        if (low - value) * operation > 0 {
-            if (high - value) * operation > 0 {
-                return combinations + get_accepted_count(ratings, workflows, Some(goto));
-            } else {
-                let mut next_part = ratings.clone();
-                next_part.insert(String::from(category), (*low, value - 1));
-                combinations += get_accepted_count(&mut next_part, workflows, None);
-                ratings.insert(String::from(category), (*value, *high));
+
+        Ok(Self { names, workflows })
+    }
+
+    fn start(&self) -> Result<u32, WorkflowError> {
+        self.names
+            .get("in")
+            .ok_or_else(|| WorkflowError::UnknownWorkflow(String::from("in")))
+    }
+
+    fn get(&self, id: u32) -> Result<&[Rule], WorkflowError> {
+        match self.workflows.get(id as usize) {
+            Some(Some(rules)) => Ok(rules),
+            _ => Err(WorkflowError::UnknownWorkflow(String::from(
+                self.names.name(id),
+            ))),
+        }
+    }
+
+    // Depth first search over the workflows reachable from "in", returning the first loop found
+    fn find_cycle(&self) -> Result<Option<Vec<String>>, WorkflowError> {
+        let mut finished = vec![false; self.names.len()];
+        let mut path = vec![];
+        self.find_cycle_from(self.start()?, &mut path, &mut finished)
+    }
+
+    fn find_cycle_from(
+        &self,
+        id: u32,
+        path: &mut Vec<u32>,
+        finished: &mut [bool],
+    ) -> Result<Option<Vec<String>>, WorkflowError> {
+        if let Some(start) = path.iter().position(|&n| n == id) {
+            let mut cycle: Vec<String> = path[start..]
+                .iter()
+                .map(|&n| String::from(self.names.name(n)))
+                .collect();
+            cycle.push(String::from(self.names.name(id)));
+            return Ok(Some(cycle));
+        }
+        if finished[id as usize] {
+            return Ok(None);
+        }
+
+        path.push(id);
+        for rule in self.get(id)? {
+            if let Target::Workflow(next) = rule.target {
+                if let Some(cycle) = self.find_cycle_from(next, path, finished)? {
+                    return Ok(Some(cycle));
+                }
+            }
+        }
+        path.pop();
+        finished[id as usize] = true;
+
+        Ok(None)
+    }
+
+    /* Pushes the whole domain through the workflows starting at "in", splitting it at every rule, so the accepted
+     * parts end up as a list of disjoint ranges. The range splitting is based on the solution by Reddit user
+     * Polaric_Spiral https://www.reddit.com/r/adventofcode/comments/18ltr8m/comment/ke48wv2/
+     */
+    fn compile(&self, domain: &PartRange) -> Result<CompiledWorkflows, WorkflowError> {
+        if let Some(cycle) = self.find_cycle()? {
+            return Err(WorkflowError::Cycle(cycle));
+        }
+
+        let mut accepted = vec![];
+        let mut reached: Vec<Vec<bool>> = self
+            .workflows
+            .iter()
+            .map(|rules| vec![false; rules.as_ref().map_or(0, |rules| rules.len())])
+            .collect();
+        let mut stack = vec![(self.start()?, *domain)];
+
+        while let Some((id, mut range)) = stack.pop() {
+            for (index, rule) in self.get(id)?.iter().enumerate() {
+                if range.is_empty() {
+                    break;
+                }
+
+                let matching = match &rule.condition {
+                    Some(condition) => {
+                        let (matching, rest) = range.split(condition);
+                        range = rest;
+                        matching
+                    }
+                    None => std::mem::replace(&mut range, PartRange::empty()),
+                };
+                if matching.is_empty() {
+                    continue;
+                }
+                reached[id as usize][index] = true;
+
+                match rule.target {
+                    Target::Accept => accepted.push(matching),
+                    Target::Reject => {}
+                    Target::Workflow(next) => stack.push((next, matching)),
+                }
             }
-        } else if (high - value) * operation > 0 {
-            let mut next_part = ratings.clone();
-            next_part.insert(String::from(category), (value + 1, *high));
-            combinations += get_accepted_count(&mut next_part, workflows, None);
-            ratings.insert(String::from(category), (*low, *value));
         }
+
+        let mut unreachable: Vec<(String, usize)> = reached
+            .iter()
+            .enumerate()
+            .flat_map(|(id, rules)| {
+                rules
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &reached)| !reached)
+                    .map(move |(index, _)| (String::from(self.names.name(id as u32)), index))
+            })
+            .collect();
+        unreachable.sort();
+
+        Ok(CompiledWorkflows {
+            accepted,
+            unreachable,
+        })
+    }
+}
+
+struct CompiledWorkflows {
+    accepted: Vec<PartRange>,
+    unreachable: Vec<(String, usize)>,
+}
+
+impl CompiledWorkflows {
+    fn accepts(&self, part: &Part) -> bool {
+        self.accepted.iter().any(|range| range.contains(part))
     }
 
-    0
+    /* The accepted ranges never overlap, so their intersections with the bounds can just be added up. They all lie
+     * within the domain the workflows were compiled for, so any parts in the bounds outside it count as rejected.
+     */
+    fn count_accepted(&self, bounds: &PartRange) -> u128 {
+        self.accepted
+            .iter()
+            .map(|range| range.intersect(bounds).volume())
+            .sum()
+    }
+
+    fn unreachable_rules(&self) -> &[(String, usize)] {
+        &self.unreachable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_domain() -> PartRange {
+        PartRange::new([MIN_RATING; 4], [MAX_RATING; 4])
+    }
+
+    fn compile(lines: &[&str]) -> Result<CompiledWorkflows, WorkflowError> {
+        let lines: Vec<String> = lines.iter().map(|line| String::from(*line)).collect();
+        Workflows::parse(&lines)?.compile(&get_domain())
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let mut names = Interner::new();
+        names.intern("in");
+
+        assert_eq!(
+            Rule::parse("a<2006:qkq", &mut names),
+            Ok(Rule {
+                condition: Some(Condition {
+                    category: Category::A,
+                    comparison: Comparison::Lt,
+                    value: 2006,
+                }),
+                target: Target::Workflow(1),
+            })
+        );
+        assert_eq!(names.name(1), "qkq");
+        assert_eq!(
+            Rule::parse("R", &mut names),
+            Ok(Rule {
+                condition: None,
+                target: Target::Reject,
+            })
+        );
+    }
+
+    #[test]
+    fn test_example() {
+        let (workflows, parts) = parse_input(&get_lines("resources/test_input.txt")).unwrap();
+        let compiled = workflows.compile(&get_domain()).unwrap();
+
+        assert_eq!(part_one(&compiled, &parts), 19114);
+        assert_eq!(compiled.count_accepted(&get_domain()), 167409079868000);
+        assert!(compiled.accepts(&[787, 2655, 1222, 2876]));
+        assert!(!compiled.accepts(&[1679, 44, 2067, 496]));
+    }
+
+    #[test]
+    fn test_count_accepted_with_bounds() {
+        let compiled = compile(&["in{x<10:A,m>20:A,R}"]).unwrap();
+        let bounds = PartRange::new([1, 1, 1, 1], [20, 30, 1, 1]);
+
+        assert_eq!(compiled.count_accepted(&bounds), 9 * 30 + 11 * 10);
+        assert_eq!(compiled.count_accepted(&PartRange::empty()), 0);
+
+        // Ratings of 0 and above MAX_RATING are outside the domain, so they are never accepted
+        let outside = PartRange::new([0, 1, 1, 1], [20, 30, 1, 1]);
+        assert_eq!(compiled.count_accepted(&outside), 9 * 30 + 11 * 10);
+        let above = PartRange::new([1, 1, 1, 1], [MAX_RATING + 100, 1, 1, 1]);
+        assert_eq!(compiled.count_accepted(&above), 9);
+
+        // Compiling for a wider domain takes them into account
+        let lines = vec![String::from("in{x<10:A,m>20:A,R}")];
+        let wider = Workflows::parse(&lines)
+            .unwrap()
+            .compile(&PartRange::new([0; 4], [MAX_RATING + 100; 4]))
+            .unwrap();
+        assert_eq!(wider.count_accepted(&outside), 10 * 30 + 11 * 10);
+        assert_eq!(wider.count_accepted(&above), 9);
+    }
+
+    #[test]
+    fn test_unreachable_rules() {
+        let compiled = compile(&["in{x<10:A,x<5:R,ab}", "ab{s>0:A,R}", "zz{A}"]).unwrap();
+
+        assert_eq!(
+            compiled.unreachable_rules(),
+            &[
+                (String::from("ab"), 1),
+                (String::from("in"), 1),
+                (String::from("zz"), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cycle() {
+        assert_eq!(
+            compile(&["in{x<10:ab,A}", "ab{m>5:cd,R}", "cd{ab}"]).err(),
+            Some(WorkflowError::Cycle(vec![
+                String::from("ab"),
+                String::from("cd"),
+                String::from("ab")
+            ]))
+        );
+        assert_eq!(
+            compile(&["in{x<10:ab,A}"]).err(),
+            Some(WorkflowError::UnknownWorkflow(String::from("ab")))
+        );
+    }
+
+    #[test]
+    fn test_duplicate_workflow() {
+        assert_eq!(
+            compile(&["in{x<10:ab,A}", "ab{R}", "ab{A}"]).err(),
+            Some(WorkflowError::DuplicateWorkflow(String::from("ab")))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |message: &str| Some(WorkflowError::InvalidWorkflow(String::from(message)));
+        let mut names = Interner::new();
+
+        assert_eq!(compile(&["in{A"]).err(), error("in{A"));
+        assert_eq!(compile(&["in A}"]).err(), error("in A}"));
+        assert_eq!(
+            Rule::parse("q<5:A", &mut names),
+            Err(WorkflowError::InvalidCategory(String::from("q")))
+        );
+        assert_eq!(
+            Rule::parse("x=5:A", &mut names),
+            Err(WorkflowError::InvalidComparison(String::from("x=5")))
+        );
+        assert_eq!(
+            Rule::parse("x<y:A", &mut names),
+            Err(WorkflowError::InvalidNumber(String::from("y")))
+        );
+        assert_eq!(parse_part("{x=1,m=2,a=3,s=4}"), Ok([1, 2, 3, 4]));
+        assert_eq!(
+            parse_part("x=1,m=2"),
+            Err(WorkflowError::InvalidPart(String::from("x=1,m=2")))
+        );
+        assert_eq!(
+            parse_part("{x=1,m}"),
+            Err(WorkflowError::InvalidPart(String::from("{x=1,m}")))
+        );
+        assert_eq!(
+            parse_part("{x=-1}"),
+            Err(WorkflowError::InvalidNumber(String::from("-1")))
+        );
+        assert!(matches!(
+            parse_input(&[String::from("in{A}")]),
+            Err(WorkflowError::MissingParts)
+        ));
+    }
 }
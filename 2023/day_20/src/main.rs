@@ -1,136 +1,137 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::VecDeque;
 
+use library::intern::Interner;
 use library::{get_filename_arg, get_lines};
 
 fn main() {
     let file_name = get_filename_arg();
     let lines = get_lines(&file_name);
 
-    let (mut part_one_modules, destinations) = get_modules_and_destinations(&lines);
-    println!("Part 1: {}", part_one(&mut part_one_modules, &destinations));
+    let mut part_one_circuit = get_circuit(&lines);
+    println!("Part 1: {}", part_one(&mut part_one_circuit));
 
-    let (mut part_two_modules, _) = get_modules_and_destinations(&lines);
-    println!("Part 2: {}", part_two(&mut part_two_modules, &destinations));
+    let mut part_two_circuit = get_circuit(&lines);
+    println!("Part 2: {}", part_two(&mut part_two_circuit));
 }
 
-fn get_modules_and_destinations(
-    lines: &Vec<String>,
-) -> (
-    HashMap<String, Box<dyn Module>>,
-    HashMap<String, Vec<String>>,
-) {
-    let mut modules: HashMap<String, Box<dyn Module>> = HashMap::new();
-    let mut destinations: HashMap<String, Vec<String>> = HashMap::new();
+/* Module names are interned, and the module and destinations for each id are stored at that index. Names that are
+* only ever a destination, like rx, have an id but no module.
+*/
+struct Circuit {
+    names: Interner,
+    modules: Vec<Option<Box<dyn Module>>>,
+    destinations: Vec<Vec<u32>>,
+}
+
+impl Circuit {
+    fn id(&self, name: &str) -> u32 {
+        self.names
+            .get(name)
+            .unwrap_or_else(|| panic!("No module named {}", name))
+    }
+
+    // Sends a low pulse to the broadcaster and runs until the circuit settles, calling `on_send` for every pulse sent
+    fn press_button<F>(&mut self, mut on_send: F)
+    where
+        F: FnMut(u32, bool),
+    {
+        let mut queue = VecDeque::new();
+        queue.push_back((self.id("broadcaster"), false));
+        while let Some((from, pulse_value)) = queue.pop_front() {
+            for &destination in &self.destinations[from as usize] {
+                on_send(destination, pulse_value);
+
+                let Some(next_module) = self.modules[destination as usize].as_mut() else {
+                    continue;
+                };
+                if !next_module.continue_pulsing(pulse_value) {
+                    continue;
+                }
+                next_module.pulse(pulse_value, from);
+                queue.push_back((destination, next_module.get_state()));
+            }
+        }
+    }
+}
+
+fn get_circuit(lines: &[String]) -> Circuit {
+    let mut names = Interner::new();
+    let mut modules: Vec<Option<Box<dyn Module>>> = vec![];
+    let mut destinations: Vec<Vec<u32>> = vec![];
     for line in lines {
         let (source, destination) = line.split_once(" -> ").unwrap();
-        let destination_names: Vec<String> =
-            destination.split(", ").map(|s| String::from(s)).collect();
         let prefix = source.chars().collect::<Vec<char>>()[0];
-        let mut name = source[1..source.len()].to_string();
+        let mut name = &source[1..source.len()];
         let module: Box<dyn Module> = match prefix {
             '%' => Box::new(FlipFlop::default()),
             '&' => Box::new(Conjunction::default()),
             _ => {
-                name = String::from("broadcaster");
+                name = "broadcaster";
                 Box::new(Broadcaster::default())
             }
         };
-        modules.insert(name.clone(), module);
-        destinations.insert(name, destination_names);
+        let id = names.intern(name) as usize;
+        let destination_ids = destination.split(", ").map(|s| names.intern(s)).collect();
+
+        modules.resize_with(names.len(), || None);
+        destinations.resize(names.len(), vec![]);
+        modules[id] = Some(module);
+        destinations[id] = destination_ids;
     }
 
-    for (name, destination_names) in destinations.iter() {
-        for destination_name in destination_names {
-            modules
-                .get_mut(destination_name)
-                .map(|n| n.add_input(name.clone()));
+    for (id, destination_ids) in destinations.iter().enumerate() {
+        for &destination in destination_ids {
+            if let Some(module) = modules[destination as usize].as_mut() {
+                module.add_input(id as u32);
+            }
         }
     }
-    (modules, destinations)
+
+    Circuit {
+        names,
+        modules,
+        destinations,
+    }
 }
 
-fn part_one(
-    modules: &mut HashMap<String, Box<dyn Module>>,
-    destinations: &HashMap<String, Vec<String>>,
-) -> u64 {
+fn part_one(circuit: &mut Circuit) -> u64 {
     let mut high_count = 0;
     let mut low_count = 0;
 
     for _ in 0..1000 {
-        let mut queue = VecDeque::new();
         low_count += 1;
-        queue.push_back((String::from("broadcaster"), false));
-        while queue.len() > 0 {
-            let (name, pulse_value) = queue.pop_front().unwrap();
-            for destination in destinations.get(&name).unwrap() {
-                if pulse_value {
-                    high_count += 1;
-                } else {
-                    low_count += 1;
-                }
-
-                let next_module = modules.get_mut(destination);
-
-                if next_module.is_none() {
-                    continue;
-                }
-
-                let next_module = next_module.unwrap();
-                if !next_module.continue_pulsing(pulse_value) {
-                    continue;
-                }
-                next_module.pulse(pulse_value, name.clone());
-                queue.push_back((destination.to_string(), next_module.get_state()));
+        circuit.press_button(|_, pulse_value| {
+            if pulse_value {
+                high_count += 1;
+            } else {
+                low_count += 1;
             }
-        }
+        });
     }
 
     high_count * low_count
 }
 
-fn part_two(
-    modules: &mut HashMap<String, Box<dyn Module>>,
-    destinations: &HashMap<String, Vec<String>>,
-) -> u64 {
+fn part_two(circuit: &mut Circuit) -> u64 {
     let mut i = 1;
     let mut cycle_counts = vec![];
-    let mut inputs_to_inputs_to_rx = vec![
-        String::from("pq"),
-        String::from("fg"),
-        String::from("dk"),
-        String::from("fm"),
-    ];
-
-    while inputs_to_inputs_to_rx.len() > 0 {
-        let mut queue = VecDeque::new();
-        queue.push_back((String::from("broadcaster"), false));
-        while queue.len() > 0 {
-            let (name, pulse_value) = queue.pop_front().unwrap();
-            for destination in destinations.get(&name).unwrap() {
-                if inputs_to_inputs_to_rx.contains(destination) && !pulse_value {
-                    inputs_to_inputs_to_rx.remove(
-                        inputs_to_inputs_to_rx
-                            .iter()
-                            .position(|r| r == destination)
-                            .unwrap(),
-                    );
+    let mut inputs_to_inputs_to_rx: Vec<u32> = ["pq", "fg", "dk", "fm"]
+        .iter()
+        .map(|name| circuit.id(name))
+        .collect();
+
+    while !inputs_to_inputs_to_rx.is_empty() {
+        circuit.press_button(|destination, pulse_value| {
+            if !pulse_value {
+                if let Some(position) = inputs_to_inputs_to_rx
+                    .iter()
+                    .position(|&r| r == destination)
+                {
+                    inputs_to_inputs_to_rx.remove(position);
                     cycle_counts.push(i);
                 }
-
-                let next_module = modules.get_mut(destination);
-
-                if next_module.is_none() {
-                    continue;
-                }
-
-                let next_module = next_module.unwrap();
-                if !next_module.continue_pulsing(pulse_value) {
-                    continue;
-                }
-                next_module.pulse(pulse_value, name.clone());
-                queue.push_back((destination.to_string(), next_module.get_state()));
             }
-        }
+        });
         i += 1;
     }
     least_common_multiple(cycle_counts)
@@ -159,9 +160,9 @@ fn least_common_multiple(values: Vec<u64>) -> u64 {
 }
 
 trait Module {
-    fn pulse(&mut self, pulse_value: bool, from: String);
+    fn pulse(&mut self, pulse_value: bool, from: u32);
     fn get_state(&self) -> bool;
-    fn add_input(&mut self, _: String) {}
+    fn add_input(&mut self, _: u32) {}
     fn continue_pulsing(&self, _: bool) -> bool {
         true
     }
@@ -173,7 +174,7 @@ struct FlipFlop {
 }
 
 impl Module for FlipFlop {
-    fn pulse(&mut self, pulse_value: bool, _: String) {
+    fn pulse(&mut self, pulse_value: bool, _: u32) {
         if !pulse_value {
             self.state = !self.state;
         }
@@ -193,15 +194,17 @@ impl Module for FlipFlop {
 
 #[derive(Default, Clone, Debug)]
 struct Conjunction {
-    inputs: HashMap<String, bool>,
+    inputs: Vec<(u32, bool)>,
     state: bool,
 }
 
 impl Module for Conjunction {
-    fn pulse(&mut self, pulse_value: bool, from: String) {
-        self.inputs.insert(from, pulse_value);
+    fn pulse(&mut self, pulse_value: bool, from: u32) {
+        if let Some(input) = self.inputs.iter_mut().find(|(id, _)| *id == from) {
+            input.1 = pulse_value;
+        }
         let mut new_pulse_value = false;
-        for value in self.inputs.values() {
+        for (_, value) in &self.inputs {
             if !value {
                 new_pulse_value = true
             }
@@ -213,8 +216,8 @@ impl Module for Conjunction {
         self.state
     }
 
-    fn add_input(&mut self, from: String) {
-        self.inputs.insert(from, false);
+    fn add_input(&mut self, from: u32) {
+        self.inputs.push((from, false));
     }
 }
 
@@ -224,7 +227,7 @@ struct Broadcaster {
 }
 
 impl Module for Broadcaster {
-    fn pulse(&mut self, pulse_value: bool, _: String) {
+    fn pulse(&mut self, pulse_value: bool, _: u32) {
         self.state = pulse_value;
     }
 
@@ -232,3 +235,33 @@ impl Module for Broadcaster {
         self.state
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_one() {
+        let mut circuit = get_circuit(&get_lines("resources/test_input.txt"));
+
+        assert_eq!(part_one(&mut circuit), 32000000);
+    }
+
+    #[test]
+    fn test_get_circuit() {
+        let circuit = get_circuit(&[
+            String::from("broadcaster -> a"),
+            String::from("%a -> inv, con"),
+            String::from("&inv -> b"),
+            String::from("%b -> con"),
+            String::from("&con -> output"),
+        ]);
+
+        let output = circuit.id("output");
+        assert!(circuit.modules[output as usize].is_none());
+        assert_eq!(
+            circuit.destinations[circuit.id("a") as usize],
+            vec![circuit.id("inv"), circuit.id("con")]
+        );
+    }
+}
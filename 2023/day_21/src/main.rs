@@ -1,137 +1,308 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
 
+use library::grid::Grid;
 use library::{get_filename_arg, get_two_dimensional_vector};
 
+const STEPS: usize = 64;
+const INFINITE_STEPS: usize = 26501365;
+// How many periods of steps are simulated directly when looking for the quadratic the counts settle into
+const LOOKAHEAD: usize = 8;
+// How many more periods have to follow the quadratic exactly before it is trusted
+const CONFIRMATIONS: usize = 2;
+// The most steps simulated directly on the infinite map when the counts can not be extrapolated
+const MAX_SIMULATED_STEPS: usize = 2000;
+
 fn main() {
     let file_name = get_filename_arg();
     let input = get_two_dimensional_vector(&file_name);
+    let garden = Garden::parse(&input);
+
+    println!("Part 1: {}", garden.reachable(STEPS));
+    println!(
+        "Part 2: {}",
+        garden
+            .reachable_infinite(INFINITE_STEPS)
+            .unwrap_or_else(|error| panic!("{}", error))
+    );
+}
 
-    let mut s_location = None;
-    for i in 0..input.len() {
-        let s_column = input[i].iter().position(|&j| j == 'S');
-        if s_column.is_some() {
-            s_location = Some((i, s_column.unwrap()));
-            break;
+#[derive(PartialEq, Eq, Debug)]
+enum GardenError {
+    TooManySteps(usize),
+}
+
+impl fmt::Display for GardenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManySteps(steps) => write!(
+                f,
+                "The counts for this garden do not settle into a quadratic, and {} steps are too many to simulate \
+                 (at most {})",
+                steps, MAX_SIMULATED_STEPS
+            ),
         }
     }
+}
 
-    let s_location = s_location.expect("No starting position found");
+// Plots that can be stood on after exactly `steps` steps, from how many are first reached after each number of steps
+fn count_reachable(distance_counts: &[u64], steps: usize) -> u64 {
+    /* A plot first reached after d steps can also be stood on after d + 2, d + 4... steps by stepping away and back,
+     * but never after an odd number more, since every step changes the parity of row + column.
+     */
+    distance_counts
+        .iter()
+        .take(steps + 1)
+        .skip(steps % 2)
+        .step_by(2)
+        .sum()
+}
 
-    println!("Part 1: {}", part_one(&input, s_location));
-    println!("Part 2: {}", part_two(&input, s_location));
+struct Garden {
+    rocks: Grid<bool>,
+    start: (usize, usize),
 }
 
-fn get_adjacent_locations(
-    coordinate: (usize, usize),
-    input: &Vec<Vec<char>>,
-) -> Vec<(usize, usize)> {
-    let mut adjacent = vec![];
-    let (row, column) = coordinate;
+impl Garden {
+    fn parse(input: &[Vec<char>]) -> Self {
+        let mut start = None;
+        for (row, line) in input.iter().enumerate() {
+            if let Some(column) = line.iter().position(|&c| c == 'S') {
+                start = Some((row, column));
+                break;
+            }
+        }
 
-    if row > 0 && input[row - 1][column] != '#' {
-        adjacent.push((row - 1, column));
-    }
-    if column > 0 && input[row][column - 1] != '#' {
-        adjacent.push((row, column - 1));
+        Self {
+            rocks: Grid::from_rows(
+                input
+                    .iter()
+                    .map(|line| line.iter().map(|&c| c == '#').collect())
+                    .collect(),
+            ),
+            start: start.expect("No starting position found"),
+        }
     }
-    if row < input.len() - 1 && input[row + 1][column] != '#' {
-        adjacent.push((row + 1, column));
+
+    /* counts[d] is the number of plots first reached after exactly d steps, for d up to max_steps. On the infinite
+     * map each copy of the garden that is reached gets its own table of distances, keyed by how many copies up and
+     * across from the original it is.
+     */
+    fn get_distance_counts(&self, max_steps: usize, infinite: bool) -> Vec<u64> {
+        let height = self.rocks.height() as i64;
+        let width = self.rocks.width() as i64;
+        let mut tiles: HashMap<(i64, i64), Vec<u32>> = HashMap::new();
+        let mut counts = vec![0; max_steps + 1];
+        let mut queue = VecDeque::new();
+
+        let start = (self.start.0 as i64, self.start.1 as i64);
+        tiles.insert((0, 0), vec![u32::MAX; (height * width) as usize]);
+        tiles.get_mut(&(0, 0)).unwrap()[self.start.0 * width as usize + self.start.1] = 0;
+        queue.push_back((start, 0));
+
+        while let Some(((row, column), distance)) = queue.pop_front() {
+            counts[distance] += 1;
+            if distance == max_steps {
+                continue;
+            }
+
+            for (row_step, column_step) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let (next_row, next_column) = (row + row_step, column + column_step);
+                let tile = (next_row.div_euclid(height), next_column.div_euclid(width));
+                if !infinite && tile != (0, 0) {
+                    continue;
+                }
+                let (tile_row, tile_column) = (
+                    next_row.rem_euclid(height) as usize,
+                    next_column.rem_euclid(width) as usize,
+                );
+                if *self.rocks.get(tile_row, tile_column).unwrap() {
+                    continue;
+                }
+
+                let distances = tiles
+                    .entry(tile)
+                    .or_insert_with(|| vec![u32::MAX; (height * width) as usize]);
+                let cell = &mut distances[tile_row * width as usize + tile_column];
+                if *cell == u32::MAX {
+                    *cell = distance as u32 + 1;
+                    queue.push_back(((next_row, next_column), distance + 1));
+                }
+            }
+        }
+
+        counts
     }
-    if column < input[0].len() - 1 && input[row][column + 1] != '#' {
-        adjacent.push((row, column + 1));
+
+    // Plots reachable in exactly `steps` steps without leaving the garden
+    fn reachable(&self, steps: usize) -> u64 {
+        count_reachable(&self.get_distance_counts(steps, false), steps)
     }
 
-    adjacent
-}
+    /* Plots reachable in exactly `steps` steps on the garden repeated infinitely in every direction. Simulating
+     * takes memory for every plot reached, so it is only done up to MAX_SIMULATED_STEPS.
+     */
+    fn reachable_infinite(&self, steps: usize) -> Result<u64, GardenError> {
+        if let Some(count) = self.extrapolate(steps) {
+            return Ok(count);
+        }
+        if steps > MAX_SIMULATED_STEPS {
+            return Err(GardenError::TooManySteps(steps));
+        }
 
-fn part_one(input: &Vec<Vec<char>>, start_location: (usize, usize)) -> usize {
-    let steps = 64;
-    let mut current_locations = HashSet::new();
-    current_locations.insert(start_location);
+        Ok(count_reachable(
+            &self.get_distance_counts(steps, true),
+            steps,
+        ))
+    }
 
-    for _i in 0..steps {
-        let locations = current_locations.clone();
-        current_locations = HashSet::new();
+    /* Once the reachable area is a few copies of the garden across, it grows by the same whole copies every time the
+     * number of steps goes up by the size of the garden, so f(k) = reachable(k * size + remainder) is a quadratic in
+     * k. That needs the garden to be square, so that the area spreads at the same rate in every direction, and how
+     * soon the quadratic takes over depends on the layout of the rocks. So f is worked out directly for the first few
+     * values of k, and the quadratic is only used if the values after some point all fit it. It can also take two
+     * sizes worth of steps to repeat when the garden's size is odd, so that period is tried too. None means the steps
+     * should be simulated directly instead.
+     */
+    fn extrapolate(&self, steps: usize) -> Option<u64> {
+        let size = self.rocks.width();
+        if size != self.rocks.height() || size == 0 {
+            return None;
+        }
 
-        for location in locations {
-            let adjacent = get_adjacent_locations(location, &input);
-            for adjacent_location in adjacent {
-                current_locations.insert(adjacent_location);
+        for period in [size, 2 * size] {
+            let (periods, remainder) = (steps / period, steps % period);
+            if periods <= LOOKAHEAD {
+                return None;
             }
+
+            let counts = self.get_distance_counts(LOOKAHEAD * period + remainder, true);
+            let values: Vec<i128> = (0..=LOOKAHEAD)
+                .map(|k| count_reachable(&counts, k * period + remainder) as i128)
+                .collect();
+            let third_differences: Vec<i128> = values
+                .windows(4)
+                .map(|v| v[3] - 3 * v[2] + 3 * v[1] - v[0])
+                .collect();
+            let Some(first) = (0..=third_differences.len() - CONFIRMATIONS)
+                .find(|&k| third_differences[k..].iter().all(|&d| d == 0))
+            else {
+                continue;
+            };
+
+            // Newton's forward difference formula from f(first), with t = periods - first
+            let t = (periods - first) as i128;
+            let first_difference = values[first + 1] - values[first];
+            let second_difference = values[first + 2] - 2 * values[first + 1] + values[first];
+            let value = t
+                .checked_mul(first_difference)?
+                .checked_add(values[first])?
+                .checked_add((t.checked_mul(t - 1)? / 2).checked_mul(second_difference)?)?;
+            return u64::try_from(value).ok();
         }
-    }
 
-    current_locations.len()
+        None
+    }
 }
 
-// I was stumped on part 2. Thanks to HyperNeutrino for the explanation https://youtu.be/9UOMZSL0JTg?si=DBJz-S_An1uKAtY1
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
 
-fn fill(start_location: (usize, usize), steps: usize, input: &Vec<Vec<char>>) -> usize {
-    let mut answers = HashSet::new();
-    let mut seen = HashSet::new();
-    seen.insert(start_location);
-    let mut queue = VecDeque::new();
-    queue.push_back((start_location.0, start_location.1, steps));
+    use super::*;
 
-    while !queue.is_empty() {
-        let (row, column, steps) = queue.pop_front().unwrap();
+    fn get_test_garden() -> Garden {
+        Garden::parse(&get_two_dimensional_vector("resources/test_input.txt"))
+    }
 
-        if steps % 2 == 0 {
-            answers.insert((row, column));
+    // Steps every position forward one step at a time, the way part 1 used to be solved
+    fn get_brute_force(garden: &Garden, steps: usize, infinite: bool) -> usize {
+        let height = garden.rocks.height() as i64;
+        let width = garden.rocks.width() as i64;
+        let mut current = HashSet::from([(garden.start.0 as i64, garden.start.1 as i64)]);
+        for _ in 0..steps {
+            current = current
+                .iter()
+                .flat_map(|&(row, column)| {
+                    [
+                        (row - 1, column),
+                        (row + 1, column),
+                        (row, column - 1),
+                        (row, column + 1),
+                    ]
+                })
+                .filter(|&(row, column)| {
+                    (infinite || (0..height).contains(&row) && (0..width).contains(&column))
+                        && garden.rocks.get(
+                            row.rem_euclid(height) as usize,
+                            column.rem_euclid(width) as usize,
+                        ) != Some(&true)
+                })
+                .collect();
         }
+        current.len()
+    }
 
-        if steps == 0 {
-            continue;
-        }
+    fn parse(lines: &[&str]) -> Garden {
+        let input: Vec<Vec<char>> = lines.iter().map(|line| line.chars().collect()).collect();
+        Garden::parse(&input)
+    }
 
-        'inner: for (next_row, next_column) in get_adjacent_locations((row, column), input) {
-            if seen.contains(&(next_row, next_column)) {
-                continue 'inner;
-            }
+    #[test]
+    fn test_reachable() {
+        let garden = get_test_garden();
+
+        assert_eq!(garden.reachable(6), 16);
+        assert_eq!(
+            garden.reachable(64),
+            get_brute_force(&garden, 64, false) as u64
+        );
+    }
+
+    #[test]
+    fn test_reachable_infinite() {
+        let garden = get_test_garden();
 
-            seen.insert((next_row, next_column));
-            queue.push_back((next_row, next_column, steps - 1));
+        assert_eq!(garden.reachable_infinite(6), Ok(16));
+        assert_eq!(garden.reachable_infinite(10), Ok(50));
+        assert_eq!(garden.reachable_infinite(50), Ok(1594));
+        assert_eq!(garden.reachable_infinite(100), Ok(6536));
+        assert_eq!(garden.reachable_infinite(500), Ok(167004));
+    }
+
+    #[test]
+    fn test_extrapolate_matches_simulation() {
+        let garden = get_test_garden();
+        for steps in [300, 301, 512, 1000] {
+            let simulated = count_reachable(&garden.get_distance_counts(steps, true), steps);
+
+            assert_eq!(garden.extrapolate(steps), Some(simulated));
         }
+        assert_eq!(garden.extrapolate(1000), Some(668697));
     }
 
-    answers.len()
-}
+    #[test]
+    fn test_non_square_garden() {
+        let garden = parse(&[".....", "..#S.", "....."]);
 
-fn part_two(input: &Vec<Vec<char>>, start_location: (usize, usize)) -> usize {
-    let size = input.len();
-    let steps = 26501365;
-    let grid_width = (steps / size) - 1;
-
-    let odd_grids = ((grid_width / 2) * 2 + 1).pow(2);
-    let even_grids = (((grid_width + 1) / 2) * 2).pow(2);
-
-    let top_corner = fill((size - 1, start_location.1), size - 1, input);
-    let right_corner = fill((start_location.0, 0), size - 1, input);
-    let bottom_corner = fill((0, start_location.1), size - 1, input);
-    let left_corner = fill((start_location.0, size - 1), size - 1, input);
-
-    let floor_divide = (size / 2) - 1;
-    let small_top_right = fill((size - 1, 0), floor_divide, input);
-    let small_bottom_right = fill((0, 0), floor_divide, input);
-    let small_top_left = fill((size - 1, size - 1), floor_divide, input);
-    let small_bottom_left = fill((0, size - 1), floor_divide, input);
-
-    let floor_divide_big = ((size * 3) / 2) - 1;
-    let big_top_right = fill((size - 1, 0), floor_divide_big, input);
-    let big_bottom_right = fill((0, 0), floor_divide_big, input);
-    let big_top_left = fill((size - 1, size - 1), floor_divide_big, input);
-    let big_bottom_left = fill((0, size - 1), floor_divide_big, input);
-
-    let odd_points = fill(start_location, size * 2 + 1, input);
-    let even_points = fill(start_location, size * 2, input);
-
-    odd_grids * odd_points
-        + even_grids * even_points
-        + top_corner
-        + right_corner
-        + bottom_corner
-        + left_corner
-        + (grid_width + 1)
-            * (small_top_right + small_top_left + small_bottom_right + small_bottom_left)
-        + grid_width * (big_bottom_left + big_bottom_right + big_top_left + big_top_right)
+        assert_eq!(garden.extrapolate(1000), None);
+        for steps in [0, 1, 7, 20] {
+            assert_eq!(
+                garden.reachable_infinite(steps),
+                Ok(get_brute_force(&garden, steps, true) as u64)
+            );
+            assert_eq!(
+                garden.reachable(steps),
+                get_brute_force(&garden, steps, false) as u64
+            );
+        }
+        assert_eq!(
+            garden.reachable_infinite(INFINITE_STEPS),
+            Err(GardenError::TooManySteps(INFINITE_STEPS))
+        );
+        assert_eq!(
+            garden.reachable_infinite(MAX_SIMULATED_STEPS + 1),
+            Err(GardenError::TooManySteps(MAX_SIMULATED_STEPS + 1))
+        );
+    }
 }
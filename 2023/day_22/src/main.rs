@@ -1,171 +1,461 @@
-use std::collections::HashSet;
-
+use library::grid::Grid;
 use library::{get_filename_arg, get_lines};
-use priority_queue::PriorityQueue;
 
 fn main() {
     let file_name = get_filename_arg();
     let lines = get_lines(&file_name);
-    let mut bricks = vec![];
-
-    for line in lines {
-        let parts: Vec<_> = line.split("~").collect();
-        let (a, b, c) = get_graph_coordinates(parts[0]);
-        let (x, y, z) = get_graph_coordinates(parts[1]);
-        bricks.push(Brick {
-            x_range: (a, x),
-            y_range: (b, y),
-            z_range: (c, z),
-            supporting_indices: vec![],
-            supported_by_indices: vec![],
-        });
-    }
-    bricks.sort_by_key(|brick| brick.z_range.0);
-    bricks = drop_bricks(bricks);
-
-    println!("Part 1: {}", part_one(&bricks));
-    println!("Part 2: {}", part_two(&bricks));
-}
+    let bricks = lines.iter().map(|line| Brick::parse(line)).collect();
+    let stack = Stack::settle(bricks);
 
-fn part_one(bricks: &Vec<Brick>) -> usize {
-    let mut sum = 0;
+    println!("Part 1: {}", part_one(&stack));
+    println!("Part 2: {}", part_two(&stack));
+}
 
-    'bricks: for i in 0..bricks.len() {
-        for &index in bricks[i].supporting_indices.iter() {
-            if bricks[index].supported_by_indices.len() == 1 {
-                continue 'bricks;
-            }
-        }
-        sum += 1;
-    }
+fn part_one(stack: &Stack) -> usize {
+    (0..stack.len())
+        .filter(|&brick| stack.can_disintegrate(brick))
+        .count()
+}
 
-    sum
+fn part_two(stack: &Stack) -> usize {
+    (0..stack.len())
+        .map(|brick| stack.chain_reaction(brick))
+        .sum()
 }
 
-fn part_two(bricks: &Vec<Brick>) -> usize {
-    let mut sum = 0;
-    for i in (0..bricks.len()).rev() {
-        let mut queue = PriorityQueue::new();
-        queue.push(i, bricks[i].z_range.1);
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Brick {
+    start: [usize; 3],
+    end: [usize; 3],
+}
 
-        let mut fallen = HashSet::new();
-        while let Some((index, _)) = queue.pop() {
-            fallen.insert(index);
+impl Brick {
+    fn parse(line: &str) -> Self {
+        let (start, end) = line.split_once('~').expect("Invalid brick");
+        let (start, end) = (parse_coordinates(start), parse_coordinates(end));
 
-            'supporting: for supporting_index in &bricks[index].supporting_indices {
-                for supported_by_index in &bricks[*supporting_index].supported_by_indices {
-                    if !fallen.contains(supported_by_index) {
-                        continue 'supporting;
-                    }
-                }
-                queue.push(*supporting_index, bricks[*supporting_index].z_range.1);
-                sum += 1;
-            }
+        Self {
+            start: [0, 1, 2].map(|i| start[i].min(end[i])),
+            end: [0, 1, 2].map(|i| start[i].max(end[i])),
         }
     }
-    sum
+
+    fn footprint(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.start[0]..=self.end[0])
+            .flat_map(move |x| (self.start[1]..=self.end[1]).map(move |y| (x, y)))
+    }
+
+    fn moved_to(&self, bottom: usize) -> Self {
+        let mut brick = *self;
+        brick.end[2] = bottom + self.end[2] - self.start[2];
+        brick.start[2] = bottom;
+        brick
+    }
 }
 
-fn drop_bricks(bricks: Vec<Brick>) -> Vec<Brick> {
-    let mut fallen_bricks: Vec<Brick> = vec![];
+fn parse_coordinates(coordinates: &str) -> [usize; 3] {
+    let values: Vec<usize> = coordinates
+        .split(',')
+        .map(|value| value.parse().expect("Failed to parse coordinate"))
+        .collect();
+    [values[0], values[1], values[2]]
+}
 
-    for mut brick in bricks {
-        loop {
-            let mut can_drop = true;
+/* Settled bricks plus everything needed to answer questions about them. Bricks keep the index they were given when
+* sorted by their starting height, so every brick's supporters have a smaller index than it does.
+*/
+struct Stack {
+    bricks: Vec<Brick>,
+    supporters: Vec<Vec<usize>>,
+    supporting: Vec<Vec<usize>>,
+    // How many bricks have each brick as a dominator in the support graph, directly or not
+    dominated_counts: Vec<usize>,
+}
 
-            for i in (0..fallen_bricks.len()).rev() {
-                if fallen_bricks[i].supports(&brick) {
-                    can_drop = false;
-                    let length = fallen_bricks.len();
-                    fallen_bricks[i].supporting_indices.push(length);
-                    brick.supported_by_indices.push(i);
+impl Stack {
+    /* Drops the bricks lowest first onto a height map of the tallest settled brick over each x/y column, so each brick
+     * only looks at the columns under its own footprint instead of every brick that has already fallen.
+     */
+    fn settle(mut bricks: Vec<Brick>) -> Self {
+        bricks.sort_by_key(|brick| brick.start[2]);
+        let width = bricks
+            .iter()
+            .map(|brick| brick.end[0] + 1)
+            .max()
+            .unwrap_or(0);
+        let depth = bricks
+            .iter()
+            .map(|brick| brick.end[1] + 1)
+            .max()
+            .unwrap_or(0);
+        let mut height_map: Grid<(usize, Option<usize>)> = Grid::new(width, depth, (0, None));
+
+        let mut settled = Vec::with_capacity(bricks.len());
+        let mut supporters = Vec::with_capacity(bricks.len());
+        for (index, brick) in bricks.iter().enumerate() {
+            let mut floor = 0;
+            let mut below = vec![];
+            for (x, y) in brick.footprint() {
+                let &(top, owner) = height_map.get(y, x).unwrap();
+                if top > floor {
+                    floor = top;
+                    below.clear();
+                }
+                if let Some(owner) = owner.filter(|owner| top == floor && !below.contains(owner)) {
+                    below.push(owner);
                 }
             }
 
-            if !can_drop || brick.is_hitting_bottom() {
-                break;
+            let brick = brick.moved_to(floor + 1);
+            for (x, y) in brick.footprint() {
+                height_map.set(y, x, (brick.end[2], Some(index)));
+            }
+            settled.push(brick);
+            supporters.push(below);
+        }
+
+        let mut supporting = vec![vec![]; settled.len()];
+        for (index, below) in supporters.iter().enumerate() {
+            for &supporter in below {
+                supporting[supporter].push(index);
+            }
+        }
+
+        let dominators = get_dominators(&supporters);
+        let mut dominated_counts = vec![0; settled.len()];
+        for index in (0..settled.len()).rev() {
+            if let Some(dominator) = dominators[index] {
+                dominated_counts[dominator] += dominated_counts[index] + 1;
             }
+        }
 
-            brick.drop();
+        Self {
+            bricks: settled,
+            supporters,
+            supporting,
+            dominated_counts,
         }
+    }
 
-        fallen_bricks.push(brick);
+    fn len(&self) -> usize {
+        self.bricks.len()
     }
 
-    fallen_bricks
+    // The bricks `index` rests on directly
+    fn supporters(&self, index: usize) -> &[usize] {
+        &self.supporters[index]
+    }
+
+    // The bricks resting directly on `index`
+    fn supporting(&self, index: usize) -> &[usize] {
+        &self.supporting[index]
+    }
+
+    fn can_disintegrate(&self, index: usize) -> bool {
+        self.supporting(index)
+            .iter()
+            .all(|&above| self.supporters(above).len() > 1)
+    }
+
+    // A brick falls without `index` exactly when every path from it down to the ground goes through `index`
+    fn chain_reaction(&self, index: usize) -> usize {
+        self.dominated_counts[index]
+    }
 }
 
-fn get_graph_coordinates(coordinates: &str) -> (i32, i32, i32) {
-    let coordinate_strings: Vec<_> = coordinates.split(",").collect();
-    (
-        coordinate_strings[0].parse().unwrap(),
-        coordinate_strings[1].parse().unwrap(),
-        coordinate_strings[2].parse().unwrap(),
-    )
+/* The immediate dominator of each brick in the support graph, where None is the ground. Bricks are already in
+* topological order, so each immediate dominator is just the lowest common ancestor in the dominator tree of all of
+* that brick's supporters.
+*/
+fn get_dominators(supporters: &[Vec<usize>]) -> Vec<Option<usize>> {
+    let mut dominators: Vec<Option<usize>> = Vec::with_capacity(supporters.len());
+    let mut depths: Vec<usize> = Vec::with_capacity(supporters.len());
+
+    for below in supporters {
+        let dominator = match below.split_first() {
+            None => None,
+            Some((&first, rest)) => rest.iter().try_fold(first, |dominator, &supporter| {
+                common_dominator(&dominators, &depths, dominator, supporter)
+            }),
+        };
+
+        depths.push(dominator.map_or(1, |dominator| depths[dominator] + 1));
+        dominators.push(dominator);
+    }
+
+    dominators
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-struct Brick {
-    x_range: (i32, i32),
-    y_range: (i32, i32),
-    z_range: (i32, i32),
-    supporting_indices: Vec<usize>,
-    supported_by_indices: Vec<usize>,
+fn common_dominator(
+    dominators: &[Option<usize>],
+    depths: &[usize],
+    mut a: usize,
+    mut b: usize,
+) -> Option<usize> {
+    while a != b {
+        if depths[a] < depths[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        a = dominators[a]?;
+    }
+    Some(a)
 }
 
-impl Brick {
-    // intersects based on https://stackoverflow.com/a/30160064
-    fn intersects(&self, other: &Brick) -> bool {
-        let (x1, x2) = self.x_range;
-        let (y1, y2) = self.y_range;
-        let (x3, x4) = other.x_range;
-        let (y3, y4) = other.y_range;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let f1 = rotation_direction(x1, y1, x2, y2, x4, y4);
-        let f2 = rotation_direction(x1, y1, x2, y2, x3, y3);
-        let f3 = rotation_direction(x1, y1, x3, y3, x4, y4);
-        let f4 = rotation_direction(x2, y2, x3, y3, x4, y4);
+    fn get_test_stack() -> Stack {
+        let lines = get_lines("resources/test_input.txt");
+        Stack::settle(lines.iter().map(|line| Brick::parse(line)).collect())
+    }
 
-        if f1 != f2 && f3 != f4 {
-            return true;
-        }
+    fn get_cells(brick: &Brick) -> Vec<(usize, usize, usize)> {
+        brick
+            .footprint()
+            .flat_map(|(x, y)| (brick.start[2]..=brick.end[2]).map(move |z| (x, y, z)))
+            .collect()
+    }
+
+    // Simple linear congruential generator so the random stacks are the same on every run
+    fn get_random_bricks(seed: u64, count: usize) -> Vec<String> {
+        let mut state = seed;
+        let mut next = |limit: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 33) % limit) as usize
+        };
 
-        f1 == 0
-            && f2 == 0
-            && f3 == 0
-            && f4 == 0
-            && (contains_segment(x1, y1, x2, y2, x3, y3)
-                || contains_segment(x1, y1, x2, y2, x4, y4)
-                || contains_segment(x3, y3, x4, y4, x1, y1)
-                || contains_segment(x3, y3, x4, y4, x2, y2))
+        (0..count)
+            .map(|_| {
+                let (x, y, z) = (next(4), next(4), next(40) + 1);
+                let length = next(3);
+                let (end_x, end_y, end_z) = match next(3) {
+                    0 => ((x + length).min(3), y, z),
+                    1 => (x, (y + length).min(3), z),
+                    _ => (x, y, z + length),
+                };
+                format!("{},{},{}~{},{},{}", x, y, z, end_x, end_y, end_z)
+            })
+            .filter({
+                let mut used = std::collections::HashSet::new();
+                move |line: &String| {
+                    let cells = get_cells(&Brick::parse(line));
+                    let free = cells.iter().all(|cell| !used.contains(cell));
+                    used.extend(cells);
+                    free
+                }
+            })
+            .collect()
     }
-    fn supports(&self, other: &Brick) -> bool {
-        other.z_range.0 == self.z_range.1 + 1 && self.intersects(other)
+
+    #[test]
+    fn test_example() {
+        let stack = get_test_stack();
+
+        assert_eq!(part_one(&stack), 5);
+        assert_eq!(part_two(&stack), 7);
+        assert_eq!(stack.chain_reaction(0), 6);
+        assert_eq!(stack.chain_reaction(5), 1);
+        assert_eq!(stack.supporters(3), [1, 2]);
+        assert_eq!(stack.supporting(0), [1, 2]);
+        assert_eq!(get_dominators(&stack.supporters)[3], Some(0));
     }
-    fn is_hitting_bottom(&self) -> bool {
-        self.z_range.0 == 1
+
+    #[test]
+    fn test_settled_positions() {
+        let stack = get_test_stack();
+
+        assert_eq!(stack.bricks[6].start, [1, 1, 5]);
+        assert_eq!(stack.bricks[6].end, [1, 1, 6]);
+        assert_eq!(stack.bricks[1].start, [0, 0, 2]);
+        // No two settled bricks share a cell
+        let mut cells: Vec<_> = stack.bricks.iter().flat_map(get_cells).collect();
+        let count = cells.len();
+        cells.sort();
+        cells.dedup();
+        assert_eq!(cells.len(), count);
     }
-    fn drop(&mut self) {
-        self.z_range.0 -= 1;
-        self.z_range.1 -= 1;
+
+    #[test]
+    fn test_matches_reference() {
+        let mut inputs = vec![get_lines("resources/test_input.txt")];
+        inputs.extend((0..20).map(|seed| get_random_bricks(seed, 60)));
+
+        for lines in inputs {
+            let stack = Stack::settle(lines.iter().map(|line| Brick::parse(line)).collect());
+            let reference_bricks = reference::parse_and_drop(&lines);
+
+            for index in 0..stack.len() {
+                let sorted = |indices: &[usize]| {
+                    let mut indices = indices.to_vec();
+                    indices.sort();
+                    indices
+                };
+                assert_eq!(
+                    sorted(stack.supporters(index)),
+                    sorted(&reference_bricks[index].supported_by_indices)
+                );
+                assert_eq!(
+                    sorted(stack.supporting(index)),
+                    sorted(&reference_bricks[index].supporting_indices)
+                );
+                assert_eq!(
+                    stack.chain_reaction(index),
+                    reference::chain_reaction(&reference_bricks, index)
+                );
+            }
+            assert_eq!(part_one(&stack), reference::part_one(&reference_bricks));
+        }
     }
-}
 
-// intersects based on https://stackoverflow.com/a/30160064
+    // The original quadratic implementation, kept to check the faster one against
+    mod reference {
 
-fn contains_segment(x1: i32, y1: i32, x2: i32, y2: i32, sx: i32, sy: i32) -> bool {
-    (x1 < x2 && x1 < sx && sx < x2)
-        || (x2 < x1 && x2 < sx && sx < x1)
-        || (y1 < y2 && y1 < sy && sy < y2)
-        || (y2 < y1 && y2 < sy && sy < y1)
-        || (x1 == sx && y1 == sy || x2 == sx && y2 == sy)
-}
+        pub fn parse_and_drop(lines: &[String]) -> Vec<Brick> {
+            let mut bricks = vec![];
+            for line in lines {
+                let parts: Vec<_> = line.split('~').collect();
+                let (a, b, c) = get_graph_coordinates(parts[0]);
+                let (x, y, z) = get_graph_coordinates(parts[1]);
+                bricks.push(Brick {
+                    x_range: (a, x),
+                    y_range: (b, y),
+                    z_range: (c, z),
+                    supporting_indices: vec![],
+                    supported_by_indices: vec![],
+                });
+            }
+            bricks.sort_by_key(|brick| brick.z_range.0);
+            drop_bricks(bricks)
+        }
+
+        pub fn part_one(bricks: &[Brick]) -> usize {
+            let mut sum = 0;
+
+            'bricks: for brick in bricks {
+                for &index in brick.supporting_indices.iter() {
+                    if bricks[index].supported_by_indices.len() == 1 {
+                        continue 'bricks;
+                    }
+                }
+                sum += 1;
+            }
+
+            sum
+        }
+
+        // Knocks out brick i and checks every brick above it in order, since supporters always come first
+        pub fn chain_reaction(bricks: &[Brick], i: usize) -> usize {
+            let mut fallen = vec![false; bricks.len()];
+            fallen[i] = true;
+            for index in i + 1..bricks.len() {
+                let below = &bricks[index].supported_by_indices;
+                fallen[index] = !below.is_empty() && below.iter().all(|&below| fallen[below]);
+            }
+            fallen.iter().filter(|&&fallen| fallen).count() - 1
+        }
+
+        fn drop_bricks(bricks: Vec<Brick>) -> Vec<Brick> {
+            let mut fallen_bricks: Vec<Brick> = vec![];
+
+            for mut brick in bricks {
+                loop {
+                    let mut can_drop = true;
+
+                    for i in (0..fallen_bricks.len()).rev() {
+                        if fallen_bricks[i].supports(&brick) {
+                            can_drop = false;
+                            let length = fallen_bricks.len();
+                            fallen_bricks[i].supporting_indices.push(length);
+                            brick.supported_by_indices.push(i);
+                        }
+                    }
 
-fn rotation_direction(x1: i32, y1: i32, x2: i32, y2: i32, x3: i32, y3: i32) -> i32 {
-    if ((y3 - y1) * (x2 - x1)) > ((y2 - y1) * (x3 - x1)) {
-        return 1;
-    } else if ((y3 - y1) * (x2 - x1)) == ((y2 - y1) * (x3 - x1)) {
-        return 0;
+                    if !can_drop || brick.is_hitting_bottom() {
+                        break;
+                    }
+
+                    brick.drop();
+                }
+
+                fallen_bricks.push(brick);
+            }
+
+            fallen_bricks
+        }
+
+        fn get_graph_coordinates(coordinates: &str) -> (i32, i32, i32) {
+            let coordinate_strings: Vec<_> = coordinates.split(',').collect();
+            (
+                coordinate_strings[0].parse().unwrap(),
+                coordinate_strings[1].parse().unwrap(),
+                coordinate_strings[2].parse().unwrap(),
+            )
+        }
+
+        pub struct Brick {
+            x_range: (i32, i32),
+            y_range: (i32, i32),
+            z_range: (i32, i32),
+            pub supporting_indices: Vec<usize>,
+            pub supported_by_indices: Vec<usize>,
+        }
+
+        impl Brick {
+            // intersects based on https://stackoverflow.com/a/30160064
+            fn intersects(&self, other: &Brick) -> bool {
+                let (x1, x2) = self.x_range;
+                let (y1, y2) = self.y_range;
+                let (x3, x4) = other.x_range;
+                let (y3, y4) = other.y_range;
+
+                let f1 = rotation_direction(x1, y1, x2, y2, x4, y4);
+                let f2 = rotation_direction(x1, y1, x2, y2, x3, y3);
+                let f3 = rotation_direction(x1, y1, x3, y3, x4, y4);
+                let f4 = rotation_direction(x2, y2, x3, y3, x4, y4);
+
+                if f1 != f2 && f3 != f4 {
+                    return true;
+                }
+
+                f1 == 0
+                    && f2 == 0
+                    && f3 == 0
+                    && f4 == 0
+                    && (contains_segment(x1, y1, x2, y2, x3, y3)
+                        || contains_segment(x1, y1, x2, y2, x4, y4)
+                        || contains_segment(x3, y3, x4, y4, x1, y1)
+                        || contains_segment(x3, y3, x4, y4, x2, y2))
+            }
+            fn supports(&self, other: &Brick) -> bool {
+                other.z_range.0 == self.z_range.1 + 1 && self.intersects(other)
+            }
+            fn is_hitting_bottom(&self) -> bool {
+                self.z_range.0 == 1
+            }
+            fn drop(&mut self) {
+                self.z_range.0 -= 1;
+                self.z_range.1 -= 1;
+            }
+        }
+
+        fn contains_segment(x1: i32, y1: i32, x2: i32, y2: i32, sx: i32, sy: i32) -> bool {
+            (x1 < x2 && x1 < sx && sx < x2)
+                || (x2 < x1 && x2 < sx && sx < x1)
+                || (y1 < y2 && y1 < sy && sy < y2)
+                || (y2 < y1 && y2 < sy && sy < y1)
+                || (x1 == sx && y1 == sy || x2 == sx && y2 == sy)
+        }
+
+        fn rotation_direction(x1: i32, y1: i32, x2: i32, y2: i32, x3: i32, y3: i32) -> i32 {
+            if ((y3 - y1) * (x2 - x1)) > ((y2 - y1) * (x3 - x1)) {
+                return 1;
+            } else if ((y3 - y1) * (x2 - x1)) == ((y2 - y1) * (x3 - x1)) {
+                return 0;
+            }
+            -1
+        }
     }
-    -1
 }
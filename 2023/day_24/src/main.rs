@@ -1,35 +1,34 @@
 use library::{get_filename_arg, get_lines};
-use z3::ast::{Ast, Int};
-use z3::{Config, Context, Solver};
 
-static MIN: f32 = 200000000000000.;
-static MAX: f32 = 400000000000000.;
+const MIN: i128 = 200000000000000;
+const MAX: i128 = 400000000000000;
 
 fn main() {
     let file_name = get_filename_arg();
     let lines = get_lines(&file_name);
-    let mut part_one_stones = vec![];
-    let mut part_two_stones = vec![];
-    for line in lines {
-        let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
-        let parts: Vec<_> = line.split("@").collect();
-        let coords: Vec<_> = parts[0].split(",").collect();
-        let velocities: Vec<_> = parts[1].split(",").collect();
-        part_one_stones.push(PartOneStone::new(&coords, &velocities));
-        part_two_stones.push(PartTwoStone::new(&coords, &velocities));
-    }
+    let stones = parse_stones(&lines);
+
+    println!("Part 1: {}", part_one(&stones, MIN, MAX));
+    println!(
+        "Part 2: {}",
+        part_two(&stones).expect("No single throw hits every hailstone")
+    );
+
+    #[cfg(feature = "z3")]
+    println!("Part 2 (z3): {}", z3_solver::part_two(&stones));
+}
 
-    println!("Part 1: {}", part_one(&part_one_stones));
-    println!("Part 2: {}", part_two(&part_two_stones));
+fn parse_stones(lines: &[String]) -> Vec<Stone> {
+    lines.iter().map(|line| Stone::new(line)).collect()
 }
 
 /* Refactored based on HyperNeutrino's solution https://youtu.be/guOyA7Ijqgk?si=yqK6OEwMjJ7mXTrT */
-fn part_one(stones: &Vec<PartOneStone>) -> u32 {
+fn part_one(stones: &[Stone], min: i128, max: i128) -> u32 {
     let mut count = 0;
 
-    for i in 0..stones.len() - 1 {
-        for j in i..stones.len() {
-            if stones[i].will_collide(&stones[j]) {
+    for i in 0..stones.len() {
+        for j in i + 1..stones.len() {
+            if stones[i].will_collide(&stones[j], min, max) {
                 count += 1;
             }
         }
@@ -38,111 +37,250 @@ fn part_one(stones: &Vec<PartOneStone>) -> u32 {
     count
 }
 
-/* No idea how to solve this without using some equation solving library, so based this on arthomnix's solution using z3.
-* If you on this repo on Github just now that the devcontainer config will not work for part 2 as is because you need clang
-* installed for z3 to build.
+fn part_two(stones: &[Stone]) -> Option<i64> {
+    let (position, _) = find_throw(stones)?;
+    Some(position.iter().sum::<i128>() as i64)
+}
+
+/* Works in the reference frame of the first stone, where it sits still at the origin. The rock has to pass through the
+* origin, so it lies in the plane spanned by the origin and any other stone's path. Intersecting a third stone's path with
+* that plane gives the time and place the rock hits it, and two such hits pin down the whole trajectory. Every product
+* stays well inside i128 for inputs on the puzzle's scale, so no equation solver is needed.
 */
-fn part_two(stones: &Vec<PartTwoStone>) -> i64 {
-    let cfg = Config::new();
-    let ctx = Context::new(&cfg);
-    let solver = Solver::new(&ctx);
-
-    let x = Int::new_const(&ctx, "x");
-    let y = Int::new_const(&ctx, "y");
-    let z = Int::new_const(&ctx, "z");
-    let velocity_x = Int::new_const(&ctx, "velocity_x");
-    let velocity_y = Int::new_const(&ctx, "velocity_y");
-    let velocity_z = Int::new_const(&ctx, "velocity_z");
-
-    for stone in stones {
-        let stone_x = Int::from_i64(&ctx, stone.x);
-        let stone_y = Int::from_i64(&ctx, stone.y);
-        let stone_z = Int::from_i64(&ctx, stone.z);
-        let stone_velocity_x = Int::from_i64(&ctx, stone.velocity_x);
-        let stone_velocity_y = Int::from_i64(&ctx, stone.velocity_y);
-        let stone_velocity_z = Int::from_i64(&ctx, stone.velocity_z);
-        let t = Int::fresh_const(&ctx, "t");
-
-        solver.assert(&(&stone_x + &stone_velocity_x * &t)._eq(&(&x + &velocity_x * &t)));
-        solver.assert(&(&stone_y + &stone_velocity_y * &t)._eq(&(&y + &velocity_y * &t)));
-        solver.assert(&(&stone_z + &stone_velocity_z * &t)._eq(&(&z + &velocity_z * &t)));
+fn find_throw(stones: &[Stone]) -> Option<([i128; 3], [i128; 3])> {
+    let origin = stones.first()?;
+    let relative: Vec<([i128; 3], [i128; 3])> = stones[1..]
+        .iter()
+        .map(|stone| {
+            (
+                subtract(stone.position, origin.position),
+                subtract(stone.velocity, origin.velocity),
+            )
+        })
+        .collect();
+
+    for i in 0..relative.len() {
+        for j in i + 1..relative.len() {
+            let Some(time_i) = hit_time(relative[j], relative[i]) else {
+                continue;
+            };
+            let Some(time_j) = hit_time(relative[i], relative[j]) else {
+                continue;
+            };
+            if time_i == time_j {
+                continue;
+            }
+
+            let hit_i = add(relative[i].0, scale(relative[i].1, time_i));
+            let hit_j = add(relative[j].0, scale(relative[j].1, time_j));
+            let Some(velocity) = divide_exact(subtract(hit_j, hit_i), time_j - time_i) else {
+                continue;
+            };
+            let position = subtract(hit_i, scale(velocity, time_i));
+
+            let position = add(position, origin.position);
+            let velocity = add(velocity, origin.velocity);
+            if stones
+                .iter()
+                .all(|stone| stone.is_hit_by(position, velocity))
+            {
+                return Some((position, velocity));
+            }
+        }
     }
 
-    solver.check();
+    None
+}
 
-    let model = solver.get_model().unwrap();
-    let x = model.get_const_interp(&x).unwrap().as_i64().unwrap();
-    let y = model.get_const_interp(&y).unwrap().as_i64().unwrap();
-    let z = model.get_const_interp(&z).unwrap().as_i64().unwrap();
+// Time at which `stone` crosses the plane through the origin containing `plane_stone`'s path, if it is a whole number
+fn hit_time(plane_stone: ([i128; 3], [i128; 3]), stone: ([i128; 3], [i128; 3])) -> Option<i128> {
+    let normal = cross(plane_stone.0, plane_stone.1);
+    let denominator = dot(stone.1, normal);
+    if normal == [0, 0, 0] || denominator == 0 {
+        return None;
+    }
 
-    x + y + z
+    let numerator = -dot(stone.0, normal);
+    if numerator % denominator != 0 {
+        return None;
+    }
+
+    Some(numerator / denominator)
+}
+
+fn add(a: [i128; 3], b: [i128; 3]) -> [i128; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn subtract(a: [i128; 3], b: [i128; 3]) -> [i128; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [i128; 3], factor: i128) -> [i128; 3] {
+    [a[0] * factor, a[1] * factor, a[2] * factor]
+}
+
+fn divide_exact(a: [i128; 3], divisor: i128) -> Option<[i128; 3]> {
+    if a.iter().any(|value| value % divisor != 0) {
+        return None;
+    }
+    Some([a[0] / divisor, a[1] / divisor, a[2] / divisor])
 }
 
-struct PartOneStone {
-    x: f32,
-    y: f32,
-    velocity_x: f32,
-    velocity_y: f32,
+fn dot(a: [i128; 3], b: [i128; 3]) -> i128 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
 }
 
-impl PartOneStone {
-    fn new(coords: &Vec<&str>, velocities: &Vec<&str>) -> Self {
+fn cross(a: [i128; 3], b: [i128; 3]) -> [i128; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+struct Stone {
+    position: [i128; 3],
+    velocity: [i128; 3],
+}
+
+impl Stone {
+    fn new(line: &str) -> Self {
+        let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+        let parts: Vec<_> = line.split('@').collect();
+
         Self {
-            x: coords[0].parse().unwrap(),
-            y: coords[1].parse().unwrap(),
-            velocity_x: velocities[0].parse().unwrap(),
-            velocity_y: velocities[1].parse().unwrap(),
+            position: parse_vector(parts[0]),
+            velocity: parse_vector(parts[1]),
         }
     }
 
-    fn will_collide(&self, other: &Self) -> bool {
-        let denominator = self.velocity_y * -other.velocity_x - other.velocity_y * -self.velocity_x;
+    // Intersects the two paths in the x/y plane, keeping everything scaled by the determinant so it stays exact
+    fn will_collide(&self, other: &Self, min: i128, max: i128) -> bool {
+        let mut determinant =
+            other.velocity[0] * self.velocity[1] - self.velocity[0] * other.velocity[1];
 
-        if denominator == 0. {
+        if determinant == 0 {
             return false;
         }
 
-        let c1 = self.velocity_y * self.x - self.velocity_x * self.y;
-        let c2 = other.velocity_y * other.x - other.velocity_x * other.y;
+        let dx = other.position[0] - self.position[0];
+        let dy = other.position[1] - self.position[1];
+        let mut self_time = other.velocity[0] * dy - other.velocity[1] * dx;
+        let mut other_time = self.velocity[0] * dy - self.velocity[1] * dx;
 
-        let x_intersect = (c1 * -other.velocity_x - c2 * -self.velocity_x) / denominator;
-        if x_intersect < MIN || x_intersect > MAX {
-            return false;
+        if determinant < 0 {
+            determinant = -determinant;
+            self_time = -self_time;
+            other_time = -other_time;
         }
 
-        let y_intersect = (c2 * self.velocity_y - c1 * other.velocity_y) / denominator;
-        if y_intersect < MIN || y_intersect > MAX {
+        if self_time < 0 || other_time < 0 {
             return false;
         }
 
-        self.point_is_in_future(x_intersect, y_intersect)
-            && other.point_is_in_future(x_intersect, y_intersect)
+        let x_intersect = self.position[0] * determinant + self_time * self.velocity[0];
+        let y_intersect = self.position[1] * determinant + self_time * self.velocity[1];
+        let range = min * determinant..=max * determinant;
+
+        range.contains(&x_intersect) && range.contains(&y_intersect)
     }
 
-    fn point_is_in_future(&self, x: f32, y: f32) -> bool {
-        ((x - self.x).is_sign_positive() == self.velocity_x.is_sign_positive())
-            && ((y - self.y).is_sign_positive() == self.velocity_y.is_sign_positive())
+    fn is_hit_by(&self, position: [i128; 3], velocity: [i128; 3]) -> bool {
+        cross(
+            subtract(position, self.position),
+            subtract(velocity, self.velocity),
+        ) == [0, 0, 0]
     }
 }
 
-struct PartTwoStone {
-    x: i64,
-    y: i64,
-    z: i64,
-    velocity_x: i64,
-    velocity_y: i64,
-    velocity_z: i64,
+fn parse_vector(input: &str) -> [i128; 3] {
+    let values: Vec<i128> = input
+        .split(',')
+        .map(|value| value.parse().expect("Failed to parse hailstone value"))
+        .collect();
+    [values[0], values[1], values[2]]
 }
 
-impl PartTwoStone {
-    fn new(coords: &Vec<&str>, velocities: &Vec<&str>) -> Self {
-        Self {
-            x: coords[0].parse().unwrap(),
-            y: coords[1].parse().unwrap(),
-            z: coords[2].parse().unwrap(),
-            velocity_x: velocities[0].parse().unwrap(),
-            velocity_y: velocities[1].parse().unwrap(),
-            velocity_z: velocities[2].parse().unwrap(),
+/* Original solution, kept behind the `z3` feature to cross check the answer. Based on arthomnix's solution using z3.
+* Note that you need clang installed for z3 to build, so the devcontainer config will not work for this as is.
+*/
+#[cfg(feature = "z3")]
+mod z3_solver {
+    use super::Stone;
+    use z3::ast::{Ast, Int};
+    use z3::{Config, Context, Solver};
+
+    pub fn part_two(stones: &[Stone]) -> i64 {
+        let cfg = Config::new();
+        let ctx = Context::new(&cfg);
+        let solver = Solver::new(&ctx);
+
+        let x = Int::new_const(&ctx, "x");
+        let y = Int::new_const(&ctx, "y");
+        let z = Int::new_const(&ctx, "z");
+        let velocity_x = Int::new_const(&ctx, "velocity_x");
+        let velocity_y = Int::new_const(&ctx, "velocity_y");
+        let velocity_z = Int::new_const(&ctx, "velocity_z");
+
+        for stone in stones {
+            let stone_x = Int::from_i64(&ctx, stone.position[0] as i64);
+            let stone_y = Int::from_i64(&ctx, stone.position[1] as i64);
+            let stone_z = Int::from_i64(&ctx, stone.position[2] as i64);
+            let stone_velocity_x = Int::from_i64(&ctx, stone.velocity[0] as i64);
+            let stone_velocity_y = Int::from_i64(&ctx, stone.velocity[1] as i64);
+            let stone_velocity_z = Int::from_i64(&ctx, stone.velocity[2] as i64);
+            let t = Int::fresh_const(&ctx, "t");
+
+            solver.assert(&(&stone_x + &stone_velocity_x * &t)._eq(&(&x + &velocity_x * &t)));
+            solver.assert(&(&stone_y + &stone_velocity_y * &t)._eq(&(&y + &velocity_y * &t)));
+            solver.assert(&(&stone_z + &stone_velocity_z * &t)._eq(&(&z + &velocity_z * &t)));
         }
+
+        solver.check();
+
+        let model = solver.get_model().unwrap();
+        let x = model.get_const_interp(&x).unwrap().as_i64().unwrap();
+        let y = model.get_const_interp(&y).unwrap().as_i64().unwrap();
+        let z = model.get_const_interp(&z).unwrap().as_i64().unwrap();
+
+        x + y + z
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_stones() -> Vec<Stone> {
+        parse_stones(&get_lines("resources/test_input.txt"))
+    }
+
+    #[test]
+    fn test_part_one() {
+        assert_eq!(part_one(&get_test_stones(), 7, 27), 2);
+    }
+
+    #[test]
+    fn test_will_collide_at_puzzle_scale() {
+        let a = Stone::new("200000000000000, 200000000000000, 0 @ 1, 1, 0");
+        let b = Stone::new("400000000000000, 200000000000000, 0 @ -1, 1, 0");
+        let c = Stone::new("400000000000001, 200000000000000, 0 @ -1, 1, 0");
+
+        assert!(a.will_collide(&b, MIN, MAX));
+        assert!(!a.will_collide(&c, MIN, 300000000000000));
+    }
+
+    #[test]
+    fn test_find_throw() {
+        let (position, velocity) = find_throw(&get_test_stones()).unwrap();
+
+        assert_eq!(position, [24, 13, 10]);
+        assert_eq!(velocity, [-3, 1, 2]);
+    }
+
+    #[test]
+    fn test_part_two() {
+        assert_eq!(part_two(&get_test_stones()), Some(47));
     }
 }
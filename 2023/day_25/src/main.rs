@@ -1,6 +1,8 @@
+use library::intern::Interner;
 use library::{get_filename_arg, get_lines};
 use rand::prelude::SliceRandom;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::cmp::Reverse;
+use std::collections::VecDeque;
 
 /* Based on comment by LtHummus
 * https://www.reddit.com/r/adventofcode/comments/18qbsxs/comment/keu45a6/?utm_source=share&utm_medium=web3x&utm_name=web3xcss&utm_term=1&utm_content=share_button */
@@ -8,84 +10,102 @@ fn main() {
     let file_name = get_filename_arg();
     let input = get_lines(&file_name);
 
-    let mut connections = get_connections(input);
+    let mut connections = get_connections(input, &mut Interner::new());
 
-    let mut counts = HashMap::new();
-    let nodes: Vec<String> = connections.keys().map(|a| a.clone()).collect();
+    let mut counts = vec![0; connections.len()];
+    let nodes: Vec<u32> = (0..connections.len() as u32).collect();
 
     for _ in 0..100 {
         let mut random_nodes = nodes.choose_multiple(&mut rand::thread_rng(), 2);
 
         let path = get_path(
             &connections,
-            &random_nodes.next().unwrap(),
-            &random_nodes.next().unwrap(),
+            *random_nodes.next().unwrap(),
+            *random_nodes.next().unwrap(),
         );
 
         for entry in path {
-            let count = counts.get(&entry).unwrap_or(&0);
-            counts.insert(entry, count + 1);
+            counts[entry as usize] += 1;
         }
     }
 
-    let mut counts: Vec<(&String, &i32)> = counts.iter().collect();
-    counts.sort_by(|a, b| b.1.cmp(a.1));
+    let mut counts: Vec<(u32, i32)> = nodes
+        .iter()
+        .map(|&node| (node, counts[node as usize]))
+        .collect();
+    counts.sort_by_key(|count| Reverse(count.1));
 
-    disconnect(&mut connections, &counts[0].0, &counts[1].0);
-    disconnect(&mut connections, &counts[2].0, &counts[3].0);
-    disconnect(&mut connections, &counts[4].0, &counts[5].0);
+    for pair in counts[..6].chunks(2) {
+        disconnect(&mut connections, pair[0].0, pair[1].0);
+    }
 
     println!(
         "{}",
-        count_reachable_nodes(&connections, &counts[0].0)
-            * count_reachable_nodes(&connections, &counts[1].0)
+        count_reachable_nodes(&connections, counts[0].0)
+            * count_reachable_nodes(&connections, counts[1].0)
     );
 }
 
-fn get_connections(input: Vec<String>) -> HashMap<String, HashSet<String>> {
-    let mut connections = HashMap::new();
+// Undirected graph storing each node's neighbours at the id its name was interned as
+struct Connections {
+    neighbours: Vec<Vec<u32>>,
+}
+
+impl Connections {
+    fn len(&self) -> usize {
+        self.neighbours.len()
+    }
+
+    fn get(&self, node: u32) -> &[u32] {
+        &self.neighbours[node as usize]
+    }
+}
+
+fn get_connections(input: Vec<String>, names: &mut Interner) -> Connections {
+    let mut neighbours: Vec<Vec<u32>> = vec![];
 
     for line in input.iter() {
         let (node, connected) = line.split_once(": ").unwrap();
-        let node = node.to_string();
-        let connected_nodes: Vec<String> = connected.split(" ").map(|s| s.to_string()).collect();
+        let node = names.intern(node);
 
-        if !connections.contains_key(&node) {
-            connections.insert(node.clone(), HashSet::new());
-        }
+        for connected_node in connected.split(" ") {
+            let connected_node = names.intern(connected_node);
+            neighbours.resize(names.len(), vec![]);
 
-        for connected_node in connected_nodes {
-            connections
-                .get_mut(&node)
-                .unwrap()
-                .insert(connected_node.clone());
-            if !connections.contains_key(&connected_node) {
-                connections.insert(connected_node.clone(), HashSet::new());
+            if !neighbours[node as usize].contains(&connected_node) {
+                neighbours[node as usize].push(connected_node);
+                neighbours[connected_node as usize].push(node);
             }
-            connections
-                .get_mut(&connected_node)
-                .unwrap()
-                .insert(node.clone());
         }
     }
+    neighbours.resize(names.len(), vec![]);
 
-    connections
+    Connections { neighbours }
 }
 
-fn get_path(connections: &HashMap<String, HashSet<String>>, a: &String, b: &String) -> Vec<String> {
+// Breadth first search recording the node each one was first reached from, then walking back from b
+fn get_path(connections: &Connections, a: u32, b: u32) -> Vec<u32> {
+    let mut previous = vec![None; connections.len()];
+    previous[a as usize] = Some(a);
     let mut queue = VecDeque::new();
-    queue.push_back((a, vec![a.to_string()]));
+    queue.push_back(a);
 
-    while let Some((current, path_traveled)) = queue.pop_front() {
+    while let Some(current) = queue.pop_front() {
         if current == b {
-            return path_traveled;
+            let mut path = vec![b];
+            let mut node = b;
+            while node != a {
+                node = previous[node as usize].unwrap();
+                path.push(node);
+            }
+            path.reverse();
+            return path;
         }
 
-        for connected_node in connections.get(current).unwrap() {
-            if !path_traveled.contains(connected_node) {
-                let mut visited = path_traveled.clone();
-                visited.push(connected_node.clone());
-                queue.push_back((connected_node, visited));
+        for &connected_node in connections.get(current) {
+            if previous[connected_node as usize].is_none() {
+                previous[connected_node as usize] = Some(current);
+                queue.push_back(connected_node);
             }
         }
     }
@@ -93,27 +113,106 @@ fn get_path(connections: &HashMap<String, HashSet<String>>, a: &String, b: &Stri
     vec![]
 }
 
-fn disconnect(connnections: &mut HashMap<String, HashSet<String>>, a: &String, b: &String) {
-    connnections.get_mut(a).unwrap().remove(b);
-    connnections.get_mut(b).unwrap().remove(a);
+fn disconnect(connections: &mut Connections, a: u32, b: u32) {
+    connections.neighbours[a as usize].retain(|&node| node != b);
+    connections.neighbours[b as usize].retain(|&node| node != a);
 }
 
-fn count_reachable_nodes(connections: &HashMap<String, HashSet<String>>, node: &String) -> usize {
-    let mut visited = HashSet::new();
-    visited.insert(node.clone());
+fn count_reachable_nodes(connections: &Connections, node: u32) -> usize {
+    let mut visited = vec![false; connections.len()];
+    visited[node as usize] = true;
     find_reachable_nodes(connections, node, &mut visited);
-    visited.len()
+    visited.iter().filter(|&&visited| visited).count()
 }
 
-fn find_reachable_nodes(
-    connections: &HashMap<String, HashSet<String>>,
-    node: &String,
-    visited: &mut HashSet<String>,
-) {
-    for reachable in connections.get(node).unwrap() {
-        if !visited.contains(reachable) {
-            visited.insert(reachable.clone());
+fn find_reachable_nodes(connections: &Connections, node: u32, visited: &mut [bool]) {
+    for &reachable in connections.get(node) {
+        if !visited[reachable as usize] {
+            visited[reachable as usize] = true;
             find_reachable_nodes(connections, reachable, visited);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_connections() -> (Connections, Interner) {
+        let mut names = Interner::new();
+        let connections = get_connections(
+            [
+                "jqt: rhn xhk nvd",
+                "rsh: frs pzl lsr",
+                "xhk: hfx",
+                "cmg: qnr nvd lhk bvb",
+                "rhn: xhk bvb hfx",
+                "bvb: xhk hfx",
+                "pzl: lsr hfx nvd",
+                "qnr: nvd",
+                "ntq: jqt hfx bvb xhk",
+                "nvd: lhk",
+                "lsr: lhk",
+                "rzs: qnr cmg lsr rsh",
+                "frs: qnr lhk lsr",
+            ]
+            .iter()
+            .map(|line| String::from(*line))
+            .collect(),
+            &mut names,
+        );
+        (connections, names)
+    }
+
+    #[test]
+    fn test_get_connections() {
+        let (connections, names) = get_test_connections();
+        let jqt = names.get("jqt").unwrap();
+        let mut neighbours: Vec<&str> = connections
+            .get(jqt)
+            .iter()
+            .map(|&node| names.name(node))
+            .collect();
+        neighbours.sort();
+
+        assert_eq!(connections.len(), 15);
+        assert_eq!(neighbours, vec!["ntq", "nvd", "rhn", "xhk"]);
+    }
+
+    #[test]
+    fn test_get_path() {
+        let (connections, names) = get_test_connections();
+        let id = |name| names.get(name).unwrap();
+
+        assert_eq!(
+            get_path(&connections, id("jqt"), id("xhk")),
+            vec![id("jqt"), id("xhk")]
+        );
+        assert_eq!(
+            get_path(&connections, id("hfx"), id("hfx")),
+            vec![id("hfx")]
+        );
+        assert_eq!(get_path(&connections, id("qnr"), id("jqt")).len(), 3);
+    }
+
+    #[test]
+    fn test_count_reachable_nodes() {
+        let (mut connections, names) = get_test_connections();
+        let id = |name| names.get(name).unwrap();
+        let (hfx, pzl, bvb, cmg, nvd, jqt) = (
+            id("hfx"),
+            id("pzl"),
+            id("bvb"),
+            id("cmg"),
+            id("nvd"),
+            id("jqt"),
+        );
+
+        assert_eq!(count_reachable_nodes(&connections, hfx), 15);
+        disconnect(&mut connections, hfx, pzl);
+        disconnect(&mut connections, bvb, cmg);
+        disconnect(&mut connections, nvd, jqt);
+        assert_eq!(count_reachable_nodes(&connections, hfx), 6);
+        assert_eq!(count_reachable_nodes(&connections, pzl), 9);
+    }
+}
@@ -0,0 +1,339 @@
+use std::fmt;
+
+use crate::grid::Grid;
+use crate::num::gcd;
+use crate::Directions;
+
+// Lattice point, with rows growing downwards like they do in puzzle input
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Point {
+    pub row: i64,
+    pub column: i64,
+}
+
+impl Point {
+    pub fn new(row: i64, column: i64) -> Self {
+        Self { row, column }
+    }
+
+    pub fn step(&self, direction: Directions, distance: i64) -> Self {
+        match direction {
+            Directions::UP => Self::new(self.row - distance, self.column),
+            Directions::DOWN => Self::new(self.row + distance, self.column),
+            Directions::LEFT => Self::new(self.row, self.column - distance),
+            Directions::RIGHT => Self::new(self.row, self.column + distance),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Location {
+    Outside,
+    Boundary,
+    Inside,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let character = match self {
+            Self::Outside => '.',
+            Self::Boundary => '#',
+            Self::Inside => 'I',
+        };
+        write!(f, "{character}")
+    }
+}
+
+// Simple polygon with lattice point vertices, closed implicitly from the last vertex back to the first
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Polygon {
+    vertices: Vec<Point>,
+}
+
+impl Polygon {
+    pub fn from_vertices(vertices: Vec<Point>) -> Self {
+        Self { vertices }
+    }
+
+    // Builds the polygon traced by walking each (direction, distance) step in turn from `start`
+    pub fn from_steps(start: Point, steps: &[(Directions, i64)]) -> Self {
+        let mut vertices = vec![start];
+        let mut current = start;
+        for &(direction, distance) in steps {
+            current = current.step(direction, distance);
+            vertices.push(current);
+        }
+
+        if vertices.len() > 1 && vertices.last() == vertices.first() {
+            vertices.pop();
+        }
+
+        Self { vertices }
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().cycle().skip(1))
+            .map(|(a, b)| (*a, *b))
+    }
+
+    // Shoelace formula, doubled so that it stays an integer for any lattice polygon
+    pub fn twice_area(&self) -> i128 {
+        self.edges()
+            .map(|(a, b)| a.column as i128 * b.row as i128 - a.row as i128 * b.column as i128)
+            .sum::<i128>()
+            .abs()
+    }
+
+    pub fn area(&self) -> i128 {
+        self.twice_area() / 2
+    }
+
+    // Number of lattice points on the boundary, which is the perimeter for a rectilinear polygon
+    pub fn boundary_length(&self) -> i128 {
+        self.edges().map(|(a, b)| edge_steps(a, b)).sum()
+    }
+
+    // Pick's theorem, A = I + B / 2 - 1, solved for the number of strictly interior lattice points
+    pub fn interior_points(&self) -> i128 {
+        (self.twice_area() - self.boundary_length() + 2) / 2
+    }
+
+    pub fn lattice_points(&self) -> i128 {
+        self.interior_points() + self.boundary_length()
+    }
+
+    pub fn bounding_box(&self) -> (Point, Point) {
+        let min_row = self.vertices.iter().map(|p| p.row).min().unwrap_or(0);
+        let max_row = self.vertices.iter().map(|p| p.row).max().unwrap_or(0);
+        let min_column = self.vertices.iter().map(|p| p.column).min().unwrap_or(0);
+        let max_column = self.vertices.iter().map(|p| p.column).max().unwrap_or(0);
+
+        (
+            Point::new(min_row, min_column),
+            Point::new(max_row, max_column),
+        )
+    }
+
+    pub fn locate(&self, point: Point) -> Location {
+        if self.edges().any(|(a, b)| is_on_segment(point, a, b)) {
+            return Location::Boundary;
+        }
+
+        let crossings = self
+            .edges()
+            .filter_map(|(a, b)| crossing(point.row, a, b))
+            .filter(|&(numerator, denominator)| (point.column as i128) * denominator < numerator)
+            .count();
+
+        if crossings % 2 == 1 {
+            Location::Inside
+        } else {
+            Location::Outside
+        }
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.locate(point) != Location::Outside
+    }
+
+    /* Draws the polygon onto a grid covering its bounding box, so grid position (0, 0) is the bounding box's top left
+     * corner. Interior cells are found one row at a time from the edge crossings rather than by testing every cell
+     * against every edge.
+     */
+    pub fn rasterize(&self) -> Grid<Location> {
+        let (min, max) = self.bounding_box();
+        let height = (max.row - min.row + 1) as usize;
+        let width = (max.column - min.column + 1) as usize;
+        let mut grid = Grid::new(width, height, Location::Outside);
+
+        for row in min.row..=max.row {
+            let mut crossings: Vec<(i128, i128)> = self
+                .edges()
+                .filter_map(|(a, b)| crossing(row, a, b))
+                .collect();
+            crossings.sort_by(|x, y| (x.0 * y.1).cmp(&(y.0 * x.1)));
+
+            let mut remaining = crossings.len();
+            for column in min.column..=max.column {
+                while remaining > 0 {
+                    let (numerator, denominator) = crossings[crossings.len() - remaining];
+                    if (column as i128) * denominator < numerator {
+                        break;
+                    }
+                    remaining -= 1;
+                }
+                if remaining % 2 == 1 {
+                    grid.set(
+                        (row - min.row) as usize,
+                        (column - min.column) as usize,
+                        Location::Inside,
+                    );
+                }
+            }
+        }
+
+        for (a, b) in self.edges() {
+            let row_change = b.row as i128 - a.row as i128;
+            let column_change = b.column as i128 - a.column as i128;
+            let steps = edge_steps(a, b).max(1);
+            for i in 0..=steps {
+                grid.set(
+                    (a.row as i128 + row_change / steps * i - min.row as i128) as usize,
+                    (a.column as i128 + column_change / steps * i - min.column as i128) as usize,
+                    Location::Boundary,
+                );
+            }
+        }
+
+        grid
+    }
+}
+
+/* Number of lattice steps from a to b along the edge between them. The differences are taken as i128, where even the
+* widest gap between two i64 coordinates fits, so the gcd can not overflow.
+*/
+fn edge_steps(a: Point, b: Point) -> i128 {
+    gcd(
+        &(b.row as i128 - a.row as i128),
+        &(b.column as i128 - a.column as i128),
+    )
+    .expect("The gcd of two i64 differences fits in an i128")
+}
+
+fn is_on_segment(point: Point, a: Point, b: Point) -> bool {
+    let cross = (b.column - a.column) as i128 * (point.row - a.row) as i128
+        - (b.row - a.row) as i128 * (point.column - a.column) as i128;
+
+    cross == 0
+        && point.row >= a.row.min(b.row)
+        && point.row <= a.row.max(b.row)
+        && point.column >= a.column.min(b.column)
+        && point.column <= a.column.max(b.column)
+}
+
+/* Column at which the edge crosses the horizontal line through `row`, as a fraction with a positive denominator. Edges
+* count as covering their lower end but not their upper one, so a vertex on the line is only counted once.
+*/
+fn crossing(row: i64, a: Point, b: Point) -> Option<(i128, i128)> {
+    if (a.row > row) == (b.row > row) {
+        return None;
+    }
+
+    let (a, b) = if a.row < b.row { (a, b) } else { (b, a) };
+    let denominator = (b.row - a.row) as i128;
+    let numerator =
+        a.column as i128 * denominator + (row - a.row) as i128 * (b.column - a.column) as i128;
+
+    Some((numerator, denominator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_lagoon() -> Polygon {
+        let steps = [
+            (Directions::RIGHT, 6),
+            (Directions::DOWN, 5),
+            (Directions::LEFT, 2),
+            (Directions::DOWN, 2),
+            (Directions::RIGHT, 2),
+            (Directions::DOWN, 2),
+            (Directions::LEFT, 5),
+            (Directions::UP, 2),
+            (Directions::LEFT, 1),
+            (Directions::UP, 2),
+            (Directions::RIGHT, 2),
+            (Directions::UP, 3),
+            (Directions::LEFT, 2),
+            (Directions::UP, 2),
+        ];
+        Polygon::from_steps(Point::new(0, 0), &steps)
+    }
+
+    #[test]
+    fn test_from_steps() {
+        let polygon = get_lagoon();
+
+        assert_eq!(polygon.vertices().len(), 14);
+        assert_eq!(polygon.vertices()[1], Point::new(0, 6));
+        assert_eq!(polygon.bounding_box(), (Point::new(0, 0), Point::new(9, 6)));
+    }
+
+    #[test]
+    fn test_area_and_points() {
+        let polygon = get_lagoon();
+
+        assert_eq!(polygon.area(), 42);
+        assert_eq!(polygon.boundary_length(), 38);
+        assert_eq!(polygon.interior_points(), 24);
+        assert_eq!(polygon.lattice_points(), 62);
+    }
+
+    #[test]
+    fn test_triangle_points() {
+        let triangle =
+            Polygon::from_vertices(vec![Point::new(0, 0), Point::new(0, 4), Point::new(3, 0)]);
+
+        assert_eq!(triangle.twice_area(), 12);
+        assert_eq!(triangle.boundary_length(), 8);
+        assert_eq!(triangle.interior_points(), 3);
+    }
+
+    #[test]
+    fn test_extreme_coordinates() {
+        let line = Polygon::from_vertices(vec![Point::new(i64::MIN, 0), Point::new(i64::MAX, 0)]);
+        assert_eq!(line.boundary_length(), 2 * (u64::MAX as i128));
+
+        let corner = Polygon::from_vertices(vec![
+            Point::new(i64::MAX - 1, i64::MIN),
+            Point::new(i64::MAX, i64::MIN),
+            Point::new(i64::MAX, i64::MIN + 1),
+        ]);
+        assert_eq!(corner.boundary_length(), 3);
+        assert_eq!(corner.rasterize().to_string(), "#.\n##\n");
+    }
+
+    #[test]
+    fn test_locate() {
+        let polygon = get_lagoon();
+
+        assert_eq!(polygon.locate(Point::new(0, 3)), Location::Boundary);
+        assert_eq!(polygon.locate(Point::new(1, 1)), Location::Inside);
+        assert_eq!(polygon.locate(Point::new(6, 5)), Location::Outside);
+        assert_eq!(polygon.locate(Point::new(5, 3)), Location::Inside);
+        assert_eq!(polygon.locate(Point::new(3, -1)), Location::Outside);
+        assert!(polygon.contains(Point::new(7, 2)));
+    }
+
+    #[test]
+    fn test_rasterize() {
+        let grid = get_lagoon().rasterize();
+        let expected = "\
+#######
+#IIIII#
+###III#
+..#III#
+..#III#
+###I###
+#III#..
+##II###
+.#IIII#
+.######
+";
+
+        assert_eq!(grid.to_string(), expected);
+        let filled = grid
+            .rows()
+            .flatten()
+            .filter(|&&cell| cell != Location::Outside)
+            .count();
+        assert_eq!(filled, 62);
+    }
+}
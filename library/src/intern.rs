@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+/* Symbol table handing out small sequential ids for names, so that graphs keyed on strings can be stored in plain
+* vectors indexed by id and copied around without cloning strings.
+*/
+#[derive(Default, Clone, Debug)]
+pub struct Interner {
+    ids: HashMap<String, u32>,
+    names: Vec<String>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Returns the id for `name`, assigning the next unused one the first time it is seen
+    pub fn intern(&mut self, name: &str) -> u32 {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+
+        let id = u32::try_from(self.names.len()).expect("Too many names to intern");
+        self.ids.insert(String::from(name), id);
+        self.names.push(String::from(name));
+        id
+    }
+
+    pub fn get(&self, name: &str) -> Option<u32> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: u32) -> &str {
+        &self.names[id as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (id as u32, name.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern() {
+        let mut interner = Interner::new();
+
+        assert_eq!(interner.intern("AAA"), 0);
+        assert_eq!(interner.intern("BBB"), 1);
+        assert_eq!(interner.intern("AAA"), 0);
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.get("BBB"), Some(1));
+        assert_eq!(interner.get("CCC"), None);
+        assert_eq!(interner.name(1), "BBB");
+        assert_eq!(
+            interner.iter().collect::<Vec<_>>(),
+            vec![(0, "AAA"), (1, "BBB")]
+        );
+    }
+}
@@ -1,8 +1,14 @@
 use std::fmt;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::str::FromStr;
 use std::{env, process::exit};
 
+pub mod geometry;
+pub mod grid;
+pub mod intern;
+pub mod num;
+
 // From this StackOverflow answer https://stackoverflow.com/a/35820003
 pub fn get_lines(file_path: &str) -> Vec<String> {
     let file = File::open(file_path).expect("Failed to open file");
@@ -61,6 +67,77 @@ pub fn get_filename_arg() -> String {
     args[1].clone()
 }
 
+// Like get_filename_arg, but any of the `known` options can follow the filename
+pub fn get_filename_and_options(known: &[&str]) -> (String, Options) {
+    let args: Vec<String> = env::args().collect();
+    let options = args.get(2..).map(|args| Options::parse(args, known));
+    match (args.get(1), options) {
+        (Some(file_name), Some(Ok(options))) => (file_name.clone(), options),
+        (_, Some(Err(error))) => {
+            println!("{}", error);
+            exit(1);
+        }
+        _ => {
+            let known: Vec<String> = known.iter().map(|name| format!("[--{}]", name)).collect();
+            println!(
+                "Requires a filename as argument, followed by {}",
+                known.join(" ")
+            );
+            exit(1);
+        }
+    }
+}
+
+/* Options given after the filename, each either a flag like "--render" or a value like "--index=5". Only known names
+* are accepted, so a misspelt option is reported instead of being silently ignored.
+*/
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct Options {
+    values: Vec<(String, Option<String>)>,
+}
+
+impl Options {
+    pub fn parse(args: &[String], known: &[&str]) -> Result<Self, String> {
+        let mut values = vec![];
+        for arg in args {
+            let option = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("Unexpected argument {}", arg))?;
+            let (name, value) = match option.split_once('=') {
+                Some((name, value)) => (name, Some(String::from(value))),
+                None => (option, None),
+            };
+            if !known.contains(&name) {
+                return Err(format!("Unknown option --{}", name));
+            }
+            values.push((String::from(name), value));
+        }
+
+        Ok(Self { values })
+    }
+
+    pub fn is_set(&self, name: &str) -> bool {
+        self.values.iter().any(|(option, _)| option == name)
+    }
+
+    // The value given to the option the last time it appears
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .rev()
+            .find(|(option, _)| option == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    pub fn parsed_value<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.value(name).map(|value| {
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid value {} for --{}", value, name))
+        })
+    }
+}
+
 #[derive(PartialEq, Clone, Copy)]
 pub enum Directions {
     UP,
@@ -115,4 +192,31 @@ mod tests {
 
         assert_eq!(two_dimension_vec, expected);
     }
+
+    #[test]
+    fn test_options() {
+        let args =
+            |args: &[&str]| -> Vec<String> { args.iter().map(|arg| arg.to_string()).collect() };
+        let options = Options::parse(
+            &args(&["--render", "--index=5", "--index=7"]),
+            &["render", "index", "trace"],
+        )
+        .unwrap();
+
+        assert!(options.is_set("render"));
+        assert!(!options.is_set("trace"));
+        assert_eq!(options.value("render"), None);
+        assert_eq!(options.value("index"), Some("7"));
+        assert_eq!(options.parsed_value::<u32>("index"), Some(7));
+        assert_eq!(options.parsed_value::<u32>("trace"), None);
+        assert_eq!(Options::parse(&[], &["render"]), Ok(Options::default()));
+        assert_eq!(
+            Options::parse(&args(&["--rendr"]), &["render"]),
+            Err(String::from("Unknown option --rendr"))
+        );
+        assert_eq!(
+            Options::parse(&args(&["render"]), &["render"]),
+            Err(String::from("Unexpected argument render"))
+        );
+    }
 }
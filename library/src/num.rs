@@ -0,0 +1,612 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+#[cfg(feature = "bigint")]
+mod bigint;
+#[cfg(feature = "bigint")]
+pub use bigint::BigInt;
+
+// The handful of operations Rational needs from its backing integer, with overflow surfaced as None
+pub trait Integer: Clone + Ord + fmt::Debug + fmt::Display {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    // Truncates towards zero like the primitive integer types
+    fn checked_div(&self, other: &Self) -> Option<Self>;
+    fn checked_rem(&self, other: &Self) -> Option<Self>;
+    fn checked_neg(&self) -> Option<Self>;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::zero()
+    }
+
+    fn is_negative(&self) -> bool {
+        *self < Self::zero()
+    }
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Integer for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn one() -> Self {
+                    1
+                }
+
+                fn checked_add(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_add(*self, *other)
+                }
+
+                fn checked_sub(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_sub(*self, *other)
+                }
+
+                fn checked_mul(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_mul(*self, *other)
+                }
+
+                fn checked_div(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_div(*self, *other)
+                }
+
+                fn checked_rem(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_rem(*self, *other)
+                }
+
+                fn checked_neg(&self) -> Option<Self> {
+                    <$t>::checked_neg(*self)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i32, i64, i128);
+
+pub fn gcd<T: Integer>(a: &T, b: &T) -> Option<T> {
+    let mut a = abs(a)?;
+    let mut b = abs(b)?;
+    while !b.is_zero() {
+        let remainder = a.checked_rem(&b)?;
+        a = b;
+        b = remainder;
+    }
+    Some(a)
+}
+
+pub fn abs<T: Integer>(value: &T) -> Option<T> {
+    if value.is_negative() {
+        value.checked_neg()
+    } else {
+        Some(value.clone())
+    }
+}
+
+// Returns (g, x, y) with a * x + b * y = g, where g is the non-negative gcd of a and b
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return if a < 0 { (-a, -1, 0) } else { (a, 1, 0) };
+    }
+
+    let (g, x, y) = extended_gcd(b, a % b);
+    (g, y, x - (a / b) * y)
+}
+
+/* Chinese remainder theorem for congruences `x = remainder (mod modulus)` whose moduli need not be coprime. Returns
+* the combined congruence as (remainder, modulus) with 0 <= remainder < modulus, or None if the congruences contradict
+* each other or the combined modulus overflows.
+*/
+pub fn crt(congruences: &[(i128, i128)]) -> Option<(i128, i128)> {
+    let mut combined = (0, 1);
+    for &(remainder, modulus) in congruences {
+        if modulus <= 0 {
+            return None;
+        }
+
+        let (current, current_modulus) = combined;
+        let (g, x, _) = extended_gcd(current_modulus, modulus);
+        // Reducing the remainder first keeps both sides non-negative, so even remainders near the limits fit
+        let difference = remainder.rem_euclid(modulus).checked_sub(current)?;
+        if difference % g != 0 {
+            return None;
+        }
+
+        let step = modulus / g;
+        let k = (difference / g % step)
+            .checked_mul(x % step)?
+            .rem_euclid(step);
+        let lcm = (current_modulus / g).checked_mul(modulus)?;
+        let value = current_modulus.checked_mul(k)?.checked_add(current)?;
+        combined = (value.rem_euclid(lcm), lcm);
+    }
+
+    Some(combined)
+}
+
+// The largest r with r * r <= n. Newton's method from a starting point above the root, so it never overflows
+pub fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+
+    let mut root = 1 << (u128::BITS - n.leading_zeros()).div_ceil(2);
+    loop {
+        let next = (root + n / root) / 2;
+        if next >= root {
+            return root;
+        }
+        root = next;
+    }
+}
+
+// Division rounding towards negative infinity, for a positive divisor
+fn floor_div<T: Integer>(a: &T, b: &T) -> Option<T> {
+    let quotient = a.checked_div(b)?;
+    if a.checked_rem(b)?.is_negative() {
+        quotient.checked_sub(&T::one())
+    } else {
+        Some(quotient)
+    }
+}
+
+/* An exact fraction, always stored in lowest terms with a positive denominator so that equal values have equal
+* representations. The checked_* methods return None on overflow or division by zero, and the operator impls panic in
+* those cases just like the primitive integer types do in debug builds.
+*/
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Rational<T: Integer> {
+    numerator: T,
+    denominator: T,
+}
+
+impl<T: Integer> Rational<T> {
+    pub fn new(numerator: T, denominator: T) -> Option<Self> {
+        if denominator.is_zero() {
+            return None;
+        }
+
+        let divisor = gcd(&numerator, &denominator)?;
+        let mut numerator = numerator.checked_div(&divisor)?;
+        let mut denominator = denominator.checked_div(&divisor)?;
+        if denominator.is_negative() {
+            numerator = numerator.checked_neg()?;
+            denominator = denominator.checked_neg()?;
+        }
+
+        Some(Self {
+            numerator,
+            denominator,
+        })
+    }
+
+    pub fn from_integer(value: T) -> Self {
+        Self {
+            numerator: value,
+            denominator: T::one(),
+        }
+    }
+
+    pub fn zero() -> Self {
+        Self::from_integer(T::zero())
+    }
+
+    pub fn one() -> Self {
+        Self::from_integer(T::one())
+    }
+
+    pub fn numerator(&self) -> &T {
+        &self.numerator
+    }
+
+    pub fn denominator(&self) -> &T {
+        &self.denominator
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.numerator.is_negative()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denominator == T::one()
+    }
+
+    pub fn to_integer(&self) -> Option<T> {
+        if self.is_integer() {
+            Some(self.numerator.clone())
+        } else {
+            None
+        }
+    }
+
+    pub fn floor(&self) -> Option<T> {
+        floor_div(&self.numerator, &self.denominator)
+    }
+
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        // Working over the lcm of the denominators keeps the intermediate values as small as possible
+        let divisor = gcd(&self.denominator, &other.denominator)?;
+        let self_scale = other.denominator.checked_div(&divisor)?;
+        let other_scale = self.denominator.checked_div(&divisor)?;
+        let numerator = self
+            .numerator
+            .checked_mul(&self_scale)?
+            .checked_add(&other.numerator.checked_mul(&other_scale)?)?;
+        let denominator = self.denominator.checked_mul(&self_scale)?;
+
+        Self::new(numerator, denominator)
+    }
+
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        self.checked_add(&other.checked_neg()?)
+    }
+
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        // Cross cancelling first means the products are already in lowest terms
+        let first = gcd(&self.numerator, &other.denominator)?;
+        let second = gcd(&other.numerator, &self.denominator)?;
+
+        let numerator = self
+            .numerator
+            .checked_div(&first)?
+            .checked_mul(&other.numerator.checked_div(&second)?)?;
+        let denominator = self
+            .denominator
+            .checked_div(&second)?
+            .checked_mul(&other.denominator.checked_div(&first)?)?;
+
+        Some(Self {
+            numerator,
+            denominator,
+        })
+    }
+
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        self.checked_mul(&other.checked_recip()?)
+    }
+
+    pub fn checked_neg(&self) -> Option<Self> {
+        Some(Self {
+            numerator: self.numerator.checked_neg()?,
+            denominator: self.denominator.clone(),
+        })
+    }
+
+    pub fn checked_recip(&self) -> Option<Self> {
+        Self::new(self.denominator.clone(), self.numerator.clone())
+    }
+}
+
+impl<T: Integer> From<T> for Rational<T> {
+    fn from(value: T) -> Self {
+        Self::from_integer(value)
+    }
+}
+
+impl<T: Integer> Ord for Rational<T> {
+    // Compares continued fraction expansions so that no cross multiplication, and so no overflow, is needed
+    fn cmp(&self, other: &Self) -> Ordering {
+        let mut a = (self.numerator.clone(), self.denominator.clone());
+        let mut b = (other.numerator.clone(), other.denominator.clone());
+        let mut reversed = false;
+
+        loop {
+            let a_floor = floor_div(&a.0, &a.1).expect("Rational comparison overflowed");
+            let b_floor = floor_div(&b.0, &b.1).expect("Rational comparison overflowed");
+            let ordering = a_floor.cmp(&b_floor);
+            if ordering != Ordering::Equal {
+                return if reversed {
+                    ordering.reverse()
+                } else {
+                    ordering
+                };
+            }
+
+            let a_remainder = remainder(&a, &a_floor).expect("Rational comparison overflowed");
+            let b_remainder = remainder(&b, &b_floor).expect("Rational comparison overflowed");
+            let ordering = match (a_remainder.is_zero(), b_remainder.is_zero()) {
+                (true, true) => return Ordering::Equal,
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (false, false) => {
+                    a = (a.1, a_remainder);
+                    b = (b.1, b_remainder);
+                    reversed = !reversed;
+                    continue;
+                }
+            };
+            return if reversed {
+                ordering.reverse()
+            } else {
+                ordering
+            };
+        }
+    }
+}
+
+fn remainder<T: Integer>(fraction: &(T, T), floor: &T) -> Option<T> {
+    fraction.0.checked_sub(&floor.checked_mul(&fraction.1)?)
+}
+
+impl<T: Integer> PartialOrd for Rational<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Integer> fmt::Display for Rational<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+impl<T: Integer> Add for Rational<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.checked_add(&other)
+            .expect("Rational addition overflowed")
+    }
+}
+
+impl<T: Integer> Sub for Rational<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.checked_sub(&other)
+            .expect("Rational subtraction overflowed")
+    }
+}
+
+impl<T: Integer> Mul for Rational<T> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        self.checked_mul(&other)
+            .expect("Rational multiplication overflowed")
+    }
+}
+
+impl<T: Integer> Div for Rational<T> {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        self.checked_div(&other)
+            .expect("Rational division overflowed or divided by zero")
+    }
+}
+
+impl<T: Integer> Neg for Rational<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.checked_neg().expect("Rational negation overflowed")
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum SolveError {
+    Singular,
+    Overflow,
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Singular => write!(f, "System has no unique solution"),
+            Self::Overflow => write!(f, "Arithmetic overflowed while solving system"),
+        }
+    }
+}
+
+/* Solves the square system `coefficients * x = constants` by Gaussian elimination with exact fractions. Each row of
+* `coefficients` is one equation.
+*/
+pub fn solve_linear_system<T: Integer>(
+    coefficients: &[Vec<Rational<T>>],
+    constants: &[Rational<T>],
+) -> Result<Vec<Rational<T>>, SolveError> {
+    let size = constants.len();
+    if coefficients.len() != size || coefficients.iter().any(|row| row.len() != size) {
+        return Err(SolveError::Singular);
+    }
+
+    let mut rows: Vec<Vec<Rational<T>>> = coefficients
+        .iter()
+        .zip(constants)
+        .map(|(row, constant)| {
+            let mut row = row.clone();
+            row.push(constant.clone());
+            row
+        })
+        .collect();
+
+    for column in 0..size {
+        let pivot = (column..size)
+            .find(|&row| !rows[row][column].is_zero())
+            .ok_or(SolveError::Singular)?;
+        rows.swap(column, pivot);
+
+        let pivot_value = rows[column][column].clone();
+        for value in rows[column].iter_mut() {
+            *value = value
+                .checked_div(&pivot_value)
+                .ok_or(SolveError::Overflow)?;
+        }
+
+        let pivot_row = rows[column].clone();
+        for (index, row) in rows.iter_mut().enumerate() {
+            if index == column || row[column].is_zero() {
+                continue;
+            }
+
+            let factor = row[column].clone();
+            for (value, pivot_value) in row.iter_mut().zip(&pivot_row).skip(column) {
+                let reduction = pivot_value
+                    .checked_mul(&factor)
+                    .ok_or(SolveError::Overflow)?;
+                *value = value.checked_sub(&reduction).ok_or(SolveError::Overflow)?;
+            }
+        }
+    }
+
+    Ok(rows.into_iter().map(|mut row| row.pop().unwrap()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rational(numerator: i128, denominator: i128) -> Rational<i128> {
+        Rational::new(numerator, denominator).unwrap()
+    }
+
+    #[test]
+    fn test_new_normalizes() {
+        let value = rational(6, -8);
+
+        assert_eq!(*value.numerator(), -3);
+        assert_eq!(*value.denominator(), 4);
+        assert_eq!(value, rational(-9, 12));
+        assert_eq!(Rational::new(1, 0), None);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(rational(1, 2) + rational(1, 3), rational(5, 6));
+        assert_eq!(rational(1, 2) - rational(3, 4), rational(-1, 4));
+        assert_eq!(rational(2, 3) * rational(9, 4), rational(3, 2));
+        assert_eq!(rational(2, 3) / rational(-4, 9), rational(-3, 2));
+        assert_eq!(-rational(2, 3), rational(-2, 3));
+        assert_eq!(rational(0, 5) * rational(7, 3), Rational::zero());
+    }
+
+    #[test]
+    fn test_checked_arithmetic_overflow() {
+        let large = Rational::from_integer(i128::MAX);
+
+        assert_eq!(large.checked_add(&Rational::one()), None);
+        assert_eq!(large.checked_mul(&rational(2, 1)), None);
+        assert_eq!(rational(1, 1).checked_div(&Rational::zero()), None);
+        assert_eq!(
+            large.checked_mul(&rational(1, i128::MAX)),
+            Some(Rational::one())
+        );
+    }
+
+    #[test]
+    fn test_floor_and_to_integer() {
+        assert_eq!(rational(7, 2).floor(), Some(3));
+        assert_eq!(rational(-7, 2).floor(), Some(-4));
+        assert_eq!(rational(8, 2).to_integer(), Some(4));
+        assert_eq!(rational(7, 2).to_integer(), None);
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(rational(1, 3) < rational(1, 2));
+        assert!(rational(-1, 2) < rational(-1, 3));
+        assert!(rational(355, 113) < rational(22, 7));
+        assert!(rational(i128::MAX - 2, i128::MAX - 1) < rational(i128::MAX - 1, i128::MAX));
+        assert_eq!(rational(4, 6).cmp(&rational(2, 3)), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(rational(4, 2).to_string(), "2");
+        assert_eq!(rational(-3, 9).to_string(), "-1/3");
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        assert_eq!(extended_gcd(240, 46), (2, -9, 47));
+        assert_eq!(extended_gcd(-4, 6).0, 2);
+    }
+
+    #[test]
+    fn test_isqrt() {
+        for n in 0..10_000u128 {
+            let root = isqrt(n);
+            assert!(root * root <= n && (root + 1) * (root + 1) > n);
+        }
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+        assert_eq!(
+            isqrt((u64::MAX as u128) * (u64::MAX as u128)),
+            u64::MAX as u128
+        );
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt(&[(3, 4), (1, 6)]), Some((7, 12)));
+        assert_eq!(crt(&[(1, 4), (2, 6)]), None);
+        assert_eq!(crt(&[]), Some((0, 1)));
+        assert_eq!(crt(&[(0, i128::MAX), (1, i128::MAX - 1)]), None);
+    }
+
+    #[test]
+    fn test_crt_large_remainders() {
+        assert_eq!(crt(&[(5, 7), (i128::MIN, 11)]), Some((26, 77)));
+        assert_eq!(crt(&[(i128::MIN, 11), (5, 7)]), Some((26, 77)));
+        assert_eq!(crt(&[(i128::MAX, 7), (i128::MIN, 13)]), Some((15, 91)));
+        assert_eq!(crt(&[(i128::MAX, 2), (i128::MIN, 4)]), None);
+    }
+
+    #[test]
+    fn test_solve_linear_system() {
+        let coefficients = vec![
+            vec![rational(2, 1), rational(1, 1), rational(-1, 1)],
+            vec![rational(-3, 1), rational(-1, 1), rational(2, 1)],
+            vec![rational(-2, 1), rational(1, 1), rational(2, 1)],
+        ];
+        let constants = vec![rational(8, 1), rational(-11, 1), rational(-3, 1)];
+
+        assert_eq!(
+            solve_linear_system(&coefficients, &constants),
+            Ok(vec![rational(2, 1), rational(3, 1), rational(-1, 1)])
+        );
+    }
+
+    #[test]
+    fn test_solve_linear_system_fractions() {
+        let coefficients = vec![
+            vec![rational(0, 1), rational(3, 1)],
+            vec![rational(2, 1), rational(1, 1)],
+        ];
+        let constants = vec![rational(1, 1), rational(1, 1)];
+
+        assert_eq!(
+            solve_linear_system(&coefficients, &constants),
+            Ok(vec![rational(1, 3), rational(1, 3)])
+        );
+    }
+
+    #[test]
+    fn test_solve_linear_system_singular() {
+        let coefficients = vec![
+            vec![rational(1, 1), rational(2, 1)],
+            vec![rational(2, 1), rational(4, 1)],
+        ];
+        let constants = vec![rational(1, 1), rational(2, 1)];
+
+        assert_eq!(
+            solve_linear_system(&coefficients, &constants),
+            Err(SolveError::Singular)
+        );
+    }
+}
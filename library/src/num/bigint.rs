@@ -0,0 +1,400 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+use super::Integer;
+
+const BASE: u64 = 1 << 32;
+const DECIMAL_CHUNK: u32 = 1_000_000_000;
+
+/* Arbitrary precision signed integer, stored as a sign and little endian base 2^32 digits. The digits never have
+* trailing zeros and zero is never negative, so the derived equality is value equality.
+*/
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BigInt {
+    negative: bool,
+    digits: Vec<u32>,
+}
+
+impl BigInt {
+    fn from_parts(negative: bool, mut digits: Vec<u32>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        let negative = negative && !digits.is_empty();
+        Self { negative, digits }
+    }
+
+    pub fn to_i128(&self) -> Option<i128> {
+        if self.digits.len() > 4 {
+            return None;
+        }
+
+        let mut magnitude: u128 = 0;
+        for digit in self.digits.iter().rev() {
+            magnitude = (magnitude << 32) | *digit as u128;
+        }
+
+        if self.negative {
+            0i128.checked_sub_unsigned(magnitude)
+        } else {
+            i128::try_from(magnitude).ok()
+        }
+    }
+
+    fn divide(&self, other: &Self) -> Option<(Self, Self)> {
+        if other.digits.is_empty() {
+            return None;
+        }
+
+        let (quotient, remainder) = divide_digits(&self.digits, &other.digits);
+        Some((
+            Self::from_parts(self.negative != other.negative, quotient),
+            Self::from_parts(self.negative, remainder),
+        ))
+    }
+}
+
+fn compare_digits(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_digits(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    result.push(carry as u32);
+    result
+}
+
+// Requires a >= b
+fn subtract_digits(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, &digit) in a.iter().enumerate() {
+        let subtrahend = *b.get(i).unwrap_or(&0) as u64 + borrow;
+        let minuend = digit as u64;
+        if minuend >= subtrahend {
+            result.push((minuend - subtrahend) as u32);
+            borrow = 0;
+        } else {
+            result.push((minuend + BASE - subtrahend) as u32);
+            borrow = 1;
+        }
+    }
+    result
+}
+
+fn multiply_digits(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, &y) in b.iter().enumerate() {
+            let product = x as u64 * y as u64 + result[i + j] as u64 + carry;
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        result[i + b.len()] = carry as u32;
+    }
+    result
+}
+
+fn divide_small(a: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder = 0u64;
+    for i in (0..a.len()).rev() {
+        let current = (remainder << 32) | a[i] as u64;
+        quotient[i] = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+    (quotient, remainder as u32)
+}
+
+// Shift and subtract long division, one bit at a time. Slow for huge numbers but plenty for puzzle sized ones
+fn divide_digits(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if b.len() == 1 {
+        let (quotient, remainder) = divide_small(a, b[0]);
+        return (quotient, vec![remainder]);
+    }
+
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder: Vec<u32> = vec![];
+    for i in (0..a.len() * 32).rev() {
+        remainder = shift_left_one(&remainder, (a[i / 32] >> (i % 32)) & 1);
+        if compare_digits(trim(&remainder), b) != Ordering::Less {
+            remainder = subtract_digits(&remainder, b);
+            quotient[i / 32] |= 1 << (i % 32);
+        }
+    }
+    (quotient, remainder)
+}
+
+fn shift_left_one(digits: &[u32], low_bit: u32) -> Vec<u32> {
+    let mut result = Vec::with_capacity(digits.len() + 1);
+    let mut carry = low_bit;
+    for digit in trim(digits) {
+        result.push((digit << 1) | carry);
+        carry = digit >> 31;
+    }
+    if carry != 0 {
+        result.push(carry);
+    }
+    result
+}
+
+fn trim(digits: &[u32]) -> &[u32] {
+    let length = digits.len() - digits.iter().rev().take_while(|&&d| d == 0).count();
+    &digits[..length]
+}
+
+impl From<i128> for BigInt {
+    fn from(value: i128) -> Self {
+        let mut magnitude = value.unsigned_abs();
+        let mut digits = vec![];
+        while magnitude > 0 {
+            digits.push(magnitude as u32);
+            magnitude >>= 32;
+        }
+        Self::from_parts(value < 0, digits)
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        Self::from(value as i128)
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (negative, number) = match input.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, input),
+        };
+        if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("Invalid integer {}", input));
+        }
+
+        let mut digits = vec![];
+        for character in number.chars() {
+            digits = multiply_digits(trim(&digits), &[10]);
+            digits = add_digits(trim(&digits), &[character.to_digit(10).unwrap()]);
+        }
+        Ok(Self::from_parts(negative, digits))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_digits(&self.digits, &other.digits),
+            (true, true) => compare_digits(&other.digits, &self.digits),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.digits.is_empty() {
+            return write!(f, "0");
+        }
+
+        let mut chunks = vec![];
+        let mut digits = self.digits.clone();
+        while !digits.is_empty() {
+            let (quotient, remainder) = divide_small(&digits, DECIMAL_CHUNK);
+            chunks.push(remainder);
+            digits = trim(&quotient).to_vec();
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", chunks.last().unwrap())?;
+        for chunk in chunks.iter().rev().skip(1) {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl Integer for BigInt {
+    fn zero() -> Self {
+        Self::from_parts(false, vec![])
+    }
+
+    fn one() -> Self {
+        Self::from_parts(false, vec![1])
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        if self.negative == other.negative {
+            return Some(Self::from_parts(
+                self.negative,
+                add_digits(&self.digits, &other.digits),
+            ));
+        }
+
+        Some(match compare_digits(&self.digits, &other.digits) {
+            Ordering::Less => {
+                Self::from_parts(other.negative, subtract_digits(&other.digits, &self.digits))
+            }
+            _ => Self::from_parts(self.negative, subtract_digits(&self.digits, &other.digits)),
+        })
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        self.checked_add(&other.checked_neg()?)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(Self::from_parts(
+            self.negative != other.negative,
+            multiply_digits(&self.digits, &other.digits),
+        ))
+    }
+
+    fn checked_div(&self, other: &Self) -> Option<Self> {
+        Some(self.divide(other)?.0)
+    }
+
+    fn checked_rem(&self, other: &Self) -> Option<Self> {
+        Some(self.divide(other)?.1)
+    }
+
+    fn checked_neg(&self) -> Option<Self> {
+        Some(Self::from_parts(!self.negative, self.digits.clone()))
+    }
+}
+
+impl Add for BigInt {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.checked_add(&other).unwrap()
+    }
+}
+
+impl Sub for BigInt {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.checked_sub(&other).unwrap()
+    }
+}
+
+impl Mul for BigInt {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        self.checked_mul(&other).unwrap()
+    }
+}
+
+impl Neg for BigInt {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.checked_neg().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{solve_linear_system, Rational};
+    use super::*;
+
+    fn big(input: &str) -> BigInt {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(big("0").to_string(), "0");
+        assert_eq!(big("-0").to_string(), "0");
+        assert_eq!(
+            big("-123456789012345678901234567890123456789012").to_string(),
+            "-123456789012345678901234567890123456789012"
+        );
+        assert!("12a".parse::<BigInt>().is_err());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = big("340282366920938463463374607431768211456");
+        let b = big("-18446744073709551617");
+
+        assert_eq!(
+            (a.clone() + b.clone()).to_string(),
+            "340282366920938463444927863358058659839"
+        );
+        assert_eq!(
+            (b.clone() - a.clone()).to_string(),
+            "-340282366920938463481821351505477763073"
+        );
+        assert_eq!(
+            (a.clone() * b.clone()).to_string(),
+            "-6277101735386680764176071790128604879565730051895802724352"
+        );
+        assert_eq!(a.checked_div(&b), Some(big("-18446744073709551615")));
+        assert_eq!(a.checked_rem(&b), Some(big("1")));
+        assert_eq!(a.checked_div(&BigInt::zero()), None);
+    }
+
+    #[test]
+    fn test_matches_i128() {
+        let values: [i128; 6] = [0, 7, -7, 1 << 70, -(1 << 90) + 12345, i128::MAX / 3];
+        for &x in &values {
+            for &y in &values {
+                let (a, b) = (BigInt::from(x), BigInt::from(y));
+                assert_eq!(a.cmp(&b), x.cmp(&y));
+                assert_eq!(
+                    a.checked_add(&b).and_then(|v| v.to_i128()),
+                    x.checked_add(y)
+                );
+                if y != 0 {
+                    assert_eq!(a.checked_div(&b).unwrap().to_i128(), Some(x / y));
+                    assert_eq!(a.checked_rem(&b).unwrap().to_i128(), Some(x % y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_linear_system_beyond_i128() {
+        let value = |input: &str| Rational::from_integer(big(input));
+        let coefficients = vec![
+            vec![value("400000000000000000000"), value("1")],
+            vec![value("1"), value("-300000000000000000000")],
+        ];
+        let constants = vec![
+            value("2000000000000000000005000000000000000000000"),
+            value("-1499999999999999999995000000000000000000000"),
+        ];
+
+        assert_eq!(
+            solve_linear_system(&coefficients, &constants),
+            Ok(vec![
+                value("5000000000000000000000"),
+                value("5000000000000000000000")
+            ])
+        );
+    }
+}